@@ -0,0 +1,62 @@
+//! Reads API Gateway access logs in the default Common Log Format:
+//! `$context.identity.sourceIp $context.identity.caller $context.identity.user [$context.requestTime]
+//! "$context.httpMethod $context.resourcePath $context.protocol" $context.status
+//! $context.responseLength $context.requestId`
+
+use std::sync::OnceLock;
+
+use regex_lite::Regex;
+
+use crate::jq::Json;
+
+/// The names of the fields, in the order they appear in a line.
+const FIELDS: [&str; 10] = [
+    "sourceIp",
+    "caller",
+    "user",
+    "requestTime",
+    "httpMethod",
+    "resourcePath",
+    "protocol",
+    "status",
+    "responseLength",
+    "requestId",
+];
+
+/// The fields printed in columns when no others are picked.
+pub const DEFAULT_COLUMNS: [&str; 7] = [
+    "sourceIp",
+    "httpMethod",
+    "resourcePath",
+    "protocol",
+    "status",
+    "responseLength",
+    "requestId",
+];
+
+fn line_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"^(\S+) (\S+) (\S+) \[([^\]]*)\] "(\S+) (\S+) ([^"]*)" (\S+) (\S+) (\S+)$"#)
+            .expect("the access log pattern is valid")
+    })
+}
+
+/// Turns an access log line into a JSON object with a key per field, or `None` if it is in another format.
+/// The status and the response length are numbers, so that jq filters can compare them.
+pub fn to_json(message: &str) -> Option<String> {
+    let captures = line_regex().captures(message.trim_end())?;
+    let fields = FIELDS
+        .iter()
+        .zip(captures.iter().skip(1))
+        .map(|(name, value)| {
+            let value = value.map_or("", |value| value.as_str());
+            let value = match value.parse() {
+                Ok(number) if matches!(*name, "status" | "responseLength") => Json::Number(number),
+                _ => Json::String(value.to_string()),
+            };
+            (name.to_string(), value)
+        })
+        .collect();
+    Some(Json::Object(fields).to_json())
+}
@@ -0,0 +1,263 @@
+use aws_sdk_cloudwatchlogs::{
+    types::{AccountPolicy, PolicyType, Scope},
+    Client,
+};
+use clap::{Args, Subcommand, ValueEnum};
+
+use crate::confirm::ConfirmOpt;
+use crate::error::Error;
+use crate::jq::Json;
+use crate::outln;
+use crate::output::{self, format_timestamp, OutputFormat, Record};
+use crate::resource_policies::read_policy_document;
+
+/// The kind of an account policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PolicyKind {
+    /// Masks sensitive data in every log group of the account.
+    DataProtection,
+    /// Streams the events of the account's log groups to a destination.
+    SubscriptionFilter,
+}
+
+impl From<PolicyKind> for PolicyType {
+    fn from(kind: PolicyKind) -> Self {
+        match kind {
+            PolicyKind::DataProtection => PolicyType::DataProtectionPolicy,
+            PolicyKind::SubscriptionFilter => PolicyType::SubscriptionFilterPolicy,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct AccountPoliciesOpt {
+    #[command(subcommand)]
+    pub command: AccountPoliciesCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AccountPoliciesCommand {
+    /// Lists the account policies, which apply to every log group of the account.
+    List(ListAccountPoliciesOpt),
+
+    /// Creates or replaces an account policy.
+    Put(PutAccountPolicyOpt),
+
+    /// Deletes an account policy.
+    Delete(DeleteAccountPolicyOpt),
+}
+
+#[derive(Debug, Args)]
+pub struct ListAccountPoliciesOpt {
+    /// Only lists policies of this kind. Both kinds are listed if not supplied.
+    #[arg(long = "type", value_enum, value_name = "TYPE")]
+    pub kind: Option<PolicyKind>,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct PutAccountPolicyOpt {
+    /// The name of the policy. An existing policy with this name is replaced.
+    #[arg(long)]
+    pub name: String,
+
+    /// The kind of the policy.
+    #[arg(long = "type", value_enum, value_name = "TYPE")]
+    pub kind: PolicyKind,
+
+    /// A file with the JSON policy document, or `-` to read it from standard input.
+    #[arg(long, value_name = "FILE", value_parser = read_policy_document)]
+    pub document: String,
+
+    /// Only applies a subscription filter policy to the log groups it selects,
+    /// e.g. `LogGroupName NOT IN ["excluded"]`.
+    #[arg(long, value_name = "CRITERIA")]
+    pub selection_criteria: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct DeleteAccountPolicyOpt {
+    /// The name of the policy.
+    #[arg(long)]
+    pub name: String,
+
+    /// The kind of the policy.
+    #[arg(long = "type", value_enum, value_name = "TYPE")]
+    pub kind: PolicyKind,
+
+    #[command(flatten)]
+    pub confirm: ConfirmOpt,
+}
+
+impl AccountPoliciesOpt {
+    /// Checks the options that only apply to some kinds of policy.
+    pub fn validate(&self) -> Result<(), String> {
+        let AccountPoliciesCommand::Put(opt) = &self.command else {
+            return Ok(());
+        };
+        if opt.selection_criteria.is_some() && opt.kind != PolicyKind::SubscriptionFilter {
+            return Err(
+                "--selection-criteria can only be used with subscription filter policies"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Describes an account policy for structured output.
+fn policy_record(policy: &AccountPolicy) -> Record {
+    Record::new()
+        .field("name", policy.policy_name())
+        .field("type", policy.policy_type().map(|t| t.as_str()))
+        .field("scope", policy.scope().map(|s| s.as_str()))
+        .field("selectionCriteria", policy.selection_criteria())
+        .field("accountId", policy.account_id())
+        .field("lastUpdatedTime", policy.last_updated_time())
+        .field("document", policy.policy_document())
+}
+
+/// Describes an account policy as a row of the `table` output, without its document.
+fn policy_table_row(policy: &AccountPolicy) -> Record {
+    Record::new()
+        .field("NAME", policy.policy_name())
+        .field("TYPE", policy.policy_type().map(|t| t.as_str()))
+        .field("SCOPE", policy.scope().map(|s| s.as_str()))
+        .field("SELECTION", policy.selection_criteria())
+        .field(
+            "UPDATED",
+            policy.last_updated_time().map(format_timestamp).as_deref(),
+        )
+}
+
+/// Lists the account policies with DescribeAccountPolicies, which takes one policy type at a time.
+async fn list_policies(client: &Client, opt: ListAccountPoliciesOpt) -> Result<(), Error> {
+    let ListAccountPoliciesOpt { kind, output } = opt;
+    let kinds = match kind {
+        Some(kind) => vec![kind],
+        None => PolicyKind::value_variants().to_vec(),
+    };
+    let mut policies: Vec<AccountPolicy> = Vec::new();
+    for kind in kinds {
+        policies.extend(
+            client
+                .describe_account_policies()
+                .policy_type(kind.into())
+                .send()
+                .await?
+                .account_policies
+                .unwrap_or_default(),
+        );
+    }
+
+    match output {
+        OutputFormat::Text => {
+            for policy in &policies {
+                outln!(
+                    "{}\t{}\t{}\t{}",
+                    policy.policy_name().unwrap_or_default(),
+                    policy.policy_type().map_or("-", |t| t.as_str()),
+                    policy.selection_criteria().unwrap_or("-"),
+                    policy
+                        .last_updated_time()
+                        .map_or_else(|| "-".to_string(), format_timestamp)
+                );
+                if let Some(document) = policy.policy_document() {
+                    let document = Json::parse(document)
+                        .map_or_else(|_| document.to_string(), |json| json.to_json());
+                    outln!("    {}", document);
+                }
+            }
+        }
+        format => output::print_records(
+            format,
+            &policies.iter().map(policy_record).collect::<Vec<_>>(),
+            &policies.iter().map(policy_table_row).collect::<Vec<_>>(),
+        ),
+    }
+    Ok(())
+}
+
+/// Creates or replaces an account policy with PutAccountPolicy, for every log group of the account.
+async fn put_policy(client: &Client, opt: PutAccountPolicyOpt, verbose: bool) -> Result<(), Error> {
+    let PutAccountPolicyOpt {
+        name,
+        kind,
+        document,
+        selection_criteria,
+    } = opt;
+
+    if verbose {
+        eprintln!("Policy name:                   {}", &name);
+        eprintln!(
+            "Policy type:                   {}",
+            PolicyType::from(kind).as_str()
+        );
+        if let Some(selection_criteria) = &selection_criteria {
+            eprintln!("Selection criteria:            {}", selection_criteria);
+        }
+        eprintln!();
+    }
+
+    client
+        .put_account_policy()
+        .policy_name(&name)
+        .policy_type(kind.into())
+        .policy_document(document)
+        .scope(Scope::All)
+        .set_selection_criteria(selection_criteria)
+        .send()
+        .await?;
+    outln!("Put account policy {}", name);
+    Ok(())
+}
+
+/// Deletes an account policy with DeleteAccountPolicy.
+async fn delete_policy(
+    client: &Client,
+    opt: DeleteAccountPolicyOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let DeleteAccountPolicyOpt {
+        name,
+        kind,
+        confirm,
+    } = opt;
+
+    if verbose {
+        eprintln!("Policy name:                   {}", &name);
+        eprintln!(
+            "Policy type:                   {}",
+            PolicyType::from(kind).as_str()
+        );
+        eprintln!();
+    }
+
+    confirm.confirm(
+        &format!(
+            "About to delete account policy {}. It will no longer apply to any log group of the account.",
+            name
+        ),
+        &[],
+    )?;
+    client
+        .delete_account_policy()
+        .policy_name(&name)
+        .policy_type(kind.into())
+        .send()
+        .await?;
+    outln!("Deleted account policy {}", name);
+    Ok(())
+}
+
+/// Runs an account policy subcommand.
+pub async fn run(client: &Client, opt: AccountPoliciesOpt, verbose: bool) -> Result<(), Error> {
+    match opt.command {
+        AccountPoliciesCommand::List(opt) => list_policies(client, opt).await,
+        AccountPoliciesCommand::Put(opt) => put_policy(client, opt, verbose).await,
+        AccountPoliciesCommand::Delete(opt) => delete_policy(client, opt, verbose).await,
+    }
+}
@@ -0,0 +1,253 @@
+use aws_sdk_cloudwatchlogs::{
+    types::{Anomaly, AnomalyDetector, SuppressionState},
+    Client,
+};
+use clap::{Args, ValueEnum};
+
+use crate::error::Error;
+use crate::groups::resolve_group_arn;
+use crate::outln;
+use crate::output::{self, format_timestamp, OutputFormat, Record};
+
+/// The priority of an anomaly, from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    /// Reads the priority that anomaly detection assigned, such as `HIGH`.
+    fn of(anomaly: &Anomaly) -> Option<Severity> {
+        let priority = anomaly.priority()?;
+        Severity::value_variants().iter().copied().find(|severity| {
+            severity
+                .to_possible_value()
+                .is_some_and(|value| value.get_name().eq_ignore_ascii_case(priority))
+        })
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct AnomaliesOpt {
+    /// Only shows the detectors that watch this log group, given by name or ARN.
+    #[arg(short, long)]
+    pub group: Option<String>,
+
+    /// Only shows anomalies of at least this priority.
+    #[arg(long, value_enum, value_name = "SEVERITY")]
+    pub min_severity: Option<Severity>,
+
+    /// Also shows anomalies that have been suppressed.
+    #[arg(long)]
+    pub include_suppressed: bool,
+
+    /// Only lists the anomaly detectors, without their anomalies.
+    #[arg(long, conflicts_with_all = ["min_severity", "include_suppressed"])]
+    pub detectors_only: bool,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+/// Describes an anomaly detector for structured output.
+fn detector_record(detector: &AnomalyDetector) -> Record {
+    Record::new()
+        .field("name", detector.detector_name())
+        .field("arn", detector.anomaly_detector_arn())
+        .field(
+            "status",
+            detector.anomaly_detector_status().map(|s| s.as_str()),
+        )
+        .field(
+            "evaluationFrequency",
+            detector.evaluation_frequency().map(|f| f.as_str()),
+        )
+        .field("filterPattern", detector.filter_pattern())
+        .field(
+            "logGroupArns",
+            detector.log_group_arn_list().join(",").as_str(),
+        )
+        .field("creationTime", detector.creation_time_stamp())
+}
+
+/// Describes an anomaly detector as a row of the `table` output.
+fn detector_table_row(detector: &AnomalyDetector) -> Record {
+    Record::new()
+        .field("NAME", detector.detector_name())
+        .field(
+            "STATUS",
+            detector.anomaly_detector_status().map(|s| s.as_str()),
+        )
+        .field(
+            "FREQUENCY",
+            detector.evaluation_frequency().map(|f| f.as_str()),
+        )
+        .field("GROUPS", detector.log_group_arn_list().len() as i64)
+        .field(
+            "CREATED",
+            format_timestamp(detector.creation_time_stamp()).as_str(),
+        )
+}
+
+/// Describes an anomaly for structured output, with the name of its detector.
+fn anomaly_record(detector: &AnomalyDetector, anomaly: &Anomaly) -> Record {
+    Record::new()
+        .field("detector", detector.detector_name())
+        .field("anomalyId", anomaly.anomaly_id())
+        .field("priority", anomaly.priority())
+        .field("state", anomaly.state().as_str())
+        .field("active", if anomaly.active() { "true" } else { "false" })
+        .field("firstSeen", anomaly.first_seen())
+        .field("lastSeen", anomaly.last_seen())
+        .field("description", anomaly.description())
+        .field("pattern", anomaly.pattern_string())
+        .field(
+            "logGroupArns",
+            anomaly.log_group_arn_list().join(",").as_str(),
+        )
+}
+
+/// Describes an anomaly as a row of the `table` output.
+fn anomaly_table_row(detector: &AnomalyDetector, anomaly: &Anomaly) -> Record {
+    Record::new()
+        .field("DETECTOR", detector.detector_name())
+        .field("PRIORITY", anomaly.priority())
+        .field("STATE", anomaly.state().as_str())
+        .field("LAST SEEN", format_timestamp(anomaly.last_seen()).as_str())
+        .field("DESCRIPTION", anomaly.description())
+}
+
+/// The anomalies that a detector has found, without the suppressed ones unless asked.
+async fn detector_anomalies(
+    client: &Client,
+    detector: &AnomalyDetector,
+    include_suppressed: bool,
+) -> Result<Vec<Anomaly>, aws_sdk_cloudwatchlogs::Error> {
+    let mut pages = client
+        .list_anomalies()
+        .set_anomaly_detector_arn(detector.anomaly_detector_arn().map(str::to_string))
+        .set_suppression_state((!include_suppressed).then_some(SuppressionState::Unsuppressed))
+        .into_paginator()
+        .send();
+    let mut anomalies = Vec::new();
+    while let Some(page) = pages.next().await {
+        anomalies.extend(page?.anomalies.unwrap_or_default());
+    }
+    Ok(anomalies)
+}
+
+/// Lists the anomaly detectors with ListLogAnomalyDetectors and the anomalies of each
+/// with ListAnomalies, most severe and most recent first.
+pub async fn run(client: &Client, opt: AnomaliesOpt, verbose: bool) -> Result<(), Error> {
+    let AnomaliesOpt {
+        group,
+        min_severity,
+        include_suppressed,
+        detectors_only,
+        output,
+    } = opt;
+
+    if verbose {
+        if let Some(group) = &group {
+            eprintln!("Log group name:                {}", group);
+        }
+        if let Some(min_severity) = min_severity {
+            eprintln!("Minimum severity:              {:?}", min_severity);
+        }
+        eprintln!();
+    }
+
+    let group_arn = match &group {
+        Some(group) => Some(resolve_group_arn(client, group).await?),
+        None => None,
+    };
+    let mut pages = client
+        .list_log_anomaly_detectors()
+        .set_filter_log_group_arn(group_arn)
+        .into_paginator()
+        .send();
+    let mut detectors: Vec<AnomalyDetector> = Vec::new();
+    while let Some(page) = pages.next().await {
+        detectors.extend(page?.anomaly_detectors.unwrap_or_default());
+    }
+
+    if detectors_only {
+        match output {
+            OutputFormat::Text => {
+                for detector in &detectors {
+                    outln!(
+                        "{}\t{}\t{}",
+                        detector.detector_name().unwrap_or_default(),
+                        detector
+                            .anomaly_detector_status()
+                            .map_or("-", |s| s.as_str()),
+                        detector.log_group_arn_list().join(",")
+                    );
+                }
+            }
+            format => output::print_records(
+                format,
+                &detectors.iter().map(detector_record).collect::<Vec<_>>(),
+                &detectors.iter().map(detector_table_row).collect::<Vec<_>>(),
+            ),
+        }
+        return Ok(());
+    }
+
+    let mut found: Vec<(&AnomalyDetector, Anomaly)> = Vec::new();
+    for detector in &detectors {
+        let mut anomalies = detector_anomalies(client, detector, include_suppressed).await?;
+        anomalies.retain(|anomaly| min_severity.is_none() || Severity::of(anomaly) >= min_severity);
+        anomalies.sort_by_key(|anomaly| {
+            (
+                std::cmp::Reverse(Severity::of(anomaly)),
+                std::cmp::Reverse(anomaly.last_seen()),
+            )
+        });
+        found.extend(anomalies.into_iter().map(|anomaly| (detector, anomaly)));
+    }
+
+    match output {
+        OutputFormat::Text => {
+            for detector in &detectors {
+                let anomalies: Vec<&Anomaly> = found
+                    .iter()
+                    .filter(|(d, _)| std::ptr::eq(*d, detector))
+                    .map(|(_, anomaly)| anomaly)
+                    .collect();
+                outln!(
+                    "{} ({}, {} anomalies)",
+                    detector.detector_name().unwrap_or_default(),
+                    detector
+                        .anomaly_detector_status()
+                        .map_or("-", |s| s.as_str()),
+                    anomalies.len()
+                );
+                for anomaly in anomalies {
+                    outln!(
+                        "    {}\t{}\t{}",
+                        anomaly.priority().unwrap_or("-"),
+                        format_timestamp(anomaly.last_seen()),
+                        anomaly.description()
+                    );
+                }
+            }
+        }
+        format => output::print_records(
+            format,
+            &found
+                .iter()
+                .map(|(detector, anomaly)| anomaly_record(detector, anomaly))
+                .collect::<Vec<_>>(),
+            &found
+                .iter()
+                .map(|(detector, anomaly)| anomaly_table_row(detector, anomaly))
+                .collect::<Vec<_>>(),
+        ),
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! Finds the log groups of an API Gateway stage. The API Gateway SDK is not part of this build,
+//! so its REST API is called through [`SignedClient`].
+
+use crate::jq::Json;
+use crate::signed::{percent_encode, SignedClient};
+use crate::targets::Target;
+
+/// The log group that API Gateway writes the execution logs of a REST API stage to.
+fn execution_log_group(api_id: &str, stage: &str) -> String {
+    format!("API-Gateway-Execution-Logs_{}/{}", api_id, stage)
+}
+
+/// Sends a GET request, returning `None` when the resource does not exist.
+async fn get(client: &SignedClient, path: &str) -> Result<Option<Json>, String> {
+    let url = match client.endpoint_url() {
+        Some(endpoint_url) => format!("{}{}", endpoint_url, path),
+        None => format!(
+            "https://apigateway.{}.amazonaws.com{}",
+            client.region(),
+            path
+        ),
+    };
+    let (status, body) = client
+        .send("apigateway", "GET", &url, &[], Vec::new())
+        .await?;
+    if status == 404 {
+        return Ok(None);
+    }
+    let response = Json::parse(&String::from_utf8_lossy(&body))
+        .map_err(|e| format!("API Gateway returned invalid JSON: {}", e))?;
+    if status != 200 {
+        let message = match response
+            .get_path("message")
+            .or_else(|| response.get_path("Message"))
+        {
+            Some(Json::String(message)) => format!(": {}", message),
+            _ => String::new(),
+        };
+        return Err(format!("API Gateway returned {}{}", status, message));
+    }
+    Ok(Some(response))
+}
+
+/// The log groups of the stages of a REST, HTTP or WebSocket API: the execution logs of REST APIs,
+/// and the access log group that each stage is set up with. All stages are used when `stage` is `None`.
+pub async fn api_log_groups(
+    target: &Target,
+    api_id: &str,
+    stage: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let client = SignedClient::new(target)?;
+    let api = percent_encode(api_id, false);
+    // REST API は v1、HTTP と WebSocket の API は v2 のパスにある
+    let (stages, rest) = match get(&client, &format!("/restapis/{}/stages", api)).await? {
+        Some(response) => (response.get_path("item").cloned(), true),
+        None => match get(&client, &format!("/v2/apis/{}/stages", api)).await? {
+            Some(response) => (response.get_path("items").cloned(), false),
+            None => return Err(format!("there is no API with the ID {}", api_id)),
+        },
+    };
+    let stages = match stages {
+        Some(Json::Array(stages)) => stages,
+        _ => Vec::new(),
+    };
+
+    let mut groups = Vec::new();
+    let mut found_stage = false;
+    for settings in &stages {
+        let Some(Json::String(name)) = settings.get_path("stageName") else {
+            continue;
+        };
+        if stage.is_some_and(|stage| stage != name) {
+            continue;
+        }
+        found_stage = true;
+        if rest {
+            groups.push(execution_log_group(api_id, name));
+        }
+        // 宛先は `arn:aws:logs:<Region>:<アカウント>:log-group:<名前>` の形
+        if let Some(Json::String(arn)) = settings.get_path("accessLogSettings.destinationArn") {
+            match arn.split_once(":log-group:") {
+                Some((_, group)) => groups.push(group.trim_end_matches(":*").to_string()),
+                None => eprintln!(
+                    "Warning: Stage {} sends its access logs to {}, not CloudWatch Logs",
+                    name, arn
+                ),
+            }
+        }
+    }
+    if let (Some(stage), false) = (stage, found_stage) {
+        return Err(format!("the API {} has no stage named {}", api_id, stage));
+    }
+    Ok(groups)
+}
@@ -0,0 +1,196 @@
+use aws_sdk_cloudwatchlogs::types::LogGroup;
+use clap::{Args, Subcommand};
+use futures_util::future::join_all;
+
+use crate::error::Error;
+use crate::groups::{format_retention, get_groups, group_account, GroupRequestOpt};
+use crate::output::{self, format_bytes, OutputFormat, Record};
+use crate::progress::Progress;
+use crate::targets::Target;
+use crate::{out, outln};
+
+#[derive(Debug, Args)]
+pub struct AuditOpt {
+    #[command(subcommand)]
+    pub command: AuditCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AuditCommand {
+    /// Reports the log groups that keep their events forever, or longer than `--max-days`.
+    Retention(RetentionAuditOpt),
+}
+
+#[derive(Debug, Args)]
+pub struct RetentionAuditOpt {
+    /// Also reports the log groups that keep events for more than this many days.
+    #[arg(long, value_name = "DAYS")]
+    pub max_days: Option<i32>,
+
+    #[command(flatten)]
+    pub request: GroupRequestOpt,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+/// Why a log group is reported by the retention audit.
+fn retention_finding(group: &LogGroup, max_days: Option<i32>) -> Option<&'static str> {
+    match (group.retention_in_days(), max_days) {
+        (None, _) => Some("never-expire"),
+        (Some(days), Some(max_days)) if days > max_days => Some("over-max-days"),
+        _ => None,
+    }
+}
+
+/// Describes a reported log group for structured output.
+fn finding_record(target: &Target, group: &LogGroup, finding: &str, show_account: bool) -> Record {
+    let record = match show_account {
+        true => target.record().field("accountId", group_account(group)),
+        false => target.record(),
+    };
+    record
+        .field("name", group.log_group_name())
+        .field("finding", finding)
+        .field("retentionInDays", group.retention_in_days().map(i64::from))
+        .field("storedBytes", group.stored_bytes())
+        .field("arn", group.arn())
+}
+
+/// Describes a reported log group as a row of the `table` output.
+fn finding_table_row(
+    target: &Target,
+    group: &LogGroup,
+    finding: &str,
+    show_account: bool,
+) -> Record {
+    let row = match show_account {
+        true => target.table_row().field("ACCOUNT", group_account(group)),
+        false => target.table_row(),
+    };
+    row.field("NAME", group.log_group_name())
+        .field("FINDING", finding)
+        .field("RETENTION", format_retention(group).as_str())
+        .field(
+            "SIZE",
+            format_bytes(group.stored_bytes().unwrap_or_default()).as_str(),
+        )
+}
+
+/// Scans every log group in each target's Region and reports those without a retention policy
+/// or, with `--max-days`, with a longer one.
+async fn audit_retention(
+    targets: &[Target],
+    opt: RetentionAuditOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let RetentionAuditOpt {
+        max_days,
+        request,
+        output,
+    } = opt;
+
+    if verbose {
+        if let Some(max_days) = max_days {
+            eprintln!("Maximum retention:             {} days", max_days);
+        }
+        if let Some(prefix) = &request.prefix {
+            eprintln!("Log group prefix:              {}", prefix);
+        }
+        request.accounts.print_verbose();
+        eprintln!();
+    }
+    let show_account = request.accounts.enabled();
+
+    let progress = Progress::new("groups");
+    let results = join_all(
+        targets
+            .iter()
+            .map(|target| get_groups(&target.client, &request, &progress)),
+    )
+    .await;
+    progress.clear();
+    let mut scanned = 0;
+    let mut findings = Vec::new();
+    for (target, result) in targets.iter().zip(results) {
+        let groups = result?;
+        scanned += groups.len();
+        findings.extend(groups.into_iter().filter_map(|group| {
+            retention_finding(&group, max_days).map(|finding| (target, group, finding))
+        }));
+    }
+
+    let records = || {
+        findings
+            .iter()
+            .map(|(target, group, finding)| finding_record(target, group, finding, show_account))
+            .collect::<Vec<_>>()
+    };
+    match output {
+        OutputFormat::Text => {
+            let never_expire = findings
+                .iter()
+                .filter(|(_, _, finding)| *finding == "never-expire")
+                .count();
+            match max_days {
+                Some(max_days) => outln!(
+                    "Scanned {} groups: {} never expire, {} keep events for more than {} days",
+                    scanned,
+                    never_expire,
+                    findings.len() - never_expire,
+                    max_days
+                ),
+                None => outln!("Scanned {} groups: {} never expire", scanned, never_expire),
+            }
+            for (target, group, _) in &findings {
+                let prefix = match show_account {
+                    true => format!(
+                        "{}{}\t",
+                        target.text_prefix(),
+                        group_account(group).unwrap_or("-")
+                    ),
+                    false => target.text_prefix(),
+                };
+                outln!(
+                    "{}{}\t{}\t{}",
+                    prefix,
+                    group.log_group_name().unwrap_or_default(),
+                    format_retention(group),
+                    format_bytes(group.stored_bytes().unwrap_or_default())
+                );
+            }
+        }
+        OutputFormat::Json => outln!("{}", output::to_json_array(&records())),
+        OutputFormat::Ndjson => {
+            for record in records() {
+                outln!("{}", record.to_json());
+            }
+        }
+        OutputFormat::Csv => out!("{}", output::to_csv(&records(), true)),
+        OutputFormat::Yaml => {
+            let records = records();
+            match records.is_empty() {
+                true => outln!("[]"),
+                false => out!("{}", output::to_yaml_items(&records)),
+            }
+        }
+        OutputFormat::Table => {
+            let rows: Vec<Record> = findings
+                .iter()
+                .map(|(target, group, finding)| {
+                    finding_table_row(target, group, finding, show_account)
+                })
+                .collect();
+            out!("{}", output::to_table(&rows));
+        }
+    }
+    Ok(())
+}
+
+/// Runs an audit across each target's Region.
+pub async fn run(targets: &[Target], opt: AuditOpt, verbose: bool) -> Result<(), Error> {
+    match opt.command {
+        AuditCommand::Retention(opt) => audit_retention(targets, opt, verbose).await,
+    }
+}
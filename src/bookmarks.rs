@@ -0,0 +1,383 @@
+//! Named bookmarks of log groups, saved with `bookmark add` in
+//! `~/.config/print-log-streams/bookmarks.toml` and given as `-g @NAME`:
+//!
+//! ```toml
+//! [payments]
+//! groups = ["/aws/lambda/payments-api", "/aws/lambda/payments-worker"]
+//! regions = ["eu-west-1"]
+//! ```
+//!
+//! The favorite groups of `config.toml` are expanded the same way, but only that file changes them.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+
+use crate::config::{self, Config};
+use crate::outln;
+use crate::output::{self, OutputFormat, Record};
+
+#[derive(Debug, Args)]
+pub struct BookmarkOpt {
+    #[command(subcommand)]
+    pub command: BookmarkCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BookmarkCommand {
+    /// Saves log groups under a name, with the Regions given by `--region`.
+    Add(AddBookmarkOpt),
+
+    /// Lists the bookmarks and the favorite groups of config.toml.
+    List(ListBookmarksOpt),
+
+    /// Removes bookmarks.
+    #[command(visible_alias = "remove")]
+    Rm(RmBookmarkOpt),
+}
+
+#[derive(Debug, Args)]
+pub struct AddBookmarkOpt {
+    /// The name of the bookmark, given as `-g @NAME`. Letters, digits, `-` and `_`.
+    #[arg(value_parser = parse_name)]
+    pub name: String,
+
+    /// A log group to save. Can be repeated.
+    #[arg(short, long = "group", value_name = "LOG-GROUP", required = true)]
+    pub groups: Vec<String>,
+
+    /// Replaces a bookmark of the same name.
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ListBookmarksOpt {
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct RmBookmarkOpt {
+    /// The names of the bookmarks.
+    #[arg(required = true)]
+    pub names: Vec<String>,
+}
+
+fn parse_name(name: &str) -> Result<String, String> {
+    match !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        true => Ok(name.to_string()),
+        false => Err("a bookmark name has only letters, digits, `-` and `_`".to_string()),
+    }
+}
+
+/// The log groups of a bookmark, and the Regions to look in if it was saved with any.
+#[derive(Debug, Clone, Default)]
+pub struct Bookmark {
+    pub groups: Vec<String>,
+    pub regions: Vec<String>,
+}
+
+/// The saved bookmarks, by name.
+#[derive(Debug, Default)]
+pub struct Bookmarks {
+    saved: BTreeMap<String, Bookmark>,
+}
+
+fn path() -> Option<PathBuf> {
+    Some(config::dir()?.join("bookmarks.toml"))
+}
+
+impl Bookmarks {
+    /// Reads the saved bookmarks, none without a file.
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = path() else {
+            return Ok(Bookmarks::default());
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Bookmarks::default()),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        Bookmarks::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut saved: BTreeMap<String, Bookmark> = BTreeMap::new();
+        for entry in config::parse_entries(text)? {
+            let Some(name) = entry.table.clone() else {
+                return Err(format!(
+                    "line {}: `{}` is not in a [bookmark] table",
+                    entry.line, entry.key
+                ));
+            };
+            let bookmark = saved.entry(name).or_default();
+            match entry.key.as_str() {
+                "groups" => bookmark.groups = entry.strings()?,
+                "regions" => bookmark.regions = entry.strings()?,
+                key => return Err(format!("line {}: unknown setting `{}`", entry.line, key)),
+            }
+        }
+        Ok(Bookmarks { saved })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = path().ok_or("cannot find the home directory to save bookmarks in")?;
+        let text: String = self
+            .saved
+            .iter()
+            .map(|(name, bookmark)| {
+                let mut table = format!("[{}]\ngroups = {}\n", name, array(&bookmark.groups));
+                if !bookmark.regions.is_empty() {
+                    table.push_str(&format!("regions = {}\n", array(&bookmark.regions)));
+                }
+                table
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let write = || -> io::Result<()> {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, text)
+        };
+        write().map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// `@NAME` for every bookmark and favorite group, to complete `--group` with.
+    pub fn names(&self, config: &Config) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .saved
+            .keys()
+            .map(String::as_str)
+            .chain(config.favorites())
+            .map(|name| format!("@{}", name))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// The bookmark of `name`, or else the favorite groups of the same name in config.toml.
+    fn get(&self, config: &Config, name: &str) -> Option<Bookmark> {
+        self.saved.get(name).cloned().or_else(|| {
+            config.favorite(name).map(|groups| Bookmark {
+                groups: groups.to_vec(),
+                regions: Vec::new(),
+            })
+        })
+    }
+
+    /// Replaces each `@NAME` given to `-g` or `--group` with the groups of the bookmark.
+    /// The Regions of the bookmarks are added as `--region` unless a Region is given.
+    pub fn expand(
+        &self,
+        config: &Config,
+        args: impl IntoIterator<Item = OsString>,
+    ) -> Result<Vec<OsString>, String> {
+        self.expand_with(config, args, true)
+    }
+
+    /// Replaces each `@NAME` like [`Bookmarks::expand`], but leaves the Regions out,
+    /// for a client whose Region is already chosen.
+    pub fn expand_groups(
+        &self,
+        config: &Config,
+        args: impl IntoIterator<Item = OsString>,
+    ) -> Result<Vec<OsString>, String> {
+        self.expand_with(config, args, false)
+    }
+
+    fn expand_with(
+        &self,
+        config: &Config,
+        args: impl IntoIterator<Item = OsString>,
+        with_regions: bool,
+    ) -> Result<Vec<OsString>, String> {
+        let mut expanded = Vec::new();
+        let mut regions: Vec<String> = Vec::new();
+        let mut has_region = false;
+        let mut after_flag = false;
+        let mut rest = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(text) = arg.to_str() else {
+                after_flag = false;
+                expanded.push(arg);
+                continue;
+            };
+            if text == "--" {
+                rest.push(arg);
+                rest.extend(args);
+                break;
+            }
+            let (prefix, value) = match text {
+                _ if after_flag => ("", text),
+                _ if text.starts_with("--group=") => ("--group=", &text["--group=".len()..]),
+                _ if text.starts_with("-g") && text.len() > 2 => ("-g", &text[2..]),
+                _ => {
+                    after_flag = text == "-g" || text == "--group";
+                    has_region |= text == "--all-regions"
+                        || text.starts_with("--region")
+                        || (text.starts_with("-r") && !text.starts_with("--"));
+                    expanded.push(arg);
+                    continue;
+                }
+            };
+            after_flag = false;
+            let Some(name) = value.strip_prefix('@') else {
+                expanded.push(arg);
+                continue;
+            };
+            let bookmark = self.get(config, name).ok_or_else(|| {
+                format!(
+                    "no bookmark `@{}`; `bookmark list` shows the saved ones",
+                    name
+                )
+            })?;
+            for (i, group) in bookmark.groups.iter().enumerate() {
+                // 2 つ目からは --group=NAME として足す
+                match i {
+                    0 => expanded.push(format!("{}{}", prefix, group).into()),
+                    _ => expanded.push(format!("--group={}", group).into()),
+                }
+            }
+            for region in bookmark.regions {
+                if !regions.contains(&region) {
+                    regions.push(region);
+                }
+            }
+        }
+        if with_regions && !has_region {
+            expanded.extend(
+                regions
+                    .into_iter()
+                    .map(|region| format!("--region={}", region).into()),
+            );
+        }
+        expanded.extend(rest);
+        Ok(expanded)
+    }
+}
+
+/// `["a", "b"]`, quoted as TOML basic strings.
+fn array(values: &[String]) -> String {
+    let values: Vec<String> = values.iter().map(|value| config::quote(value)).collect();
+    format!("[{}]", values.join(", "))
+}
+
+/// Runs a `bookmark` subcommand. `regions` are the Regions given on the command line.
+pub fn run(opt: BookmarkOpt, regions: Vec<String>) -> Result<(), String> {
+    let config = config::load()?;
+    let mut bookmarks = Bookmarks::load()?;
+    match opt.command {
+        BookmarkCommand::Add(opt) => {
+            if config.favorite(&opt.name).is_some() {
+                return Err(format!(
+                    "`{}` is a favorite group in config.toml, choose another name",
+                    opt.name
+                ));
+            }
+            if bookmarks.saved.contains_key(&opt.name) && !opt.force {
+                return Err(format!(
+                    "bookmark `@{}` already exists, use --force to replace it",
+                    opt.name
+                ));
+            }
+            let bookmark = Bookmark {
+                groups: opt.groups,
+                regions,
+            };
+            bookmarks.saved.insert(opt.name.clone(), bookmark);
+            bookmarks.save()?;
+            if !output::is_quiet() {
+                outln!("Saved @{}.", opt.name);
+            }
+        }
+        BookmarkCommand::List(opt) => list(&bookmarks, config, opt.output),
+        BookmarkCommand::Rm(opt) => {
+            for name in &opt.names {
+                let name = name.strip_prefix('@').unwrap_or(name);
+                if bookmarks.saved.remove(name).is_none() {
+                    return Err(match config.favorite(name) {
+                        Some(_) => format!("`{}` is defined in config.toml, edit it there", name),
+                        None => format!("no bookmark `@{}`", name),
+                    });
+                }
+            }
+            bookmarks.save()?;
+            if !output::is_quiet() {
+                outln!("Removed {} bookmarks.", opt.names.len());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn list(bookmarks: &Bookmarks, config: &Config, format: OutputFormat) {
+    let saved = bookmarks
+        .saved
+        .iter()
+        .map(|(name, bookmark)| (name.as_str(), bookmark.clone(), "bookmarks"));
+    let favorites = config.favorites().map(|name| {
+        let groups = config.favorite(name).unwrap_or_default().to_vec();
+        let bookmark = Bookmark {
+            groups,
+            regions: Vec::new(),
+        };
+        (name, bookmark, "config")
+    });
+    let all: Vec<_> = saved.chain(favorites).collect();
+
+    match format {
+        OutputFormat::Text => {
+            for (name, bookmark, source) in &all {
+                outln!(
+                    "@{}\t{}\t{}{}",
+                    name,
+                    match bookmark.regions.as_slice() {
+                        [] => "-".to_string(),
+                        regions => regions.join(","),
+                    },
+                    bookmark.groups.join(","),
+                    match *source {
+                        "config" => "\t(config.toml)",
+                        _ => "",
+                    }
+                );
+            }
+        }
+        format => {
+            let records: Vec<Record> = all
+                .iter()
+                .map(|(name, bookmark, source)| {
+                    Record::new()
+                        .field("name", *name)
+                        .field("groups", bookmark.groups.join(",").as_str())
+                        .field("regions", bookmark.regions.join(",").as_str())
+                        .field("source", *source)
+                })
+                .collect();
+            let rows: Vec<Record> = all
+                .iter()
+                .map(|(name, bookmark, source)| {
+                    Record::new()
+                        .field("NAME", format!("@{}", name).as_str())
+                        .field("REGIONS", bookmark.regions.join(",").as_str())
+                        .field("GROUPS", bookmark.groups.join(",").as_str())
+                        .field("SOURCE", *source)
+                })
+                .collect();
+            output::print_records(format, &records, &rows);
+        }
+    }
+}
@@ -0,0 +1,722 @@
+//! The `browse` command: a full-screen log browser. The left pane lists the log groups, or the
+//! streams of one with the most recently active first, and the right pane follows the events of
+//! the selection like `tail --follow`. The time range, the filter pattern and a search of the
+//! shown events are edited with single keys.
+//!
+//! No TUI library is part of this build, so the screen is drawn with ANSI escape sequences
+//! over the raw mode of the terminal on stdin, and redrawn whole after each change.
+
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use aws_sdk_cloudwatchlogs::types::LogStream;
+use aws_sdk_cloudwatchlogs::Client;
+use clap::Args;
+
+use crate::color;
+use crate::error::Error;
+use crate::events::{filter_events, EventFilter, LogEvent};
+use crate::output::format_timestamp;
+use crate::progress::Progress;
+use crate::report;
+use crate::streams::{for_each_stream, StreamOrder, StreamRequestOpt};
+use crate::tail::Cursor;
+use crate::terminal::{self, Key, Screen, BOLD, RESET, REVERSE};
+use crate::time_range::{now_millis, parse_timestamp};
+
+/// The most log groups listed in the left pane.
+const MAX_GROUPS: usize = 1000;
+
+/// The most log streams listed for a log group, the most recently active ones.
+const MAX_STREAMS: usize = 500;
+
+/// The most events kept for the right pane. Older ones are dropped as new ones arrive.
+const MAX_EVENTS: usize = 10_000;
+
+/// How long to wait for a key before polling for events again.
+const KEY_WAIT_MILLIS: i32 = 200;
+
+const HELP: &str =
+    "Tab pane  Enter open  Esc back  / search  f filter  t time  s sort  r reload  q quit";
+
+#[derive(Debug, Args)]
+pub struct BrowseOpt {
+    /// The log group to open. Without it, the log groups are listed first.
+    #[arg(short, long)]
+    pub group: Option<String>,
+
+    /// Only lists log groups whose names start with this prefix.
+    #[arg(long)]
+    pub group_prefix: Option<String>,
+
+    /// Where to start, e.g. `2h`, `now-10m` or `2024-05-01 13:00` (UTC). `t` changes it.
+    #[arg(long, default_value = "10m", value_parser = parse_timestamp)]
+    pub since: i64,
+
+    /// A CloudWatch Logs filter pattern, e.g. `ERROR`. `f` changes it.
+    #[arg(long)]
+    pub filter_pattern: Option<String>,
+
+    /// Seconds to wait between polls for new events.
+    #[arg(long, default_value_t = 2)]
+    pub interval: u64,
+}
+
+/// Which pane the arrow keys move in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    List,
+    Events,
+}
+
+/// What the left pane lists.
+enum List {
+    Groups(Vec<String>),
+    /// The streams of a log group. The first row selects every stream.
+    Streams {
+        group: String,
+        streams: Vec<LogStream>,
+    },
+}
+
+impl List {
+    fn len(&self) -> usize {
+        match self {
+            List::Groups(groups) => groups.len(),
+            List::Streams { streams, .. } => streams.len() + 1,
+        }
+    }
+}
+
+/// A line being typed in the status line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Search,
+    Filter,
+    Time,
+}
+
+impl Edit {
+    fn label(self) -> &'static str {
+        match self {
+            Edit::Search => "Search",
+            Edit::Filter => "Filter pattern",
+            Edit::Time => "Since[..until]",
+        }
+    }
+}
+
+/// The log group, or one of its streams, whose events are followed.
+struct Source {
+    group: String,
+    stream: Option<String>,
+    cursor: Cursor,
+    events: Vec<LogEvent>,
+    polled: bool,
+    /// Set once the events up to `--until` have been read, so that polling stops.
+    done: bool,
+}
+
+struct Browser {
+    client: Client,
+    color: bool,
+    group_prefix: Option<String>,
+    since: i64,
+    until: Option<i64>,
+    filter_pattern: Option<String>,
+    search: Option<String>,
+    order: StreamOrder,
+    list: List,
+    selected: usize,
+    focus: Focus,
+    source: Option<Source>,
+    /// How many events the right pane is scrolled up from the newest; 0 follows new events.
+    scroll: usize,
+    edit: Option<(Edit, String)>,
+    message: Option<String>,
+}
+
+/// Lists the log groups, or opens `--group`, and browses them until `q`.
+pub async fn run(client: &Client, opt: BrowseOpt, color: bool) -> Result<(), Error> {
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        return Err(Error::failed(
+            "browse needs a terminal on stdin and stderr.",
+        ));
+    }
+    let BrowseOpt {
+        group,
+        group_prefix,
+        since,
+        filter_pattern,
+        interval,
+    } = opt;
+    let mut browser = Browser {
+        client: client.clone(),
+        color,
+        group_prefix,
+        since,
+        until: None,
+        filter_pattern,
+        search: None,
+        order: StreamOrder::LastEvent,
+        list: List::Groups(Vec::new()),
+        selected: 0,
+        focus: Focus::List,
+        source: None,
+        scroll: 0,
+        edit: None,
+        message: None,
+    };
+    // グループが存在しなければ、画面を開く前に普通のエラーとして返す
+    match group {
+        Some(group) => {
+            browser.open_group(group).await?;
+            browser.focus = Focus::Events;
+        }
+        None => browser.load_groups().await?,
+    }
+
+    let _screen = Screen::enter()
+        .map_err(|e| Error::failed(format!("Failed to set up the terminal: {}", e)))?;
+    let interval = Duration::from_secs(interval);
+    let mut next_poll = Instant::now();
+    loop {
+        // キーを待つ間は待機点がないので、ここで一度譲って SIGTERM で破棄できるようにする
+        tokio::task::yield_now().await;
+        if Instant::now() >= next_poll {
+            browser.poll().await;
+            next_poll = Instant::now() + interval;
+        }
+        browser.draw();
+        let key = match tokio::task::block_in_place(|| terminal::poll_key(KEY_WAIT_MILLIS)) {
+            Ok(Some(key)) => key,
+            Ok(None) => continue,
+            Err(_) => break,
+        };
+        match browser.edit.is_some() {
+            true => browser.edit_key(key),
+            false => {
+                if !browser.key(key).await {
+                    break;
+                }
+            }
+        }
+        // 開き直したソースはすぐに読む
+        if browser.source.as_ref().is_some_and(|source| !source.polled) {
+            next_poll = Instant::now();
+        }
+    }
+    Ok(())
+}
+
+impl Browser {
+    async fn load_groups(&mut self) -> Result<(), aws_sdk_cloudwatchlogs::Error> {
+        let mut pages = self
+            .client
+            .describe_log_groups()
+            .set_log_group_name_prefix(self.group_prefix.clone())
+            .into_paginator()
+            .send();
+        let mut groups = Vec::new();
+        while let Some(page) = pages.next().await {
+            let page = page?;
+            groups.extend(
+                page.log_groups()
+                    .iter()
+                    .filter_map(|group| group.log_group_name())
+                    .map(str::to_string),
+            );
+            if groups.len() >= MAX_GROUPS {
+                groups.truncate(MAX_GROUPS);
+                break;
+            }
+        }
+        self.list = List::Groups(groups);
+        self.selected = 0;
+        Ok(())
+    }
+
+    /// Lists the streams of `group` and follows the events of all of them.
+    async fn open_group(&mut self, group: String) -> Result<(), aws_sdk_cloudwatchlogs::Error> {
+        let streams = self.load_streams(&group).await?;
+        self.list = List::Streams {
+            group: group.clone(),
+            streams,
+        };
+        self.selected = 0;
+        self.follow(group, None);
+        Ok(())
+    }
+
+    async fn load_streams(
+        &self,
+        group: &str,
+    ) -> Result<Vec<LogStream>, aws_sdk_cloudwatchlogs::Error> {
+        let request = StreamRequestOpt {
+            order_by: StreamOrder::LastEvent,
+            descending: true,
+            limit: Some(MAX_STREAMS),
+            ..Default::default()
+        };
+        let mut streams = Vec::new();
+        for_each_stream(
+            &self.client,
+            group,
+            &request,
+            &Progress::hidden(),
+            |stream| streams.push(stream.clone()),
+        )
+        .await?;
+        if self.order == StreamOrder::Name {
+            sort_by_name(&mut streams);
+        }
+        Ok(streams)
+    }
+
+    /// Starts following the events of `group`, or of one of its streams, from `--since`.
+    fn follow(&mut self, group: String, stream: Option<String>) {
+        let streams = stream.iter().cloned().collect();
+        self.source = Some(Source {
+            cursor: Cursor::new(group.clone(), streams, self.since),
+            group,
+            stream,
+            events: Vec::new(),
+            polled: false,
+            done: false,
+        });
+        self.scroll = 0;
+    }
+
+    /// Reads the time range or filter pattern again from the start.
+    fn restart(&mut self) {
+        if let Some(source) = self.source.take() {
+            self.follow(source.group, source.stream);
+        }
+    }
+
+    /// Reads the events that arrived since the last poll.
+    async fn poll(&mut self) {
+        let Some(source) = &mut self.source else {
+            return;
+        };
+        if source.done {
+            return;
+        }
+        source.polled = true;
+        let filter = EventFilter {
+            stream_names: source.stream.iter().cloned().collect(),
+            filter_pattern: self.filter_pattern.clone(),
+            start_time: Some(source.cursor.last_timestamp),
+            end_time: self.until,
+            ..Default::default()
+        };
+        match filter_events(&self.client, &source.group, &filter).await {
+            Ok(mut events) => {
+                source.cursor.retain_new(&mut events);
+                // 上にスクロール中なら、新しいイベントの分だけ位置をずらして表示を保つ
+                if self.scroll > 0 {
+                    self.scroll += events
+                        .iter()
+                        .filter(|event| matches(&self.search, event))
+                        .count();
+                }
+                source.events.extend(events);
+                if source.events.len() > MAX_EVENTS {
+                    source.events.drain(..source.events.len() - MAX_EVENTS);
+                }
+                source.done = self.until.is_some();
+            }
+            Err(e) => self.message = Some(format!("Error: {}", report::describe(&e))),
+        }
+    }
+
+    /// Handles a key outside of editing. Returns false to quit.
+    async fn key(&mut self, key: Key) -> bool {
+        self.message = None;
+        let result = match key {
+            Key::Char('q') | Key::Ctrl('c') | Key::Ctrl('d') => return false,
+            Key::Tab => {
+                self.focus = match self.focus {
+                    Focus::List => Focus::Events,
+                    Focus::Events => Focus::List,
+                };
+                Ok(())
+            }
+            Key::Char('/') => {
+                self.edit = Some((Edit::Search, self.search.clone().unwrap_or_default()));
+                Ok(())
+            }
+            Key::Char('f') => {
+                self.edit = Some((
+                    Edit::Filter,
+                    self.filter_pattern.clone().unwrap_or_default(),
+                ));
+                Ok(())
+            }
+            Key::Char('t') => {
+                self.edit = Some((Edit::Time, String::new()));
+                Ok(())
+            }
+            Key::Char('s') => {
+                self.toggle_order();
+                Ok(())
+            }
+            Key::Char('r') => self.reload().await,
+            Key::Enter if self.focus == Focus::List => self.open_selected().await,
+            Key::Escape | Key::Backspace | Key::Left | Key::Char('h') => self.back().await,
+            Key::Ctrl('l') => {
+                eprint!("\x1b[2J");
+                Ok(())
+            }
+            key => {
+                self.move_by(key);
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            self.message = Some(format!("Error: {}", report::describe(&e)));
+        }
+        true
+    }
+
+    /// Handles a key while a line is typed in the status line.
+    fn edit_key(&mut self, key: Key) {
+        let Some((edit, text)) = &mut self.edit else {
+            return;
+        };
+        match key {
+            Key::Char(c) => text.push(c),
+            Key::Backspace => {
+                text.pop();
+            }
+            Key::Ctrl('u') => text.clear(),
+            Key::Escape | Key::Ctrl('c') => self.edit = None,
+            Key::Enter => {
+                let (edit, text) = (*edit, text.trim().to_string());
+                self.edit = None;
+                self.apply(edit, text);
+            }
+            _ => {}
+        }
+    }
+
+    fn apply(&mut self, edit: Edit, text: String) {
+        let text = Some(text).filter(|text| !text.is_empty());
+        match edit {
+            Edit::Search => {
+                self.search = text;
+                self.scroll = 0;
+            }
+            Edit::Filter => {
+                self.filter_pattern = text;
+                self.restart();
+            }
+            Edit::Time => match parse_range(text.as_deref().unwrap_or("10m")) {
+                Ok((since, until)) => {
+                    self.since = since;
+                    self.until = until;
+                    self.restart();
+                }
+                Err(e) => self.message = Some(format!("Error: {}", e)),
+            },
+        }
+    }
+
+    fn toggle_order(&mut self) {
+        self.order = match self.order {
+            StreamOrder::LastEvent => StreamOrder::Name,
+            StreamOrder::Name => StreamOrder::LastEvent,
+        };
+        if let List::Streams { streams, .. } = &mut self.list {
+            match self.order {
+                StreamOrder::Name => sort_by_name(streams),
+                StreamOrder::LastEvent => {
+                    streams.sort_by_key(|stream| std::cmp::Reverse(stream.last_event_timestamp()))
+                }
+            }
+        }
+    }
+
+    /// Lists the groups or streams again and reads the events from the start.
+    async fn reload(&mut self) -> Result<(), aws_sdk_cloudwatchlogs::Error> {
+        let selected = self.selected;
+        match &self.list {
+            List::Groups(_) => self.load_groups().await?,
+            List::Streams { group, .. } => {
+                let group = group.clone();
+                let streams = self.load_streams(&group).await?;
+                self.list = List::Streams { group, streams };
+            }
+        }
+        self.selected = selected.min(self.list.len().saturating_sub(1));
+        self.restart();
+        Ok(())
+    }
+
+    async fn open_selected(&mut self) -> Result<(), aws_sdk_cloudwatchlogs::Error> {
+        match &self.list {
+            List::Groups(groups) => match groups.get(self.selected) {
+                Some(group) => self.open_group(group.clone()).await,
+                None => Ok(()),
+            },
+            List::Streams { group, streams } => {
+                let stream = match self.selected {
+                    0 => None,
+                    i => streams[i - 1].log_stream_name().map(str::to_string),
+                };
+                self.follow(group.clone(), stream);
+                self.focus = Focus::Events;
+                Ok(())
+            }
+        }
+    }
+
+    /// Goes from the events to the list, and from the streams back to the log groups.
+    async fn back(&mut self) -> Result<(), aws_sdk_cloudwatchlogs::Error> {
+        if self.focus == Focus::Events {
+            self.focus = Focus::List;
+            return Ok(());
+        }
+        let List::Streams { group, .. } = &self.list else {
+            return Ok(());
+        };
+        let group = group.clone();
+        self.load_groups().await?;
+        if let List::Groups(groups) = &self.list {
+            self.selected = groups.iter().position(|name| *name == group).unwrap_or(0);
+        }
+        Ok(())
+    }
+
+    fn move_by(&mut self, key: Key) {
+        let (_, rows) = terminal::size();
+        let page = rows.saturating_sub(4).max(1);
+        match self.focus {
+            Focus::List => {
+                let last = self.list.len().saturating_sub(1);
+                self.selected = match key {
+                    Key::Up | Key::Char('k') => self.selected.saturating_sub(1),
+                    Key::Down | Key::Char('j') => (self.selected + 1).min(last),
+                    Key::PageUp => self.selected.saturating_sub(page),
+                    Key::PageDown => (self.selected + page).min(last),
+                    Key::Home | Key::Char('g') => 0,
+                    Key::End | Key::Char('G') => last,
+                    _ => self.selected,
+                };
+            }
+            Focus::Events => {
+                let last = self.shown_events().len().saturating_sub(1);
+                self.scroll = match key {
+                    Key::Up | Key::Char('k') => (self.scroll + 1).min(last),
+                    Key::Down | Key::Char('j') => self.scroll.saturating_sub(1),
+                    Key::PageUp => (self.scroll + page).min(last),
+                    Key::PageDown => self.scroll.saturating_sub(page),
+                    Key::Home | Key::Char('g') => last,
+                    Key::End | Key::Char('G') => 0,
+                    _ => self.scroll,
+                };
+            }
+        }
+    }
+
+    /// The events that match the search, the oldest first.
+    fn shown_events(&self) -> Vec<&LogEvent> {
+        self.source
+            .iter()
+            .flat_map(|source| &source.events)
+            .filter(|event| matches(&self.search, event))
+            .collect()
+    }
+
+    fn draw(&self) {
+        let (cols, rows) = terminal::size();
+        let body = rows.saturating_sub(2);
+        let left_width = (cols / 3).clamp(20, 48).min(cols.saturating_sub(2));
+        let right_width = cols.saturating_sub(left_width + 1);
+        let left = self.list_lines(body, left_width);
+        let right = self.event_lines(body, right_width);
+
+        let mut frame = String::from("\x1b[H");
+        frame.push_str(&format!(
+            "{}{}{}\r\n",
+            REVERSE,
+            fit(&self.header(), cols),
+            RESET
+        ));
+        for (left, right) in left.iter().zip(&right) {
+            frame.push_str(&format!("{}│{}\x1b[K\r\n", left, right));
+        }
+        // 最後の行で改行すると画面がスクロールするので、カーソルはその行に残す
+        match &self.edit {
+            Some((edit, text)) => {
+                let line = format!("{}: {}", edit.label(), text);
+                frame.push_str(&format!("{}\x1b[K\x1b[?25h", fit(&line, cols).trim_end()));
+            }
+            None => {
+                let line = self.message.as_deref().unwrap_or(HELP);
+                frame.push_str(&format!("\x1b[?25l{}\x1b[K", fit(line, cols)));
+            }
+        }
+        let mut stderr = io::stderr().lock();
+        let _ = stderr.write_all(frame.as_bytes());
+        let _ = stderr.flush();
+    }
+
+    /// What is followed, for the top line.
+    fn header(&self) -> String {
+        let mut header = match &self.source {
+            Some(Source {
+                group,
+                stream: Some(stream),
+                ..
+            }) => format!(" {} › {}", group, stream),
+            Some(source) => format!(" {} › all streams", source.group),
+            None => " Log groups".to_string(),
+        };
+        header.push_str(&format!("   from {}", format_timestamp(self.since)));
+        match self.until {
+            Some(until) => header.push_str(&format!(" to {}", format_timestamp(until))),
+            None => header.push_str(", following"),
+        }
+        if let Some(pattern) = &self.filter_pattern {
+            header.push_str(&format!("   filter: {}", pattern));
+        }
+        if let Some(search) = &self.search {
+            header.push_str(&format!("   search: {}", search));
+        }
+        header
+    }
+
+    /// The rows of the left pane, each `width` wide.
+    fn list_lines(&self, height: usize, width: usize) -> Vec<String> {
+        let (title, rows): (String, Vec<(String, String)>) = match &self.list {
+            List::Groups(groups) => (
+                format!("Log groups ({})", groups.len()),
+                groups
+                    .iter()
+                    .map(|group| (group.clone(), String::new()))
+                    .collect(),
+            ),
+            List::Streams { group, streams } => {
+                let order = match self.order {
+                    StreamOrder::LastEvent => "by last event",
+                    StreamOrder::Name => "by name",
+                };
+                let now = now_millis();
+                let rows = std::iter::once(("(all streams)".to_string(), String::new()))
+                    .chain(streams.iter().map(|stream| {
+                        let name = stream.log_stream_name().unwrap_or_default().to_string();
+                        let age = stream
+                            .last_event_timestamp()
+                            .map(|last| format_age(now - last))
+                            .unwrap_or_default();
+                        (name, age)
+                    }))
+                    .collect();
+                (format!("{} ({})", group, order), rows)
+            }
+        };
+        let mut lines = vec![format!("{}{}{}", BOLD, fit(&title, width), RESET)];
+        let visible = height.saturating_sub(1);
+        let top = (self.selected + 1).saturating_sub(visible);
+        for (i, (name, age)) in rows.iter().enumerate().skip(top).take(visible) {
+            let name_width = width.saturating_sub(age.chars().count() + 1);
+            let row = format!("{} {}", fit(name, name_width), age);
+            let row = fit(&row, width);
+            let style = match (i == self.selected, self.focus) {
+                (true, Focus::List) => REVERSE,
+                (true, Focus::Events) => BOLD,
+                (false, _) => "",
+            };
+            match style.is_empty() {
+                true => lines.push(row),
+                false => lines.push(format!("{}{}{}", style, row, RESET)),
+            }
+        }
+        lines.resize(height, " ".repeat(width));
+        lines
+    }
+
+    /// The rows of the right pane: the newest events that fit, or as scrolled up.
+    fn event_lines(&self, height: usize, width: usize) -> Vec<String> {
+        let events = self.shown_events();
+        let end = events.len().saturating_sub(self.scroll);
+        let start = end.saturating_sub(height);
+        let all_streams = self
+            .source
+            .as_ref()
+            .is_some_and(|source| source.stream.is_none());
+        let mut lines: Vec<String> = events[start..end]
+            .iter()
+            .map(|event| {
+                let timestamp = format_timestamp(event.timestamp);
+                let mut rest = String::new();
+                if all_streams {
+                    rest.push_str(event.stream_name.as_deref().unwrap_or_default());
+                    rest.push(' ');
+                }
+                rest.push_str(&event.message);
+                let rest = fit(&rest, width.saturating_sub(timestamp.chars().count() + 1));
+                let rest = rest.trim_end();
+                match self.color {
+                    true => format!("{} {}", color::timestamp(&timestamp), color::message(rest)),
+                    false => format!("{} {}", timestamp, rest),
+                }
+            })
+            .collect();
+        if lines.is_empty() {
+            let text = match &self.source {
+                None => "Select a log group and press Enter.",
+                Some(source) if source.done || !source.events.is_empty() => "No matching events.",
+                Some(_) => "Waiting for events...",
+            };
+            lines.push(fit(text, width).trim_end().to_string());
+        }
+        lines.resize(height, String::new());
+        lines
+    }
+}
+
+fn matches(search: &Option<String>, event: &LogEvent) -> bool {
+    search
+        .as_ref()
+        .is_none_or(|search| event.message.contains(search.as_str()))
+}
+
+fn sort_by_name(streams: &mut [LogStream]) {
+    streams.sort_by(|a, b| a.log_stream_name().cmp(&b.log_stream_name()));
+}
+
+/// `2h` or `2h..1h`: where to start and, if given, where to stop.
+fn parse_range(text: &str) -> Result<(i64, Option<i64>), String> {
+    match text.split_once("..") {
+        Some((since, until)) => Ok((
+            parse_timestamp(since.trim())?,
+            Some(parse_timestamp(until.trim())?),
+        )),
+        None => Ok((parse_timestamp(text)?, None)),
+    }
+}
+
+/// How long ago an event was, such as `42s`, `5m` or `3d`.
+fn format_age(millis: i64) -> String {
+    let seconds = millis.max(0) / 1000;
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m", seconds / 60),
+        3600..=86399 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
+
+/// `text` cut or padded with spaces to `width` columns, with tabs and newlines as spaces.
+fn fit(text: &str, width: usize) -> String {
+    let mut fitted: String = text
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .take(width)
+        .collect();
+    let len = fitted.chars().count();
+    fitted.extend(std::iter::repeat_n(' ', width - len));
+    fitted
+}
@@ -0,0 +1,198 @@
+//! `--checkpoint-file` for `tail` and `get-events`: the last delivered event of each stream is
+//! recorded after every batch that is printed, so that the next run with the same file resumes
+//! right after it, without printing an event twice or skipping one.
+//!
+//! Each stream is recorded with the timestamp of its last printed event and the IDs of the events
+//! printed at that timestamp, as several events can share a millisecond:
+//!
+//! ```json
+//! {"streams":[{"group":"/aws/lambda/api","stream":"2024/05/01/[$LATEST]abc","timestamp":1714521600000,"seen":["3780..."]}]}
+//! ```
+//!
+//! GetLogEvents returns no event IDs, so the events it reads are told apart by a hash of their message.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::events::LogEvent;
+use crate::jq::Json;
+
+/// Where a stream has been read up to.
+#[derive(Debug, Default)]
+struct Position {
+    timestamp: i64,
+    /// The events printed at `timestamp`.
+    seen: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+pub struct Checkpoint {
+    path: PathBuf,
+    /// By log group and stream name.
+    streams: BTreeMap<(String, String), Position>,
+}
+
+/// What tells an event apart from the others of its stream at the same time.
+fn event_key(event: &LogEvent) -> String {
+    match &event.event_id {
+        Some(id) => id.clone(),
+        None => {
+            // FNV-1a
+            let hash = event
+                .message
+                .bytes()
+                .fold(0xcbf29ce484222325u64, |hash, byte| {
+                    (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+                });
+            format!("#{:016x}", hash)
+        }
+    }
+}
+
+impl Checkpoint {
+    /// Reads the checkpoint file, or starts an empty one if it does not exist yet.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut checkpoint = Checkpoint {
+            path: path.to_path_buf(),
+            streams: BTreeMap::new(),
+        };
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(checkpoint),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        let invalid = || format!("{} is not a checkpoint file", path.display());
+        let json = Json::parse(&text).map_err(|e| format!("{}: {}", invalid(), e))?;
+        let Some(Json::Array(streams)) = json.get_path("streams") else {
+            return Err(invalid());
+        };
+        for stream in streams {
+            let (
+                Some(Json::String(group)),
+                Some(Json::String(name)),
+                Some(Json::Number(timestamp)),
+                Some(Json::Array(seen)),
+            ) = (
+                stream.get_path("group"),
+                stream.get_path("stream"),
+                stream.get_path("timestamp"),
+                stream.get_path("seen"),
+            )
+            else {
+                return Err(invalid());
+            };
+            let seen = seen
+                .iter()
+                .map(|id| match id {
+                    Json::String(id) => Some(id.clone()),
+                    _ => None,
+                })
+                .collect::<Option<_>>()
+                .ok_or_else(invalid)?;
+            let position = Position {
+                timestamp: *timestamp as i64,
+                seen,
+            };
+            checkpoint
+                .streams
+                .insert((group.clone(), name.clone()), position);
+        }
+        Ok(checkpoint)
+    }
+
+    /// The time of the last printed event of `group`, among `streams` if any are named,
+    /// and the events printed at that time. `None` if none of them has been read.
+    pub fn resume_group(&self, group: &str, streams: &[String]) -> Option<(i64, Vec<String>)> {
+        let positions: Vec<&Position> = self
+            .streams
+            .iter()
+            .filter(|((g, stream), _)| {
+                g == group && (streams.is_empty() || streams.contains(stream))
+            })
+            .map(|(_, position)| position)
+            .collect();
+        let timestamp = positions.iter().map(|position| position.timestamp).max()?;
+        let seen = positions
+            .iter()
+            .filter(|position| position.timestamp == timestamp)
+            .flat_map(|position| position.seen.iter().cloned())
+            .collect();
+        Some((timestamp, seen))
+    }
+
+    /// The time of the last printed event of a stream, to read it again from.
+    pub fn resume_stream(&self, group: &str, stream: &str) -> Option<i64> {
+        self.streams
+            .get(&(group.to_string(), stream.to_string()))
+            .map(|position| position.timestamp)
+    }
+
+    /// Drops the events of `group` that were printed before the checkpoint was written.
+    pub fn retain_new(&self, group: &str, events: &mut Vec<LogEvent>) {
+        events.retain(|event| {
+            let stream = event.stream_name.clone().unwrap_or_default();
+            match self.streams.get(&(group.to_string(), stream)) {
+                Some(position) => {
+                    event.timestamp > position.timestamp
+                        || (event.timestamp == position.timestamp
+                            && !position.seen.contains(&event_key(event)))
+                }
+                None => true,
+            }
+        });
+    }
+
+    /// Moves the streams of `group` past the events that have been printed.
+    pub fn advance(&mut self, group: &str, events: &[LogEvent]) {
+        for event in events {
+            let stream = event.stream_name.clone().unwrap_or_default();
+            let position = self.streams.entry((group.to_string(), stream)).or_default();
+            if event.timestamp > position.timestamp {
+                position.timestamp = event.timestamp;
+                position.seen.clear();
+            }
+            if event.timestamp == position.timestamp {
+                position.seen.insert(event_key(event));
+            }
+        }
+    }
+
+    /// Writes the checkpoint file, replacing it only once the new one is complete.
+    /// Fails if it cannot be written, as the next run would print the same events again.
+    pub fn save(&self) -> Result<(), String> {
+        let streams = self
+            .streams
+            .iter()
+            .map(|((group, stream), position)| {
+                Json::Object(vec![
+                    ("group".to_string(), Json::String(group.clone())),
+                    ("stream".to_string(), Json::String(stream.clone())),
+                    (
+                        "timestamp".to_string(),
+                        Json::Number(position.timestamp as f64),
+                    ),
+                    (
+                        "seen".to_string(),
+                        Json::Array(position.seen.iter().cloned().map(Json::String).collect()),
+                    ),
+                ])
+            })
+            .collect();
+        let text = Json::Object(vec![("streams".to_string(), Json::Array(streams))]).to_json();
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        let write = || -> io::Result<()> {
+            fs::write(&temporary, format!("{}\n", text))?;
+            fs::rename(&temporary, &self.path)
+        };
+        write().map_err(|e| {
+            format!(
+                "Failed to write the checkpoint {}: {}",
+                self.path.display(),
+                e
+            )
+        })
+    }
+}
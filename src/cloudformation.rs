@@ -0,0 +1,102 @@
+//! Finds the log groups of a CloudFormation stack. The CloudFormation SDK is not part of this build,
+//! so its query API is called through [`SignedClient`].
+
+use crate::ecs::task_definition_log_groups;
+use crate::groups::lambda_group;
+use crate::signed::{percent_encode, xml_elements, xml_values, SignedClient};
+use crate::targets::Target;
+
+/// A resource of a stack, as listed by ListStackResources.
+struct StackResource {
+    resource_type: String,
+    physical_id: String,
+}
+
+/// Lists the resources of a stack with ListStackResources, skipping the deleted ones.
+async fn list_stack_resources(
+    client: &SignedClient,
+    stack: &str,
+) -> Result<Vec<StackResource>, String> {
+    let url = match client.endpoint_url() {
+        Some(endpoint_url) => format!("{}/", endpoint_url),
+        None => format!("https://cloudformation.{}.amazonaws.com/", client.region()),
+    };
+    let mut resources = Vec::new();
+    let mut next_token: Option<String> = None;
+    loop {
+        let mut body = format!(
+            "Action=ListStackResources&Version=2010-05-15&StackName={}",
+            percent_encode(stack, false)
+        );
+        if let Some(token) = &next_token {
+            body.push_str(&format!("&NextToken={}", percent_encode(token, false)));
+        }
+        let headers = [("content-type", "application/x-www-form-urlencoded")];
+        let (status, body) = client
+            .send("cloudformation", "POST", &url, &headers, body.into_bytes())
+            .await?;
+        let body = String::from_utf8_lossy(&body);
+        if status != 200 {
+            let code = xml_values(&body, "Code").into_iter().next();
+            let message = xml_values(&body, "Message").into_iter().next();
+            return Err(format!(
+                "CloudFormation returned {}{}{}",
+                status,
+                code.map(|code| format!(" ({})", code)).unwrap_or_default(),
+                message
+                    .map(|message| format!(": {}", message))
+                    .unwrap_or_default()
+            ));
+        }
+        for member in xml_elements(&body, "member") {
+            let value = |tag| xml_values(member, tag).into_iter().next();
+            // 削除済みのリソースは物理 ID が残っていても使えない
+            if value("ResourceStatus").as_deref() == Some("DELETE_COMPLETE") {
+                continue;
+            }
+            if let (Some(resource_type), Some(physical_id)) =
+                (value("ResourceType"), value("PhysicalResourceId"))
+            {
+                resources.push(StackResource {
+                    resource_type,
+                    physical_id,
+                });
+            }
+        }
+        next_token = xml_values(&body, "NextToken").into_iter().next();
+        if next_token.is_none() {
+            return Ok(resources);
+        }
+    }
+}
+
+/// The log groups that a stack and its nested stacks create or write to: its `AWS::Logs::LogGroup`
+/// resources, and the implicit groups of its Lambda functions and ECS task definitions.
+pub async fn stack_log_groups(target: &Target, stack: &str) -> Result<Vec<String>, String> {
+    let client = SignedClient::new(target)?;
+    let mut groups: Vec<String> = Vec::new();
+    let mut stacks = vec![stack.to_string()];
+    while let Some(stack) = stacks.pop() {
+        for resource in list_stack_resources(&client, &stack).await? {
+            let found = match resource.resource_type.as_str() {
+                "AWS::Logs::LogGroup" => vec![resource.physical_id],
+                "AWS::Lambda::Function" => vec![lambda_group(&resource.physical_id)],
+                "AWS::ECS::TaskDefinition" => {
+                    task_definition_log_groups(target, &resource.physical_id).await?
+                }
+                // 入れ子のスタックの物理 ID はスタックの ARN
+                "AWS::CloudFormation::Stack" => {
+                    stacks.push(resource.physical_id);
+                    Vec::new()
+                }
+                _ => Vec::new(),
+            };
+            for group in found {
+                if !groups.contains(&group) {
+                    groups.push(group);
+                }
+            }
+        }
+    }
+    Ok(groups)
+}
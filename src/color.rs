@@ -0,0 +1,138 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use regex_lite::Regex;
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const CYAN: &str = "\x1b[36m";
+const BRIGHT_RED: &str = "\x1b[1;91m";
+const BRIGHT_YELLOW: &str = "\x1b[1;93m";
+const BRIGHT_GREEN: &str = "\x1b[92m";
+const GRAY: &str = "\x1b[90m";
+const BRIGHT_WHITE: &str = "\x1b[97m";
+
+/// The severity of a log message, as far as it can be told from the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "FATAL" | "CRITICAL" | "PANIC" | "ERROR" | "ERR" => Some(Level::Error),
+            "WARN" | "WARNING" => Some(Level::Warn),
+            "INFO" | "NOTICE" => Some(Level::Info),
+            "DEBUG" | "TRACE" => Some(Level::Debug),
+            _ => None,
+        }
+    }
+
+    fn style(self) -> &'static str {
+        match self {
+            Level::Error => BRIGHT_RED,
+            Level::Warn => BRIGHT_YELLOW,
+            Level::Info => BRIGHT_GREEN,
+            Level::Debug => GRAY,
+        }
+    }
+}
+
+/// Finds the severity of a message: the `level` (or `severity`, `lvl`) field of a JSON message,
+/// otherwise the first upper-case severity word such as `ERROR` or `WARN`.
+pub fn detect_level(message: &str) -> Option<Level> {
+    static JSON_LEVEL: OnceLock<Regex> = OnceLock::new();
+    static LEVEL_WORD: OnceLock<Regex> = OnceLock::new();
+
+    if message.trim_start().starts_with('{') {
+        let json_level = JSON_LEVEL.get_or_init(|| {
+            Regex::new(r#""(?i:level|severity|lvl|log\.level)"\s*:\s*"([A-Za-z]+)""#).unwrap()
+        });
+        if let Some(level) = json_level
+            .captures(message)
+            .and_then(|c| Level::from_name(&c[1]))
+        {
+            return Some(level);
+        }
+    }
+    // 普通の文中の "error" などに反応しないよう、大文字の単語だけを見る
+    let level_word = LEVEL_WORD.get_or_init(|| {
+        Regex::new(r"\b(FATAL|CRITICAL|PANIC|ERROR|ERR|WARNING|WARN|INFO|NOTICE|DEBUG|TRACE)\b")
+            .unwrap()
+    });
+    level_word
+        .captures(message)
+        .and_then(|c| Level::from_name(&c[1]))
+}
+
+/// When output is colored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// When stdout is a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    /// Always, e.g. when piping to `less -R`.
+    Always,
+    /// Never.
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether colored output should be written to stdout.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            // https://no-color.org: 空でない値が設定されていれば色を付けない
+            ColorChoice::Auto => {
+                env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+fn paint(style: &str, text: &str) -> String {
+    format!("{}{}{}", style, text, RESET)
+}
+
+/// A dim timestamp.
+pub fn timestamp(text: &str) -> String {
+    paint(DIM, text)
+}
+
+/// A log stream name.
+pub fn stream_name(text: &str) -> String {
+    paint(CYAN, text)
+}
+
+/// The current log group in the `repl` prompt.
+pub fn prompt(text: &str) -> String {
+    paint(CYAN, text)
+}
+
+/// A line that is new since the previous listing of `--watch`.
+pub fn added(text: &str) -> String {
+    paint(BRIGHT_GREEN, text)
+}
+
+/// A line that is gone since the previous listing of `--watch`.
+pub fn removed(text: &str) -> String {
+    paint(BRIGHT_RED, text)
+}
+
+/// A stream that has had events since the previous listing of `--watch`.
+pub fn changed(text: &str) -> String {
+    paint(BRIGHT_YELLOW, text)
+}
+
+/// A message, in the color of its severity or bright when it has none.
+pub fn message(text: &str) -> String {
+    let style = detect_level(text).map_or(BRIGHT_WHITE, Level::style);
+    paint(style, text)
+}
@@ -0,0 +1,483 @@
+//! Generates shell completion scripts from the clap definition of the command line.
+//! `clap_complete` is not part of this build, so the scripts are written out here.
+//! They complete `--group` from the names that `list-groups` last printed, kept in a local cache.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::{builder::ValueHint, Args, ValueEnum};
+
+use crate::bookmarks::Bookmarks;
+use crate::{out, outln};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+#[derive(Debug, Args)]
+pub struct CompletionsOpt {
+    /// The shell to print the completion script for, e.g. for bash:
+    /// `source <(print-log-stream-names completions bash)`.
+    #[arg(value_enum, required_unless_present = "groups")]
+    pub shell: Option<Shell>,
+
+    /// Prints the bookmarks as `@NAME` and the cached log group names instead.
+    /// The completion scripts call this to complete `--group`.
+    #[arg(long, hide = true, conflicts_with = "shell")]
+    pub groups: bool,
+}
+
+/// What an option's value is completed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Values {
+    /// The option takes no value.
+    Flag,
+    /// Any value, which is not completed.
+    Any,
+    Choices(Vec<String>),
+    Paths,
+    /// The cached log group names.
+    Groups,
+}
+
+#[derive(Debug)]
+struct OptionSpec {
+    /// Such as `-g`, `--group` and the visible aliases.
+    names: Vec<String>,
+    values: Values,
+}
+
+/// The words that can follow a command or a subcommand such as `tags list`.
+#[derive(Debug)]
+struct Level {
+    /// The subcommand names from the top, separated by spaces. Empty for the top level.
+    path: String,
+    /// The subcommands and their aliases, with a one-line description.
+    subcommands: Vec<(String, String)>,
+    /// The choices of the positional arguments, such as the shell of `completions`.
+    positional: Vec<String>,
+    options: Vec<OptionSpec>,
+}
+
+impl Level {
+    /// Every word that is completed when no option value is expected.
+    fn words(&self) -> Vec<&str> {
+        self.subcommands
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .chain(self.positional.iter().map(String::as_str))
+            .chain(
+                self.options
+                    .iter()
+                    .flat_map(|option| option.names.iter().map(String::as_str)),
+            )
+            .collect()
+    }
+
+    /// The options that take a value, which is what the word before the cursor is checked against.
+    fn value_options(&self) -> impl Iterator<Item = &OptionSpec> {
+        self.options
+            .iter()
+            .filter(|option| option.values != Values::Flag)
+    }
+}
+
+/// The subcommands to complete, leaving out the `help` subcommands that clap adds.
+fn visible_subcommands(command: &clap::Command) -> impl Iterator<Item = &clap::Command> {
+    command
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set() && sub.get_name() != "help")
+}
+
+/// Walks the subcommands depth first, after clap has copied the global options into each of them.
+fn levels(command: &clap::Command, path: &str, levels_out: &mut Vec<Level>) {
+    let subcommands = visible_subcommands(command)
+        .flat_map(|sub| {
+            let about = sub
+                .get_about()
+                .map(|about| about.to_string())
+                .unwrap_or_default();
+            let about = about.lines().next().unwrap_or_default().to_string();
+            std::iter::once(sub.get_name())
+                .chain(sub.get_visible_aliases())
+                .map(move |name| (name.to_string(), about.clone()))
+        })
+        .collect();
+    let mut positional = Vec::new();
+    let mut options = Vec::new();
+    for arg in command.get_arguments().filter(|arg| !arg.is_hide_set()) {
+        let choices: Vec<String> = arg
+            .get_possible_values()
+            .iter()
+            .filter(|value| !value.is_hide_set())
+            .map(|value| value.get_name().to_string())
+            .collect();
+        if arg.is_positional() {
+            positional.extend(choices);
+            continue;
+        }
+        let mut names: Vec<String> = Vec::new();
+        names.extend(arg.get_short().map(|short| format!("-{}", short)));
+        names.extend(
+            arg.get_visible_short_aliases()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|short| format!("-{}", short)),
+        );
+        names.extend(arg.get_long().map(|long| format!("--{}", long)));
+        names.extend(
+            arg.get_visible_aliases()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|long| format!("--{}", long)),
+        );
+        let values = if !arg.get_action().takes_values() {
+            Values::Flag
+        } else if arg.get_id() == "group" {
+            Values::Groups
+        } else if !choices.is_empty() {
+            Values::Choices(choices)
+        } else if matches!(
+            arg.get_value_hint(),
+            ValueHint::AnyPath | ValueHint::FilePath | ValueHint::DirPath
+        ) {
+            Values::Paths
+        } else {
+            Values::Any
+        };
+        options.push(OptionSpec { names, values });
+    }
+    levels_out.push(Level {
+        path: path.to_string(),
+        subcommands,
+        positional,
+        options,
+    });
+    for sub in visible_subcommands(command) {
+        let sub_path = match path {
+            "" => sub.get_name().to_string(),
+            _ => format!("{} {}", path, sub.get_name()),
+        };
+        levels(sub, &sub_path, levels_out);
+    }
+}
+
+/// The `parent:child` pairs that move the completion one subcommand down, as the scripts match them.
+fn transitions(levels: &[Level]) -> Vec<String> {
+    levels
+        .iter()
+        .flat_map(|level| {
+            level
+                .subcommands
+                .iter()
+                .map(move |(name, _)| format!("{}:{}", level.path, name))
+        })
+        .collect()
+}
+
+/// Prints the completion script for `shell`, or the cached log group names.
+pub fn run(mut command: clap::Command, opt: CompletionsOpt) {
+    if opt.groups {
+        // ブックマークとお気に入りは @NAME で補完する
+        let bookmarks = match (crate::config::load(), Bookmarks::load()) {
+            (Ok(config), Ok(bookmarks)) => bookmarks.names(config),
+            _ => Vec::new(),
+        };
+        for group in bookmarks.into_iter().chain(cached_groups()) {
+            outln!("{}", group);
+        }
+        return;
+    }
+    command.build();
+    let mut all = Vec::new();
+    levels(&command, "", &mut all);
+    let name = command.get_name().to_string();
+    let script = match opt.shell.unwrap_or(Shell::Bash) {
+        Shell::Bash => bash(&name, &all),
+        Shell::Zsh => zsh(&name, &all),
+        Shell::Fish => fish(&name, &all),
+        Shell::Powershell => powershell(&name, &all),
+    };
+    out!("{}", script);
+}
+
+/// The name of the shell function, which cannot contain `-`.
+fn function_name(name: &str) -> String {
+    format!("_{}", name.replace('-', "_"))
+}
+
+fn bash(name: &str, levels: &[Level]) -> String {
+    let function = function_name(name);
+    let mut script = format!(
+        "{function}() {{\n    \
+         local cur=\"${{COMP_WORDS[COMP_CWORD]}}\" prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\" cmd_path=\"\" word i\n    \
+         for ((i = 1; i < COMP_CWORD; i++)); do\n        \
+         word=\"${{COMP_WORDS[i]}}\"\n        \
+         case \"${{cmd_path}}:${{word}}\" in\n            \
+         {transitions}) cmd_path=\"${{cmd_path:+$cmd_path }}$word\" ;;\n        \
+         esac\n    \
+         done\n    \
+         case \"$cmd_path\" in\n",
+        transitions = quoted_alternatives(&transitions(levels)),
+    );
+    for level in levels {
+        script.push_str(&format!("        \"{}\")\n", level.path));
+        script.push_str("            case \"$prev\" in\n");
+        for option in level.value_options() {
+            let reply = match &option.values {
+                Values::Choices(choices) => {
+                    format!("COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", choices.join(" "))
+                }
+                Values::Paths => "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string(),
+                Values::Groups => format!(
+                    "COMPREPLY=($(compgen -W \"$({} completions --groups 2>/dev/null)\" -- \"$cur\"))",
+                    name
+                ),
+                Values::Any | Values::Flag => "COMPREPLY=()".to_string(),
+            };
+            script.push_str(&format!(
+                "                {}) {}; return ;;\n",
+                option.names.join("|"),
+                reply
+            ));
+        }
+        script.push_str("            esac\n");
+        script.push_str(&format!(
+            "            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            ;;\n",
+            level.words().join(" ")
+        ));
+    }
+    script.push_str(&format!(
+        "    esac\n}}\ncomplete -F {} {}\n",
+        function, name
+    ));
+    script
+}
+
+fn zsh(name: &str, levels: &[Level]) -> String {
+    let function = function_name(name);
+    // zsh の path は PATH と結び付いているので使わない
+    let mut script = format!(
+        "{function}() {{\n    \
+         local cur=${{words[CURRENT]}} prev=${{words[CURRENT-1]}} cmd_path=\"\" word i\n    \
+         for ((i = 2; i < CURRENT; i++)); do\n        \
+         word=${{words[i]}}\n        \
+         case \"${{cmd_path}}:${{word}}\" in\n            \
+         ({transitions}) cmd_path=\"${{cmd_path:+$cmd_path }}$word\" ;;\n        \
+         esac\n    \
+         done\n    \
+         case \"$cmd_path\" in\n",
+        transitions = quoted_alternatives(&transitions(levels)),
+    );
+    for level in levels {
+        script.push_str(&format!("        (\"{}\")\n", level.path));
+        script.push_str("            case \"$prev\" in\n");
+        for option in level.value_options() {
+            let reply = match &option.values {
+                Values::Choices(choices) => format!("compadd -- {}", choices.join(" ")),
+                Values::Paths => "_files".to_string(),
+                Values::Groups => format!(
+                    "compadd -- ${{(f)\"$({} completions --groups 2>/dev/null)\"}}",
+                    name
+                ),
+                Values::Any | Values::Flag => ":".to_string(),
+            };
+            script.push_str(&format!(
+                "                ({}) {}; return ;;\n",
+                option.names.join("|"),
+                reply
+            ));
+        }
+        script.push_str("            esac\n");
+        script.push_str(&format!(
+            "            compadd -- {}\n            ;;\n",
+            level.words().join(" ")
+        ));
+    }
+    script.push_str(&format!("    esac\n}}\ncompdef {} {}\n", function, name));
+    script
+}
+
+fn fish(name: &str, levels: &[Level]) -> String {
+    let at = format!("__{}_at", name.replace('-', "_"));
+    let transitions: Vec<String> = transitions(levels)
+        .iter()
+        .map(|transition| format!("'{}'", transition))
+        .collect();
+    let mut script = format!(
+        "function {at}\n    \
+         set -l cmd_path ''\n    \
+         for word in (commandline -opc)[2..-1]\n        \
+         switch \"$cmd_path:$word\"\n            \
+         case {transitions}\n                \
+         set cmd_path (string trim -- \"$cmd_path $word\")\n        \
+         end\n    \
+         end\n    \
+         test \"$cmd_path\" = \"$argv[1]\"\n\
+         end\n\n\
+         complete -c {name} -f\n",
+        transitions = transitions.join(" "),
+    );
+    for level in levels {
+        let condition = format!("-n '{} \"{}\"'", at, level.path);
+        for (subcommand, about) in &level.subcommands {
+            script.push_str(&format!(
+                "complete -c {} {} -a {} -d '{}'\n",
+                name,
+                condition,
+                subcommand,
+                about.replace('\\', "\\\\").replace('\'', "\\'")
+            ));
+        }
+        if !level.positional.is_empty() {
+            script.push_str(&format!(
+                "complete -c {} {} -a '{}'\n",
+                name,
+                condition,
+                level.positional.join(" ")
+            ));
+        }
+        for option in &level.options {
+            let mut line = format!("complete -c {} {}", name, condition);
+            for option_name in &option.names {
+                match option_name.strip_prefix("--") {
+                    Some(long) => line.push_str(&format!(" -l {}", long)),
+                    None => line.push_str(&format!(" -s {}", &option_name[1..])),
+                }
+            }
+            match &option.values {
+                Values::Flag => {}
+                Values::Any => line.push_str(" -r"),
+                Values::Choices(choices) => {
+                    line.push_str(&format!(" -r -a '{}'", choices.join(" ")))
+                }
+                Values::Paths => line.push_str(" -r -F"),
+                Values::Groups => line.push_str(&format!(
+                    " -r -a '({} completions --groups 2>/dev/null)'",
+                    name
+                )),
+            }
+            script.push_str(&line);
+            script.push('\n');
+        }
+    }
+    script
+}
+
+fn powershell(name: &str, levels: &[Level]) -> String {
+    let list = |words: &[&str]| {
+        let quoted: Vec<String> = words
+            .iter()
+            .map(|word| format!("'{}'", word.replace('\'', "''")))
+            .collect();
+        format!("@({})", quoted.join(", "))
+    };
+    let mut subcommands = String::new();
+    let mut words = String::new();
+    let mut values = String::new();
+    for level in levels {
+        let names: Vec<&str> = level
+            .subcommands
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        subcommands.push_str(&format!("        '{}' = {}\n", level.path, list(&names)));
+        words.push_str(&format!(
+            "        '{}' = {}\n",
+            level.path,
+            list(&level.words())
+        ));
+        for option in level.value_options() {
+            let completion = match &option.values {
+                Values::Choices(choices) => {
+                    list(&choices.iter().map(String::as_str).collect::<Vec<_>>())
+                }
+                Values::Paths => "'@paths'".to_string(),
+                Values::Groups => "'@groups'".to_string(),
+                Values::Any | Values::Flag => "@()".to_string(),
+            };
+            for option_name in &option.names {
+                values.push_str(&format!(
+                    "        '{}|{}' = {}\n",
+                    level.path, option_name, completion
+                ));
+            }
+        }
+    }
+    format!(
+        "Register-ArgumentCompleter -Native -CommandName '{name}' -ScriptBlock {{\n    \
+         param($wordToComplete, $commandAst, $cursorPosition)\n    \
+         $subcommands = @{{\n{subcommands}    }}\n    \
+         $words = @{{\n{words}    }}\n    \
+         $values = @{{\n{values}    }}\n    \
+         $typed = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object {{ $_.ToString() }})\n    \
+         if ($wordToComplete -ne '' -and $typed.Count -gt 0) {{ $typed = @($typed | Select-Object -SkipLast 1) }}\n    \
+         $path = ''\n    \
+         foreach ($word in $typed) {{\n        \
+         if ($subcommands[$path] -contains $word) {{ $path = \"$path $word\".Trim() }}\n    \
+         }}\n    \
+         $prev = if ($typed.Count -gt 0) {{ $typed[-1] }} else {{ '' }}\n    \
+         $key = \"$path|$prev\"\n    \
+         $candidates = if ($values.ContainsKey($key)) {{\n        \
+         switch ($values[$key]) {{\n            \
+         '@groups' {{ & '{name}' completions --groups 2>$null }}\n            \
+         '@paths' {{ return }}\n            \
+         default {{ $_ }}\n        \
+         }}\n    \
+         }} else {{ $words[$path] }}\n    \
+         $candidates | Where-Object {{ $_ -like \"$wordToComplete*\" }} | ForEach-Object {{\n        \
+         [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)\n    \
+         }}\n\
+         }}\n",
+    )
+}
+
+/// `pattern|pattern|...` with each pattern in double quotes, for a `case` branch.
+fn quoted_alternatives(patterns: &[String]) -> String {
+    patterns
+        .iter()
+        .map(|pattern| format!("\"{}\"", pattern))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// `$XDG_CACHE_HOME/print-log-stream-names/groups`, or the same under `~/.cache`.
+fn cache_path() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_CACHE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(dir.join("print-log-stream-names").join("groups"))
+}
+
+fn cached_groups() -> Vec<String> {
+    cache_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|text| text.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Adds the listed log group names to the cache that completes `--group`.
+/// Groups of every profile and Region share one cache. Failing to write it is not worth a warning.
+pub fn remember_groups<'a>(names: impl IntoIterator<Item = &'a str>) {
+    let Some(path) = cache_path() else {
+        return;
+    };
+    let mut groups: BTreeSet<String> = cached_groups().into_iter().collect();
+    let before = groups.len();
+    groups.extend(names.into_iter().map(str::to_string));
+    if groups.len() == before {
+        return;
+    }
+    let mut text = groups.into_iter().collect::<Vec<_>>().join("\n");
+    text.push('\n');
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = fs::write(path, text);
+}
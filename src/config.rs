@@ -0,0 +1,460 @@
+//! The defaults read from `~/.config/print-log-streams/config.toml`, such as:
+//!
+//! ```toml
+//! region = "eu-west-1"
+//! profile = "dev"
+//! output = "table"
+//! color = "always"
+//! tz = "Asia/Tokyo"
+//! timestamps = "relative"
+//!
+//! # Favorite log groups, given as `-g @api`
+//! [groups]
+//! api = "/aws/lambda/orders-api"
+//! web = ["/ecs/web", "/ecs/web-worker"]
+//! ```
+//!
+//! A flag on the command line wins over an environment variable (`AWS_REGION`, `AWS_DEFAULT_REGION`,
+//! `AWS_PROFILE` and `NO_COLOR`), which wins over the file, which wins over the profile's Region
+//! and the built-in defaults. No TOML parser is part of this build, so only strings and arrays of
+//! strings are read, which is all the settings need.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+
+use crate::color::ColorChoice;
+use crate::output::{OutputFormat, TimestampStyle};
+use crate::tz::TimeZone;
+
+/// The settings of the configuration file, all unset without one.
+#[derive(Debug, Default)]
+pub struct Config {
+    path: Option<PathBuf>,
+    region: Option<String>,
+    profile: Option<String>,
+    output: Option<String>,
+    color: Option<String>,
+    tz: Option<String>,
+    timestamps: Option<String>,
+    favorites: BTreeMap<String, Vec<String>>,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Reads the configuration file once: `$PRINT_LOG_STREAMS_CONFIG`, otherwise
+/// `$XDG_CONFIG_HOME/print-log-streams/config.toml` or the same under `~/.config`.
+/// A missing file is an empty configuration, unless `$PRINT_LOG_STREAMS_CONFIG` names it.
+pub fn load() -> Result<&'static Config, String> {
+    if let Some(config) = CONFIG.get() {
+        return Ok(config);
+    }
+    let explicit = env::var_os("PRINT_LOG_STREAMS_CONFIG").filter(|path| !path.is_empty());
+    let required = explicit.is_some();
+    let config = match explicit.map(PathBuf::from).or_else(default_path) {
+        None => Config::default(),
+        Some(path) => match fs::read_to_string(&path) {
+            Ok(text) => Config::parse(&text)
+                .map(|config| Config {
+                    path: Some(path.clone()),
+                    ..config
+                })
+                .map_err(|e| format!("{}: {}", path.display(), e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => Config::default(),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        },
+    };
+    Ok(CONFIG.get_or_init(|| config))
+}
+
+/// `$XDG_CONFIG_HOME/print-log-streams`, or the same under `~/.config`.
+pub(crate) fn dir() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("print-log-streams"))
+}
+
+fn default_path() -> Option<PathBuf> {
+    Some(dir()?.join("config.toml"))
+}
+
+/// Whether an environment variable overrides the file, i.e. it is set and not empty.
+fn is_set(name: &str) -> bool {
+    env::var_os(name).is_some_and(|value| !value.is_empty())
+}
+
+impl Config {
+    /// The file that was read, if any.
+    pub fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    /// The `output` setting, for `query` whose `--output` defaults to the `--output-file` extension
+    /// before the file.
+    pub fn output(&self) -> Option<OutputFormat> {
+        self.output
+            .as_deref()
+            .and_then(|output| OutputFormat::from_str(output, false).ok())
+    }
+
+    /// The names of the favorite groups, without `@`.
+    pub fn favorites(&self) -> impl Iterator<Item = &str> {
+        self.favorites.keys().map(String::as_str)
+    }
+
+    /// The favorite groups of a name, given as `-g @NAME`.
+    pub fn favorite(&self, name: &str) -> Option<&[String]> {
+        self.favorites.get(name).map(Vec::as_slice)
+    }
+
+    /// Makes the settings the defaults of the command line, so that any flag given wins.
+    /// `region`, `profile` and `color` are left out when their environment variables are set.
+    pub fn apply(&'static self, mut command: clap::Command) -> clap::Command {
+        if let Some(region) = &self.region {
+            if !is_set("AWS_REGION") && !is_set("AWS_DEFAULT_REGION") {
+                command = command.mut_arg("region", |arg| arg.default_value(region.as_str()));
+            }
+        }
+        if let Some(profile) = &self.profile {
+            if !is_set("AWS_PROFILE") {
+                command =
+                    command.mut_arg("profile_name", |arg| arg.default_value(profile.as_str()));
+            }
+        }
+        if let Some(color) = &self.color {
+            if !is_set("NO_COLOR") {
+                command = command.mut_arg("color", |arg| arg.default_value(color.as_str()));
+            }
+        }
+        if let Some(tz) = &self.tz {
+            command = command.mut_arg("tz", |arg| arg.default_value(tz.as_str()));
+        }
+        if let Some(timestamps) = &self.timestamps {
+            command = command.mut_arg("timestamps", |arg| arg.default_value(timestamps.as_str()));
+        }
+        self.apply_output(command)
+    }
+
+    /// Makes the `output` setting the default `--output` of the subcommands.
+    pub fn apply_output(&'static self, command: clap::Command) -> clap::Command {
+        match &self.output {
+            Some(output) => with_default_output(command, output),
+            None => command,
+        }
+    }
+
+    fn parse(text: &str) -> Result<Config, String> {
+        let mut config = Config::default();
+        for entry in parse_entries(text)? {
+            let line = entry.line;
+            match (entry.table.as_deref(), entry.key.as_str()) {
+                (None, "region") => config.region = Some(entry.string()?),
+                (None, "profile") => config.profile = Some(entry.string()?),
+                (None, "output") => config.output = Some(entry.choice::<OutputFormat>()?),
+                (None, "color") => config.color = Some(entry.choice::<ColorChoice>()?),
+                (None, "timestamps") => config.timestamps = Some(entry.choice::<TimestampStyle>()?),
+                (None, "tz") => {
+                    let tz = entry.string()?;
+                    TimeZone::parse(&tz).map_err(|e| format!("line {}: {}", line, e))?;
+                    config.tz = Some(tz);
+                }
+                (Some("groups"), _) => {
+                    let groups = entry.strings()?;
+                    if groups.is_empty() {
+                        return Err(format!("line {}: `{}` has no groups", line, entry.key));
+                    }
+                    config.favorites.insert(entry.key, groups);
+                }
+                (None, key) => return Err(format!("line {}: unknown setting `{}`", line, key)),
+                (Some(table), _) => {
+                    return Err(format!("line {}: unknown table `[{}]`", line, table))
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Sets the default of every `--output` with a default of its own, at every level of subcommands.
+fn with_default_output(mut command: clap::Command, output: &'static str) -> clap::Command {
+    let has_default = command
+        .get_arguments()
+        .any(|arg| arg.get_id() == "output" && !arg.get_default_values().is_empty());
+    if has_default {
+        command = command.mut_arg("output", |arg| arg.default_value(output));
+    }
+    let names: Vec<String> = command
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect();
+    for name in names {
+        command =
+            command.mut_subcommand(name, |subcommand| with_default_output(subcommand, output));
+    }
+    command
+}
+
+/// Reads the `key = value` lines of a file in the subset of TOML that the settings use.
+pub(crate) fn parse_entries(text: &str) -> Result<Vec<Entry>, String> {
+    Parser::new(text).entries()
+}
+
+/// A TOML basic string, escaping what [`parse_entries`] unescapes.
+pub(crate) fn quote(s: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+pub(crate) enum Value {
+    String(String),
+    Array(Vec<String>),
+}
+
+/// A `key = value` line, under the `[table]` header before it if any.
+pub(crate) struct Entry {
+    pub table: Option<String>,
+    pub key: String,
+    pub value: Value,
+    pub line: usize,
+}
+
+impl Entry {
+    pub fn string(&self) -> Result<String, String> {
+        match &self.value {
+            Value::String(s) => Ok(s.clone()),
+            Value::Array(_) => Err(format!(
+                "line {}: `{}` must be a string, not an array",
+                self.line, self.key
+            )),
+        }
+    }
+
+    /// A string or an array of strings.
+    pub fn strings(&self) -> Result<Vec<String>, String> {
+        match &self.value {
+            Value::String(s) => Ok(vec![s.clone()]),
+            Value::Array(values) => Ok(values.clone()),
+        }
+    }
+
+    /// A string that must name one of the values of the flag of the same name.
+    pub fn choice<T: ValueEnum>(&self) -> Result<String, String> {
+        let value = self.string()?;
+        if T::from_str(&value, false).is_ok() {
+            return Ok(value);
+        }
+        let names: Vec<String> = T::value_variants()
+            .iter()
+            .filter_map(|variant| variant.to_possible_value())
+            .map(|possible| possible.get_name().to_string())
+            .collect();
+        Err(format!(
+            "line {}: `{}` is not a valid {}, expected one of {}",
+            self.line,
+            value,
+            self.key,
+            names.join(", ")
+        ))
+    }
+}
+
+/// Reads `[table]` headers and `key = value` lines, with basic and literal strings,
+/// arrays of strings that may span lines, and `#` comments.
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn new(text: &str) -> Self {
+        Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: impl std::fmt::Display) -> String {
+        format!("line {}: {}", self.line, message)
+    }
+
+    /// Skips spaces and comments, and line breaks too if `newlines` is set.
+    fn skip_space(&mut self, newlines: bool) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' => {}
+                '\n' if newlines => {}
+                '#' => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.pos += 1;
+                    }
+                    continue;
+                }
+                _ => break,
+            }
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("expected `{}`, found `{}`", expected, c))),
+            None => Err(self.error(format!("expected `{}`", expected))),
+        }
+    }
+
+    fn entries(mut self) -> Result<Vec<Entry>, String> {
+        let mut entries = Vec::new();
+        let mut table = None;
+        loop {
+            self.skip_space(true);
+            match self.peek() {
+                None => return Ok(entries),
+                Some('[') => {
+                    self.next();
+                    self.skip_space(false);
+                    table = Some(self.key()?);
+                    self.skip_space(false);
+                    self.expect(']')?;
+                }
+                Some(_) => {
+                    let line = self.line;
+                    let key = self.key()?;
+                    self.skip_space(false);
+                    self.expect('=')?;
+                    self.skip_space(false);
+                    let value = self.value()?;
+                    entries.push(Entry {
+                        table: table.clone(),
+                        key,
+                        value,
+                        line,
+                    });
+                }
+            }
+            self.skip_space(false);
+            match self.peek() {
+                None | Some('\n') => {}
+                Some(c) => return Err(self.error(format!("unexpected `{}` after the value", c))),
+            }
+        }
+    }
+
+    /// A bare key such as `orders-api`, or a quoted one.
+    fn key(&mut self) -> Result<String, String> {
+        if matches!(self.peek(), Some('"' | '\'')) {
+            return self.string();
+        }
+        let mut key = String::new();
+        while let Some(c) = self
+            .peek()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        {
+            key.push(c);
+            self.next();
+        }
+        match key.is_empty() {
+            true => Err(self.error("expected a key")),
+            false => Ok(key),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        if self.peek() != Some('[') {
+            return self.string().map(Value::String);
+        }
+        self.next();
+        let mut values = Vec::new();
+        loop {
+            self.skip_space(true);
+            if self.peek() == Some(']') {
+                self.next();
+                return Ok(Value::Array(values));
+            }
+            values.push(self.string()?);
+            self.skip_space(true);
+            match self.next() {
+                Some(',') => {}
+                Some(']') => return Ok(Value::Array(values)),
+                _ => return Err(self.error("expected `,` or `]` in the array")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let quote = match self.peek() {
+            Some(c @ ('"' | '\'')) => c,
+            _ => return Err(self.error("expected a quoted string, e.g. `region = \"eu-west-1\"`")),
+        };
+        self.next();
+        let mut s = String::new();
+        loop {
+            let c = match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some(c) => c,
+            };
+            self.next();
+            match c {
+                c if c == quote => return Ok(s),
+                // '...' はエスケープを解釈しない
+                '\\' if quote == '"' => s.push(self.escape()?),
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        let digits = match self.next() {
+            Some('n') => return Ok('\n'),
+            Some('t') => return Ok('\t'),
+            Some('r') => return Ok('\r'),
+            Some('"') => return Ok('"'),
+            Some('\\') => return Ok('\\'),
+            Some('u') => 4,
+            Some('U') => 8,
+            _ => return Err(self.error("invalid escape in the string")),
+        };
+        let hex: String = (0..digits).filter_map(|_| self.next()).collect();
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(format!("invalid unicode escape `{}`", hex)))
+    }
+}
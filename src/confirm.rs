@@ -0,0 +1,62 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+
+use clap::Args;
+
+use crate::error::Error;
+
+/// How many affected resources are listed in the prompt before the rest are counted.
+const MAX_LISTED: usize = 20;
+
+/// What the user has to type to go ahead.
+const CONFIRMATION: &str = "yes";
+
+/// Options for commands that delete or change resources.
+#[derive(Debug, Clone, Default, Args)]
+pub struct ConfirmOpt {
+    /// Goes ahead without asking for confirmation, for scripts and automation.
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+impl ConfirmOpt {
+    /// Prints `summary` and the affected resources, then asks the user to type `yes`.
+    /// Fails when the user declines, or when there is no terminal to ask on and `--yes` was not given.
+    ///
+    /// The prompt goes through `/dev/tty`, so that it works while standard input and output are piped.
+    pub fn confirm(&self, summary: &str, resources: &[String]) -> Result<(), Error> {
+        if self.yes {
+            return Ok(());
+        }
+        let tty = OpenOptions::new().read(true).write(true).open("/dev/tty");
+        let Ok(mut tty) = tty else {
+            eprintln!("{}", summary);
+            return Err(Error::failed(
+                "There is no terminal to confirm on. Pass --yes to go ahead.",
+            ));
+        };
+        let _ = writeln!(tty, "{}", summary);
+        for resource in resources.iter().take(MAX_LISTED) {
+            let _ = writeln!(tty, "    {}", resource);
+        }
+        if resources.len() > MAX_LISTED {
+            let _ = writeln!(tty, "    ... and {} more", resources.len() - MAX_LISTED);
+        }
+        let _ = write!(tty, "Type `{}` to continue: ", CONFIRMATION);
+        let _ = tty.flush();
+
+        match read_answer(&tty).as_deref() == Some(CONFIRMATION) {
+            true => Ok(()),
+            false => Err(Error::Aborted),
+        }
+    }
+}
+
+/// Reads one line from the terminal, or `None` at the end of input.
+fn read_answer(tty: &File) -> Option<String> {
+    let mut answer = String::new();
+    match BufReader::new(tty).read_line(&mut answer) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(answer.trim().to_string()),
+    }
+}
@@ -0,0 +1,102 @@
+use clap::Args;
+
+use crate::error::Error;
+use crate::events::{filter_groups_events, parse_event_template, EventFilter, EventPrinter};
+use crate::groups::GroupOpt;
+use crate::output::{OutputFormat, Template};
+use crate::progress::Progress;
+use crate::targets::Target;
+use crate::time_range::{now_millis, parse_timestamp};
+
+#[derive(Debug, Args)]
+pub struct CorrelateOpt {
+    /// The correlation ID to look for, such as a request ID.
+    #[arg(long)]
+    pub id: String,
+
+    /// The JSON field that holds the ID, e.g. `requestId` or `ctx.requestId`.
+    /// Without it, events that contain the ID anywhere are matched.
+    #[arg(long)]
+    pub field: Option<String>,
+
+    #[command(flatten)]
+    pub groups: GroupOpt,
+
+    /// The beginning of the time range, e.g. `2h`, `now-10m` or `2024-05-01 13:00` (UTC).
+    #[arg(long, default_value = "1h", value_parser = parse_timestamp)]
+    pub since: i64,
+
+    /// The end of the time range, in the same formats as `--since`. Defaults to now.
+    #[arg(long, value_parser = parse_timestamp)]
+    pub until: Option<i64>,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+
+    /// Prints each event with a template such as `{timestamp} {group} {stream} {message}`.
+    /// Fields: timestamp, timestampMs, stream, eventId, message, and group when searching several log groups.
+    #[arg(long, conflicts_with = "output", value_parser = parse_event_template)]
+    pub format: Option<Template>,
+}
+
+/// The filter pattern that matches the ID in `field`, or anywhere in the message.
+/// A numeric ID also matches a JSON number.
+fn id_pattern(id: &str, field: Option<&str>) -> String {
+    let quoted = format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""));
+    match field {
+        Some(field) if id.parse::<f64>().is_ok() => {
+            format!("{{ $.{0} = {1} || $.{0} = {2} }}", field, quoted, id)
+        }
+        Some(field) => format!("{{ $.{} = {} }}", field, quoted),
+        None => quoted,
+    }
+}
+
+/// Finds the events of one request across the log groups and prints them as one timeline.
+pub async fn run(
+    target: &Target,
+    opt: CorrelateOpt,
+    verbose: bool,
+    color: bool,
+) -> Result<(), Error> {
+    let CorrelateOpt {
+        id,
+        field,
+        groups,
+        since,
+        until,
+        output,
+        format,
+    } = opt;
+    let client = &target.client;
+    let filter_pattern = id_pattern(&id, field.as_deref());
+
+    if verbose {
+        groups.print_verbose();
+        eprintln!("Filter pattern:                {}", filter_pattern);
+        eprintln!(
+            "Time range:                    {} - {}",
+            since,
+            until.unwrap_or_else(now_millis)
+        );
+        eprintln!();
+    }
+
+    let group_names = groups.resolve(target, &Progress::new("groups")).await?;
+    groups.ensure_found(group_names.len())?;
+    let filter = EventFilter {
+        filter_pattern: Some(filter_pattern),
+        start_time: Some(since),
+        end_time: until,
+        ..Default::default()
+    };
+    let events = filter_groups_events(client, &group_names, &filter).await?;
+
+    let mut printer = EventPrinter::new(output, true)
+        .with_template(format)
+        .with_color(color);
+    printer.print(&events);
+    printer.finish();
+    Ok(())
+}
@@ -0,0 +1,210 @@
+use aws_sdk_cloudwatchlogs::{
+    types::{DataProtectionStatus, LogGroup},
+    Client,
+};
+use clap::{Args, Subcommand};
+use regex_lite::Regex;
+
+use crate::error::Error;
+use crate::groups::{get_groups, glob_regex, group_arn, GroupRequestOpt};
+use crate::outln;
+use crate::output::{self, format_timestamp, OutputFormat, Record};
+use crate::progress::Progress;
+
+#[derive(Debug, Args)]
+pub struct DataProtectionOpt {
+    #[command(subcommand)]
+    pub command: DataProtectionCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DataProtectionCommand {
+    /// Reports which log groups have a data protection policy that masks sensitive data.
+    List(ListDataProtectionOpt),
+
+    /// Prints the data protection policy document of a log group.
+    Show(ShowDataProtectionOpt),
+}
+
+#[derive(Debug, Args)]
+pub struct ListDataProtectionOpt {
+    /// Flags the log groups whose names match this glob, such as `/aws/lambda/payments-*`,
+    /// and that have no active policy. Repeat to require a policy for several patterns.
+    #[arg(long, value_name = "GLOB", value_parser = glob_regex)]
+    pub require: Vec<Regex>,
+
+    /// Only lists the flagged log groups.
+    #[arg(long, requires = "require")]
+    pub missing_only: bool,
+
+    #[command(flatten)]
+    pub request: GroupRequestOpt,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct ShowDataProtectionOpt {
+    /// The log group name or ARN.
+    #[arg(short, long)]
+    pub group: String,
+}
+
+impl DataProtectionOpt {
+    /// The log group that the subcommand works on, if it names one.
+    pub fn group(&self) -> Option<&str> {
+        match &self.command {
+            DataProtectionCommand::List(_) => None,
+            DataProtectionCommand::Show(opt) => Some(&opt.group),
+        }
+    }
+}
+
+/// Whether a log group currently masks data with its policy.
+fn is_protected(group: &LogGroup) -> bool {
+    group.data_protection_status() == Some(&DataProtectionStatus::Activated)
+}
+
+/// The data protection status of a log group, or `-` without a policy.
+fn protection_status(group: &LogGroup) -> &str {
+    group.data_protection_status().map_or("-", |s| s.as_str())
+}
+
+/// Describes a log group's data protection for structured output.
+fn protection_record(group: &LogGroup, missing: bool) -> Record {
+    Record::new()
+        .field("name", group.log_group_name())
+        .field(
+            "dataProtectionStatus",
+            group.data_protection_status().map(|s| s.as_str()),
+        )
+        .field("missing", if missing { "true" } else { "false" })
+        .field("arn", group.arn())
+}
+
+/// Describes a log group's data protection as a row of the `table` output.
+fn protection_table_row(group: &LogGroup, missing: bool) -> Record {
+    Record::new()
+        .field("NAME", group.log_group_name())
+        .field("STATUS", protection_status(group))
+        .field("FINDING", if missing { "missing-policy" } else { "" })
+}
+
+/// Lists the log groups with the status of their data protection policy, which DescribeLogGroups
+/// returns, and flags those that match `--require` without an active policy.
+async fn list_protection(
+    client: &Client,
+    opt: ListDataProtectionOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let ListDataProtectionOpt {
+        require,
+        missing_only,
+        request,
+        output,
+    } = opt;
+
+    if verbose {
+        for pattern in &require {
+            eprintln!("Required for:                  {}", pattern.as_str());
+        }
+        if let Some(prefix) = &request.prefix {
+            eprintln!("Log group prefix:              {}", prefix);
+        }
+        request.accounts.print_verbose();
+        eprintln!();
+    }
+
+    let progress = Progress::new("groups");
+    let groups = get_groups(client, &request, &progress).await;
+    progress.clear();
+    let groups: Vec<(LogGroup, bool)> = groups?
+        .into_iter()
+        .map(|group| {
+            let name = group.log_group_name().unwrap_or_default();
+            let missing =
+                !is_protected(&group) && require.iter().any(|pattern| pattern.is_match(name));
+            (group, missing)
+        })
+        .filter(|(_, missing)| *missing || !missing_only)
+        .collect();
+
+    match output {
+        OutputFormat::Text => {
+            let protected = groups
+                .iter()
+                .filter(|(group, _)| is_protected(group))
+                .count();
+            let missing = groups.iter().filter(|(_, missing)| *missing).count();
+            match require.is_empty() {
+                true => outln!("{} of {} groups are protected", protected, groups.len()),
+                false => outln!(
+                    "{} of {} groups are protected, {} required ones lack a policy",
+                    protected,
+                    groups.len(),
+                    missing
+                ),
+            }
+            for (group, missing) in &groups {
+                outln!(
+                    "{}\t{}{}",
+                    group.log_group_name().unwrap_or_default(),
+                    protection_status(group),
+                    if *missing { "\tMISSING" } else { "" }
+                );
+            }
+        }
+        format => output::print_records(
+            format,
+            &groups
+                .iter()
+                .map(|(group, missing)| protection_record(group, *missing))
+                .collect::<Vec<_>>(),
+            &groups
+                .iter()
+                .map(|(group, missing)| protection_table_row(group, *missing))
+                .collect::<Vec<_>>(),
+        ),
+    }
+    Ok(())
+}
+
+/// Prints the policy document of a log group with GetDataProtectionPolicy.
+async fn show_protection(
+    client: &Client,
+    opt: ShowDataProtectionOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let ShowDataProtectionOpt { group } = opt;
+    let policy = client
+        .get_data_protection_policy()
+        .log_group_identifier(group_arn(&group).unwrap_or(&group))
+        .send()
+        .await?;
+    match policy.policy_document() {
+        Some(document) => {
+            if verbose {
+                if let Some(updated) = policy.last_updated_time() {
+                    eprintln!(
+                        "Last updated:                  {}",
+                        format_timestamp(updated)
+                    );
+                    eprintln!();
+                }
+            }
+            outln!("{}", document);
+        }
+        None => eprintln!("Warning: {} has no data protection policy", group),
+    }
+    Ok(())
+}
+
+/// Runs a data protection subcommand.
+pub async fn run(client: &Client, opt: DataProtectionOpt, verbose: bool) -> Result<(), Error> {
+    match opt.command {
+        DataProtectionCommand::List(opt) => list_protection(client, opt, verbose).await,
+        DataProtectionCommand::Show(opt) => show_protection(client, opt, verbose).await,
+    }
+}
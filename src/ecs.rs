@@ -0,0 +1,297 @@
+//! Finds the log streams of ECS containers from the awslogs configuration of their task definitions.
+//! The ECS SDK is not part of this build, so its JSON API is called through [`SignedClient`].
+
+use std::collections::HashMap;
+
+use clap::Args;
+
+use crate::jq::Json;
+use crate::signed::SignedClient;
+use crate::targets::Target;
+
+/// The most tasks that one DescribeTasks call accepts.
+const MAX_DESCRIBED_TASKS: usize = 100;
+
+/// Options that select the log streams of ECS tasks.
+#[derive(Debug, Clone, Default, Args)]
+pub struct EcsOpt {
+    /// Also works on the containers of ECS tasks in this cluster, reading their log group and
+    /// stream from the awslogs configuration of the task definition. Needs `--ecs-task` or `--ecs-service`.
+    #[arg(long, value_name = "CLUSTER")]
+    pub ecs_cluster: Option<String>,
+
+    /// The ID or ARN of a task in `--ecs-cluster`. Can be repeated.
+    #[arg(long, value_name = "TASK", requires = "ecs_cluster")]
+    pub ecs_task: Vec<String>,
+
+    /// Uses the running tasks of this service in `--ecs-cluster`.
+    #[arg(long, value_name = "SERVICE", requires = "ecs_cluster")]
+    pub ecs_service: Option<String>,
+
+    /// Only uses the containers with this name in the task definition. Can be repeated.
+    #[arg(long, value_name = "NAME", requires = "ecs_cluster")]
+    pub container: Vec<String>,
+}
+
+/// Where a container of a task sends its logs.
+#[derive(Debug, Clone)]
+pub struct ContainerLogs {
+    pub group: String,
+    pub stream: String,
+}
+
+impl EcsOpt {
+    pub fn is_set(&self) -> bool {
+        self.ecs_cluster.is_some()
+    }
+
+    /// Checks that the cluster comes with the tasks to look up.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.is_set() && self.ecs_task.is_empty() && self.ecs_service.is_none() {
+            return Err("--ecs-cluster needs --ecs-task or --ecs-service".to_string());
+        }
+        Ok(())
+    }
+
+    /// Rejects the options in subcommands that work on whole log groups.
+    pub fn unsupported(&self) -> Result<(), String> {
+        match self.is_set() {
+            true => Err("--ecs-cluster can only be used with tail".to_string()),
+            false => Ok(()),
+        }
+    }
+
+    /// Prints the selection for verbose output.
+    pub fn print_verbose(&self) {
+        if let Some(cluster) = &self.ecs_cluster {
+            eprintln!("ECS cluster:                   {}", cluster);
+        }
+        for task in &self.ecs_task {
+            eprintln!("ECS task:                      {}", task);
+        }
+        if let Some(service) = &self.ecs_service {
+            eprintln!("ECS service:                   {}", service);
+        }
+        for container in &self.container {
+            eprintln!("Container:                     {}", container);
+        }
+    }
+
+    /// Looks up the tasks and returns the log group and stream of each of their containers.
+    pub async fn resolve(&self, target: &Target) -> Result<Vec<ContainerLogs>, String> {
+        let Some(cluster) = &self.ecs_cluster else {
+            return Ok(Vec::new());
+        };
+        let client = EcsClient::new(target)?;
+        let mut tasks = self.ecs_task.clone();
+        if let Some(service) = &self.ecs_service {
+            let running = client.list_tasks(cluster, service).await?;
+            if running.is_empty() {
+                return Err(format!("the service {} has no running tasks", service));
+            }
+            tasks.extend(running);
+        }
+
+        let mut definitions: HashMap<String, Json> = HashMap::new();
+        let mut logs = Vec::new();
+        let mut found_containers = Vec::new();
+        for chunk in tasks.chunks(MAX_DESCRIBED_TASKS) {
+            for task in client.describe_tasks(cluster, chunk).await? {
+                let task_arn = str_at(&task, "taskArn").unwrap_or_default();
+                // ストリーム名に入るのは ARN の最後の部分のタスク ID
+                let task_id = task_arn.rsplit('/').next().unwrap_or_default();
+                let definition_arn = str_at(&task, "taskDefinitionArn").unwrap_or_default();
+                if !definitions.contains_key(definition_arn) {
+                    let definition = client.describe_task_definition(definition_arn).await?;
+                    definitions.insert(definition_arn.to_string(), definition);
+                }
+                for definition in array_at(&definitions[definition_arn], "containerDefinitions") {
+                    let name = str_at(definition, "name").unwrap_or_default();
+                    if !self.container.is_empty() && !self.container.iter().any(|c| c == name) {
+                        continue;
+                    }
+                    found_containers.push(name.to_string());
+                    let runtime_id = array_at(&task, "containers")
+                        .iter()
+                        .find(|container| str_at(container, "name") == Some(name))
+                        .and_then(|container| str_at(container, "runtimeId"));
+                    match container_logs(definition, task_id, runtime_id, client.region()) {
+                        Ok(container_logs) => logs.push(container_logs),
+                        Err(e) => {
+                            eprintln!("Warning: Container {} of task {}: {}", name, task_id, e)
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(missing) = self
+            .container
+            .iter()
+            .find(|name| !found_containers.contains(name))
+        {
+            return Err(format!("the tasks have no container named {}", missing));
+        }
+        if logs.is_empty() {
+            return Err("none of the containers send their logs to CloudWatch Logs".to_string());
+        }
+        Ok(logs)
+    }
+}
+
+/// The log groups that the awslogs containers of a task definition write to.
+pub async fn task_definition_log_groups(target: &Target, arn: &str) -> Result<Vec<String>, String> {
+    let definition = EcsClient::new(target)?
+        .describe_task_definition(arn)
+        .await?;
+    let mut groups: Vec<String> = Vec::new();
+    for container in array_at(&definition, "containerDefinitions") {
+        if str_at(container, "logConfiguration.logDriver") != Some("awslogs") {
+            continue;
+        }
+        if let Some(group) = str_at(container, "logConfiguration.options.awslogs-group") {
+            if !groups.iter().any(|g| g == group) {
+                groups.push(group.to_string());
+            }
+        }
+    }
+    Ok(groups)
+}
+
+/// Reads the log group and stream of a container from its definition.
+/// Without `awslogs-stream-prefix` the stream is named after the Docker container ID.
+fn container_logs(
+    definition: &Json,
+    task_id: &str,
+    runtime_id: Option<&str>,
+    region: &str,
+) -> Result<ContainerLogs, String> {
+    let name = str_at(definition, "name").unwrap_or_default();
+    let driver = str_at(definition, "logConfiguration.logDriver");
+    if driver != Some("awslogs") {
+        return Err(format!(
+            "logs go to the {} log driver, not awslogs",
+            driver.unwrap_or("default")
+        ));
+    }
+    let option = |key: &str| str_at(definition, &format!("logConfiguration.options.{}", key));
+    let group = option("awslogs-group").ok_or("awslogs-group is not set")?;
+    if let Some(log_region) = option("awslogs-region").filter(|r| *r != region) {
+        return Err(format!("logs go to {}, not {}", log_region, region));
+    }
+    let stream = match option("awslogs-stream-prefix") {
+        Some(prefix) => format!("{}/{}/{}", prefix, name, task_id),
+        None => runtime_id
+            .ok_or("there is no awslogs-stream-prefix and the container has not started")?
+            .to_string(),
+    };
+    Ok(ContainerLogs {
+        group: group.to_string(),
+        stream,
+    })
+}
+
+fn str_at<'a>(json: &'a Json, path: &str) -> Option<&'a str> {
+    match json.get_path(path) {
+        Some(Json::String(value)) => Some(value),
+        _ => None,
+    }
+}
+
+fn array_at<'a>(json: &'a Json, path: &str) -> &'a [Json] {
+    match json.get_path(path) {
+        Some(Json::Array(values)) => values,
+        _ => &[],
+    }
+}
+
+fn string(value: &str) -> Json {
+    Json::String(value.to_string())
+}
+
+/// Calls the ECS JSON API.
+struct EcsClient {
+    client: SignedClient,
+}
+
+impl EcsClient {
+    fn new(target: &Target) -> Result<Self, String> {
+        Ok(EcsClient {
+            client: SignedClient::new(target)?,
+        })
+    }
+
+    fn region(&self) -> &str {
+        self.client.region()
+    }
+
+    async fn call(&self, operation: &str, request: Vec<(&str, Json)>) -> Result<Json, String> {
+        let target = format!("AmazonEC2ContainerServiceV20141113.{}", operation);
+        self.client
+            .call_json("ecs", &target, "application/x-amz-json-1.1", request)
+            .await
+    }
+
+    /// The ARNs of the running tasks of a service, with ListTasks.
+    async fn list_tasks(&self, cluster: &str, service: &str) -> Result<Vec<String>, String> {
+        let mut arns = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let mut request = vec![
+                ("cluster", string(cluster)),
+                ("serviceName", string(service)),
+                ("desiredStatus", string("RUNNING")),
+            ];
+            if let Some(token) = &next_token {
+                request.push(("nextToken", string(token)));
+            }
+            let response = self.call("ListTasks", request).await?;
+            arns.extend(
+                array_at(&response, "taskArns")
+                    .iter()
+                    .filter_map(|arn| match arn {
+                        Json::String(arn) => Some(arn.clone()),
+                        _ => None,
+                    }),
+            );
+            next_token = str_at(&response, "nextToken").map(str::to_string);
+            if next_token.is_none() {
+                return Ok(arns);
+            }
+        }
+    }
+
+    /// Describes up to 100 tasks, failing when one of them cannot be found.
+    async fn describe_tasks(&self, cluster: &str, tasks: &[String]) -> Result<Vec<Json>, String> {
+        let request = vec![
+            ("cluster", string(cluster)),
+            (
+                "tasks",
+                Json::Array(tasks.iter().map(|task| string(task)).collect()),
+            ),
+        ];
+        let response = self.call("DescribeTasks", request).await?;
+        if let Some(failure) = array_at(&response, "failures").first() {
+            return Err(format!(
+                "task {}: {}",
+                str_at(failure, "arn").unwrap_or_default(),
+                str_at(failure, "reason").unwrap_or("not found")
+            ));
+        }
+        Ok(array_at(&response, "tasks").to_vec())
+    }
+
+    async fn describe_task_definition(&self, arn: &str) -> Result<Json, String> {
+        let response = self
+            .call(
+                "DescribeTaskDefinition",
+                vec![("taskDefinition", string(arn))],
+            )
+            .await?;
+        response.get_path("taskDefinition").cloned().ok_or_else(|| {
+            format!(
+                "DescribeTaskDefinition returned no task definition for {}",
+                arn
+            )
+        })
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt;
+
+use aws_sdk_cloudwatchlogs::error::{ProvideErrorMetadata, SdkError};
+
+use crate::report;
+
+/// An error from [`StreamLister`](crate::StreamLister), [`EventFetcher`](crate::EventFetcher)
+/// or a subcommand. Subcommands return it instead of exiting, so that the REPL can carry on.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The log group does not exist, or the credentials cannot see it.
+    GroupNotFound(String),
+    /// The options are a combination that CloudWatch Logs rejects.
+    InvalidRequest(String),
+    /// Any other error returned by CloudWatch Logs.
+    Service(aws_sdk_cloudwatchlogs::Error),
+    /// A subcommand could not go on, such as when a glob matched no log groups
+    /// or another service failed, with the code that the program exits with.
+    Failed { message: String, code: i32 },
+    /// The user declined to go ahead at a confirmation prompt.
+    Aborted,
+}
+
+/// The failures that come with their own advice, told apart by the error code of the service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The credentials are missing, expired or not allowed to make the call.
+    AccessDenied,
+    /// The log group, stream or other resource does not exist.
+    ResourceNotFound,
+    /// The account's request rate for the API was exceeded.
+    Throttling,
+    /// Anything else, including failures to reach the service.
+    Other,
+}
+
+impl ErrorKind {
+    /// Classifies an error of any AWS SDK, such as CloudWatch Logs or STS.
+    pub fn of(error: &impl ProvideErrorMetadata) -> Self {
+        match error.code().unwrap_or_default() {
+            "AccessDeniedException"
+            | "AccessDenied"
+            | "UnrecognizedClientException"
+            | "InvalidClientTokenId"
+            | "ExpiredToken"
+            | "ExpiredTokenException"
+            | "InvalidSignatureException" => ErrorKind::AccessDenied,
+            "ResourceNotFoundException" | "NoSuchEntity" => ErrorKind::ResourceNotFound,
+            "ThrottlingException" | "Throttling" | "TooManyRequestsException" => {
+                ErrorKind::Throttling
+            }
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// The name of the kind in JSON errors.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::AccessDenied => "AccessDenied",
+            ErrorKind::ResourceNotFound => "ResourceNotFound",
+            ErrorKind::Throttling => "Throttling",
+            ErrorKind::Other => "Other",
+        }
+    }
+
+    /// What to try next, for the kinds that have an obvious next step.
+    pub fn hint(self) -> Option<&'static str> {
+        match self {
+            ErrorKind::AccessDenied => Some(
+                "Check that the credentials of the profile have not expired \
+                 and that its IAM policy allows this call",
+            ),
+            ErrorKind::ResourceNotFound => {
+                Some("Check the name and the Region; list-groups shows the log groups that exist")
+            }
+            ErrorKind::Throttling => {
+                Some("Retry later, or lower --max-tps or raise --max-attempts")
+            }
+            ErrorKind::Other => None,
+        }
+    }
+}
+
+impl Error {
+    /// A failure that exits with [`EXIT_FAILURE`](report::EXIT_FAILURE).
+    pub(crate) fn failed(message: impl Into<String>) -> Self {
+        Error::Failed {
+            message: message.into(),
+            code: report::EXIT_FAILURE,
+        }
+    }
+
+    /// Classifies the error for choosing what to do about it.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::GroupNotFound(_) => ErrorKind::ResourceNotFound,
+            Error::InvalidRequest(_) | Error::Aborted => ErrorKind::Other,
+            Error::Service(error) => ErrorKind::of(error),
+            Error::Failed { code, .. } => match *code {
+                report::EXIT_NOT_FOUND => ErrorKind::ResourceNotFound,
+                report::EXIT_ACCESS_DENIED => ErrorKind::AccessDenied,
+                _ => ErrorKind::Other,
+            },
+        }
+    }
+
+    /// The code that the program exits with after this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::GroupNotFound(_) => report::EXIT_NOT_FOUND,
+            Error::InvalidRequest(_) | Error::Aborted => report::EXIT_FAILURE,
+            Error::Service(error) => report::exit_code(error),
+            Error::Failed { code, .. } => *code,
+        }
+    }
+
+    /// Tells a missing log group apart from the other service errors.
+    pub(crate) fn from_service(group: &str, error: aws_sdk_cloudwatchlogs::Error) -> Self {
+        match error {
+            aws_sdk_cloudwatchlogs::Error::ResourceNotFoundException(_) => {
+                Error::GroupNotFound(group.to_string())
+            }
+            error => Error::Service(error),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::GroupNotFound(group) => write!(f, "the log group {} does not exist", group),
+            Error::InvalidRequest(message) => f.write_str(message),
+            Error::Service(error) => write!(f, "{}", error),
+            Error::Failed { message, .. } => f.write_str(message),
+            Error::Aborted => f.write_str("aborted"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Service(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<aws_sdk_cloudwatchlogs::Error> for Error {
+    fn from(error: aws_sdk_cloudwatchlogs::Error) -> Self {
+        Error::Service(error)
+    }
+}
+
+/// Lets `?` turn a failed call straight into an [`Error`], as it does into the SDK's own error.
+impl<E, R> From<SdkError<E, R>> for Error
+where
+    aws_sdk_cloudwatchlogs::Error: From<SdkError<E, R>>,
+{
+    fn from(error: SdkError<E, R>) -> Self {
+        Error::Service(error.into())
+    }
+}
@@ -0,0 +1,905 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::PathBuf;
+
+use aws_sdk_cloudwatchlogs::{
+    types::{FilteredLogEvent, OutputLogEvent},
+    Client,
+};
+use clap::Args;
+use futures_util::{future::join_all, stream, StreamExt};
+use regex_lite::Regex;
+
+use crate::access_log;
+use crate::checkpoint::Checkpoint;
+use crate::color;
+use crate::error::Error;
+use crate::groups::lambda_group;
+use crate::jq;
+use crate::logs_api::{is_not_found, LogsApi};
+use crate::output::{self, format_timestamp, OutputFormat, Record, Template, Value};
+use crate::progress::Progress;
+use crate::report;
+use crate::streams::stream_names;
+use crate::time_range::parse_timestamp;
+use crate::{out, outln};
+
+/// The fields of a log event available to `--format`.
+const EVENT_FIELDS: &[&str] = &[
+    "group",
+    "timestamp",
+    "timestampMs",
+    "stream",
+    "eventId",
+    "message",
+];
+
+pub fn parse_event_template(template: &str) -> Result<Template, String> {
+    Template::parse(template, EVENT_FIELDS)
+}
+
+#[derive(Debug, Args)]
+pub struct GetEventsOpt {
+    /// The log group name, or its ARN to read a log group shared by a linked source account.
+    #[arg(
+        short,
+        long,
+        required_unless_present = "lambda",
+        conflicts_with = "lambda"
+    )]
+    pub group: Option<String>,
+
+    /// Reads the log group of this Lambda function, given by name or ARN, instead of `--group`.
+    #[arg(long, value_name = "FUNCTION")]
+    pub lambda: Option<String>,
+
+    /// The log stream name. Repeat to read several streams;
+    /// without it, every stream in the log group is read.
+    #[arg(short, long)]
+    pub stream: Vec<String>,
+
+    /// Leaves out the stream name when several streams are merged into one timeline.
+    #[arg(long)]
+    pub no_stream_name: bool,
+
+    /// How many streams are read with GetLogEvents at the same time.
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u16).range(1..))]
+    pub concurrency: u16,
+
+    /// A CloudWatch Logs filter pattern, e.g. `ERROR` or `{ $.level = "error" }`.
+    /// Matching is done server-side with FilterLogEvents.
+    #[arg(long)]
+    pub filter_pattern: Option<String>,
+
+    /// Only events at or after this time, e.g. `2h`, `now-10m` or `2024-05-01 13:00` (UTC).
+    #[arg(long, value_parser = parse_timestamp)]
+    pub since: Option<i64>,
+
+    /// Only events before this time, in the same formats as `--since`.
+    #[arg(long, value_parser = parse_timestamp)]
+    pub until: Option<i64>,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+
+    /// Prints the time since the previous event, such as `+12ms` or `+3.4s`, in text and table output.
+    #[arg(long)]
+    pub delta: bool,
+
+    /// Only prints events whose message matches this regular expression.
+    /// Applied client-side after fetching, so any regex syntax is allowed.
+    #[arg(long, value_name = "REGEX")]
+    pub grep: Option<Regex>,
+
+    /// Prints the events that do not match `--grep` instead.
+    #[arg(long, requires = "grep")]
+    pub invert_match: bool,
+
+    /// Applies a jq filter such as `select(.level == "error") | .msg` to each JSON message.
+    /// Each output replaces the message; strings are printed without quotes.
+    /// Events whose message is not JSON, or on which the filter fails, are skipped.
+    #[arg(long, value_name = "FILTER", value_parser = jq::Filter::parse)]
+    pub jq: Option<jq::Filter>,
+
+    /// Prints only these fields of JSON messages, separated by tabs, e.g. `level,requestId,msg`.
+    /// Nested fields are written as `ctx.id`. Other messages are printed as they are.
+    #[arg(long, value_delimiter = ',', conflicts_with = "jq")]
+    pub fields: Vec<String>,
+
+    /// Reads messages in API Gateway's default access log format, so that `--fields` and `--jq`
+    /// can pick out fields such as `status` or `resourcePath`. Without them, the fields are printed in columns.
+    #[arg(long)]
+    pub access_log: bool,
+
+    /// Prints each event with a template such as `{timestamp} {message}`.
+    /// Fields: timestamp, timestampMs, stream, eventId, message.
+    #[arg(long, conflicts_with = "output", value_parser = parse_event_template)]
+    pub format: Option<Template>,
+
+    /// Records the last printed event of each stream in this file, and reads each stream from
+    /// there when the file exists, so that running again prints only the events that are new.
+    /// `--since` only applies to the streams the file has no record of.
+    #[arg(long, value_name = "PATH")]
+    pub checkpoint_file: Option<PathBuf>,
+}
+
+impl GetEventsOpt {
+    /// The log group to read, from `--group` or `--lambda`.
+    pub fn group_name(&self) -> String {
+        match (&self.group, &self.lambda) {
+            (Some(group), _) => group.clone(),
+            (None, Some(function)) => lambda_group(function),
+            (None, None) => unreachable!("clap requires --group or --lambda"),
+        }
+    }
+}
+
+/// A log event as returned by either GetLogEvents or FilterLogEvents.
+#[derive(Debug, Clone, Default)]
+pub struct LogEvent {
+    /// Only set when events of several log groups are printed together.
+    pub group: Option<String>,
+    pub timestamp: i64,
+    pub stream_name: Option<String>,
+    pub event_id: Option<String>,
+    pub message: String,
+}
+
+impl From<OutputLogEvent> for LogEvent {
+    fn from(event: OutputLogEvent) -> Self {
+        LogEvent {
+            timestamp: event.timestamp.unwrap_or_default(),
+            message: event.message.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<FilteredLogEvent> for LogEvent {
+    fn from(event: FilteredLogEvent) -> Self {
+        LogEvent {
+            timestamp: event.timestamp.unwrap_or_default(),
+            stream_name: event.log_stream_name,
+            event_id: event.event_id,
+            message: event.message.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+impl LogEvent {
+    /// Describes the event for structured output. The timestamp is epoch milliseconds.
+    pub fn record(&self) -> Record {
+        self.record_with_timestamp(self.timestamp.into())
+    }
+
+    /// The fields of `--format`: the timestamp as text output prints it, in the `--tz` time zone,
+    /// and as epoch milliseconds in `timestampMs`.
+    fn template_record(&self) -> Record {
+        self.record_with_timestamp(format_timestamp(self.timestamp).as_str().into())
+            .field("timestampMs", self.timestamp)
+    }
+
+    fn record_with_timestamp(&self, timestamp: Value) -> Record {
+        let record = match &self.group {
+            Some(group) => Record::new().field("group", group.as_str()),
+            None => Record::new(),
+        };
+        record
+            .field("timestamp", timestamp)
+            .field("stream", self.stream_name.as_deref())
+            .field("eventId", self.event_id.as_deref())
+            .field("message", self.message.trim_end())
+    }
+}
+
+/// Narrows down the events requested from FilterLogEvents.
+#[derive(Debug, Default, Clone)]
+pub struct EventFilter {
+    pub stream_names: Vec<String>,
+    pub stream_prefix: Option<String>,
+    pub filter_pattern: Option<String>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
+
+async fn get_events(
+    client: &impl LogsApi,
+    log_group_name: &str,
+    log_stream_name: &str,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+) -> Result<Vec<LogEvent>, aws_sdk_cloudwatchlogs::Error> {
+    let mut events: Vec<LogEvent> = Vec::new();
+    let mut next_token: Option<String> = None;
+
+    loop {
+        let result = client
+            .get_log_events(
+                log_group_name,
+                log_stream_name,
+                start_time,
+                end_time,
+                next_token.clone(),
+            )
+            .await;
+        match result {
+            Ok(page) => {
+                events.extend(
+                    page.events
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(LogEvent::from),
+                );
+                // 末尾に達すると、送ったのと同じトークンが返ってくる
+                match page.next_forward_token {
+                    Some(token) if !token.is_empty() && Some(&token) != next_token.as_ref() => {
+                        next_token = Some(token)
+                    }
+                    _ => break,
+                }
+            }
+            Err(e) if is_not_found(&e) => return Err(e),
+            Err(e) => {
+                report::warn("Failed to retrieve a log event", &e);
+                break;
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// Reads several streams with GetLogEvents, at most `concurrency` at a time,
+/// and merges their events into one timeline.
+/// Streams that `resume` has a record of are read from their last printed event instead of `start_time`.
+async fn get_streams_events(
+    client: &impl LogsApi,
+    log_group_name: &str,
+    log_stream_names: &[String],
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    concurrency: usize,
+    resume: Option<&Checkpoint>,
+) -> Result<Vec<LogEvent>, aws_sdk_cloudwatchlogs::Error> {
+    let results: Vec<_> = stream::iter(log_stream_names)
+        .map(|log_stream_name| async move {
+            let start_time = resume
+                .and_then(|checkpoint| checkpoint.resume_stream(log_group_name, log_stream_name))
+                .or(start_time);
+            let mut events = get_events(
+                client,
+                log_group_name,
+                log_stream_name,
+                start_time,
+                end_time,
+            )
+            .await?;
+            // GetLogEvents の結果にはストリーム名が含まれないので補う
+            for event in &mut events {
+                event.stream_name = Some(log_stream_name.clone());
+            }
+            Ok::<_, aws_sdk_cloudwatchlogs::Error>(events)
+        })
+        // 同時刻のイベントを指定したストリームの順に並べるため、結果の順序を保つ
+        .buffered(concurrency)
+        .collect()
+        .await;
+
+    let streams = results.into_iter().collect::<Result<Vec<_>, _>>()?;
+    Ok(merge_by_timestamp(streams))
+}
+
+/// Merges lists of events that are each in timestamp order, such as the events of each stream,
+/// into one list in timestamp order. Events at the same time keep the order of the lists.
+pub fn merge_by_timestamp(lists: Vec<Vec<LogEvent>>) -> Vec<LogEvent> {
+    let mut merged = Vec::with_capacity(lists.iter().map(Vec::len).sum());
+    let mut lists: Vec<_> = lists
+        .into_iter()
+        .map(|list| list.into_iter().peekable())
+        .collect();
+    // 各リストの先頭だけをヒープに入れる k-way マージ
+    let mut heads: BinaryHeap<Reverse<(i64, usize)>> = lists
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(i, list)| list.peek().map(|event| Reverse((event.timestamp, i))))
+        .collect();
+    while let Some(Reverse((_, i))) = heads.pop() {
+        merged.extend(lists[i].next());
+        if let Some(event) = lists[i].peek() {
+            heads.push(Reverse((event.timestamp, i)));
+        }
+    }
+    merged
+}
+
+/// Fetches the events in the log group that match `filter`, sorted by timestamp.
+pub async fn filter_events(
+    client: &impl LogsApi,
+    log_group_name: &str,
+    filter: &EventFilter,
+) -> Result<Vec<LogEvent>, aws_sdk_cloudwatchlogs::Error> {
+    let mut events: Vec<LogEvent> = Vec::new();
+    let mut next_token: Option<String> = None;
+
+    loop {
+        match client
+            .filter_log_events(log_group_name, filter, next_token.take())
+            .await
+        {
+            Ok(page) => {
+                events.extend(
+                    page.events
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(LogEvent::from),
+                );
+                next_token = page.next_token.filter(|token| !token.is_empty());
+                if next_token.is_none() {
+                    break;
+                }
+            }
+            Err(e) if is_not_found(&e) => return Err(e),
+            Err(e) => {
+                report::warn("Failed to retrieve a page of log events", &e);
+                break;
+            }
+        }
+    }
+    // ストリームをまたいだ順序は保証されないので時刻順に並べ直す
+    events.sort_by_key(|e| e.timestamp);
+    Ok(events)
+}
+
+/// Reads the events of a log group, for embedding in other tools.
+///
+/// Named streams are read with GetLogEvents; with a filter pattern or a stream prefix the log group
+/// is searched with FilterLogEvents instead, as `get-events` does.
+/// Any [`LogsApi`] can be read from, such as an SDK [`Client`] or a [`MemoryLogs`](crate::MemoryLogs).
+#[derive(Debug, Clone)]
+pub struct EventFetcher<C = Client> {
+    client: C,
+    group: String,
+    filter: EventFilter,
+    concurrency: usize,
+}
+
+impl<C: LogsApi> EventFetcher<C> {
+    /// Reads every stream of `group`, a log group name or ARN.
+    pub fn new(client: C, group: impl Into<String>) -> Self {
+        EventFetcher {
+            client,
+            group: group.into(),
+            filter: EventFilter::default(),
+            concurrency: 5,
+        }
+    }
+
+    /// Only reads this stream. Can be called several times.
+    pub fn with_stream(mut self, stream: impl Into<String>) -> Self {
+        self.filter.stream_names.push(stream.into());
+        self
+    }
+
+    /// Only reads the streams whose names start with `prefix`.
+    pub fn with_stream_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.filter.stream_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Only returns the events that match a CloudWatch Logs filter pattern.
+    pub fn with_filter_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.filter.filter_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Only returns the events at or after `start_time`, in epoch milliseconds.
+    pub fn with_start_time(mut self, start_time: i64) -> Self {
+        self.filter.start_time = Some(start_time);
+        self
+    }
+
+    /// Only returns the events before `end_time`, in epoch milliseconds.
+    pub fn with_end_time(mut self, end_time: i64) -> Self {
+        self.filter.end_time = Some(end_time);
+        self
+    }
+
+    /// How many streams are read with GetLogEvents at the same time. The default is 5.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Returns the events in timestamp order.
+    pub async fn fetch(&self) -> Result<Vec<LogEvent>, Error> {
+        let filter = &self.filter;
+        if filter.stream_prefix.is_some() && !filter.stream_names.is_empty() {
+            return Err(Error::InvalidRequest(
+                "a stream prefix cannot be combined with stream names".to_string(),
+            ));
+        }
+        let events = match filter.filter_pattern.is_some() || filter.stream_prefix.is_some() {
+            true => filter_events(&self.client, &self.group, filter).await,
+            false => {
+                let stream_names = match filter.stream_names.is_empty() {
+                    true => stream_names(&self.client, &self.group, &Progress::hidden())
+                        .await
+                        .map_err(|e| Error::from_service(&self.group, e))?,
+                    false => filter.stream_names.clone(),
+                };
+                get_streams_events(
+                    &self.client,
+                    &self.group,
+                    &stream_names,
+                    filter.start_time,
+                    filter.end_time,
+                    self.concurrency,
+                    None,
+                )
+                .await
+            }
+        };
+        events.map_err(|e| Error::from_service(&self.group, e))
+    }
+}
+
+/// Runs the same filter on several log groups at once and merges their events into one timeline,
+/// labelling each event with its group when there is more than one.
+pub async fn filter_groups_events(
+    client: &impl LogsApi,
+    log_group_names: &[String],
+    filter: &EventFilter,
+) -> Result<Vec<LogEvent>, aws_sdk_cloudwatchlogs::Error> {
+    let results = join_all(
+        log_group_names
+            .iter()
+            .map(|group| filter_events(client, group, filter)),
+    )
+    .await;
+    let mut lists = Vec::new();
+    for (group, result) in log_group_names.iter().zip(results) {
+        let mut events = result?;
+        if log_group_names.len() > 1 {
+            for event in &mut events {
+                event.group = Some(group.clone());
+            }
+        }
+        lists.push(events);
+    }
+    Ok(merge_by_timestamp(lists))
+}
+
+/// Prints events in a fixed format, keeping state across batches so that
+/// repeated polls (e.g. `tail --follow`) produce one continuous output.
+pub struct EventPrinter {
+    output: OutputFormat,
+    show_stream: bool,
+    template: Option<Template>,
+    grep: Option<Regex>,
+    invert_match: bool,
+    jq: Option<jq::Filter>,
+    fields: Vec<String>,
+    access_log: bool,
+    delta: bool,
+    /// The timestamp of the last printed event, which `delta` is measured from.
+    previous_timestamp: Option<i64>,
+    color: bool,
+    csv_header_printed: bool,
+    printed_any: bool,
+}
+
+impl EventPrinter {
+    /// Text output shows the timestamp, the stream name when `show_stream` is set, and the message.
+    pub fn new(output: OutputFormat, show_stream: bool) -> Self {
+        EventPrinter {
+            output,
+            show_stream,
+            template: None,
+            grep: None,
+            invert_match: false,
+            jq: None,
+            fields: Vec::new(),
+            access_log: false,
+            delta: false,
+            previous_timestamp: None,
+            color: false,
+            csv_header_printed: false,
+            printed_any: false,
+        }
+    }
+
+    /// Prints every event with `template` instead of the output format.
+    pub fn with_template(mut self, template: Option<Template>) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Only prints events whose message matches `grep`, or does not when `invert_match` is set.
+    pub fn with_grep(mut self, grep: Option<Regex>, invert_match: bool) -> Self {
+        self.grep = grep;
+        self.invert_match = invert_match;
+        self
+    }
+
+    /// Replaces each message with the outputs of a jq filter, dropping events without any.
+    pub fn with_jq(mut self, jq: Option<jq::Filter>) -> Self {
+        self.jq = jq;
+        self
+    }
+
+    /// Replaces each JSON message with the values of `fields`, separated by tabs.
+    pub fn with_fields(mut self, fields: Vec<String>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// Reads messages in API Gateway's default access log format as JSON objects.
+    /// Unless other fields are picked, their fields are then printed in columns.
+    pub fn with_access_log(mut self, access_log: bool) -> Self {
+        self.access_log = access_log;
+        if access_log && self.fields.is_empty() && self.jq.is_none() {
+            self.fields = access_log::DEFAULT_COLUMNS.map(str::to_string).to_vec();
+        }
+        self
+    }
+
+    /// Picks the fields out of a JSON message. Missing fields are `-`.
+    fn select_fields(&self, message: &str) -> Option<String> {
+        let json = jq::Json::parse(message.trim()).ok()?;
+        let values: Vec<String> = self
+            .fields
+            .iter()
+            .map(|field| {
+                json.get_path(field)
+                    .map_or("-".to_string(), jq::Json::to_text)
+            })
+            .collect();
+        Some(values.join("\t"))
+    }
+
+    /// Adds the time since the previous event, across batches as well.
+    pub fn with_delta(mut self, delta: bool) -> Self {
+        self.delta = delta;
+        self
+    }
+
+    /// The time since the previous event, `+0ms` for the first one.
+    fn next_delta(&mut self, timestamp: i64) -> String {
+        let previous = self.previous_timestamp.replace(timestamp);
+        format_delta(timestamp - previous.unwrap_or(timestamp))
+    }
+
+    /// Colors text output by severity, with dim timestamps.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn print(&mut self, events: &[LogEvent]) {
+        let matched: Vec<LogEvent>;
+        let events = match &self.grep {
+            Some(grep) => {
+                matched = events
+                    .iter()
+                    .filter(|event| grep.is_match(&event.message) != self.invert_match)
+                    .cloned()
+                    .collect();
+                &matched[..]
+            }
+            None => events,
+        };
+        let parsed: Vec<LogEvent>;
+        let events = match self.access_log {
+            true => {
+                parsed = events
+                    .iter()
+                    .map(|event| LogEvent {
+                        message: access_log::to_json(&event.message)
+                            .unwrap_or_else(|| event.message.clone()),
+                        ..event.clone()
+                    })
+                    .collect();
+                &parsed[..]
+            }
+            false => events,
+        };
+        if let Some(jq) = &self.jq {
+            let filtered: Vec<LogEvent> = events
+                .iter()
+                .flat_map(|event| {
+                    jq.apply(&event.message)
+                        .into_iter()
+                        .map(move |message| LogEvent {
+                            message,
+                            ..event.clone()
+                        })
+                })
+                .collect();
+            return self.print_events(&filtered);
+        }
+        if !self.fields.is_empty() {
+            let selected: Vec<LogEvent> = events
+                .iter()
+                .map(|event| LogEvent {
+                    message: self
+                        .select_fields(&event.message)
+                        .unwrap_or_else(|| event.message.clone()),
+                    ..event.clone()
+                })
+                .collect();
+            return self.print_events(&selected);
+        }
+        self.print_events(events)
+    }
+
+    fn print_events(&mut self, events: &[LogEvent]) {
+        self.printed_any |= !events.is_empty();
+        if let Some(template) = &self.template {
+            for event in events {
+                outln!("{}", template.render(&event.template_record()));
+            }
+            return;
+        }
+        match self.output {
+            OutputFormat::Text => {
+                for event in events {
+                    let mut timestamp = format_timestamp(event.timestamp);
+                    if self.delta {
+                        timestamp =
+                            format!("{} {:>8}", timestamp, self.next_delta(event.timestamp));
+                    }
+                    let mut message = event.message.trim_end().to_string();
+                    let mut stream_name = event.stream_name.clone().filter(|_| self.show_stream);
+                    let mut group = event.group.clone();
+                    if self.color {
+                        timestamp = color::timestamp(&timestamp);
+                        message = color::message(&message);
+                        stream_name = stream_name.as_deref().map(color::stream_name);
+                        group = group.as_deref().map(color::stream_name);
+                    }
+                    let prefix = group.map_or(String::new(), |group| format!("{} ", group));
+                    match stream_name {
+                        Some(stream_name) => {
+                            outln!("{}{} {} {}", prefix, timestamp, stream_name, message)
+                        }
+                        None => outln!("{}{} {}", prefix, timestamp, message),
+                    }
+                }
+            }
+            OutputFormat::Json => {
+                let records: Vec<Record> = events.iter().map(LogEvent::record).collect();
+                outln!("{}", output::to_json_array(&records));
+            }
+            OutputFormat::Ndjson => {
+                for event in events {
+                    outln!("{}", event.record().to_json());
+                }
+            }
+            OutputFormat::Csv => {
+                let records: Vec<Record> = events.iter().map(LogEvent::record).collect();
+                out!("{}", output::to_csv(&records, !self.csv_header_printed));
+                self.csv_header_printed |= !records.is_empty();
+            }
+            OutputFormat::Table => {
+                let rows: Vec<Record> = events
+                    .iter()
+                    .map(|event| {
+                        let row = match &event.group {
+                            Some(group) => Record::new().field("GROUP", group.as_str()),
+                            None => Record::new(),
+                        };
+                        let row =
+                            row.field("TIMESTAMP", format_timestamp(event.timestamp).as_str());
+                        let row = match self.delta {
+                            true => row.field("DELTA", self.next_delta(event.timestamp).as_str()),
+                            false => row,
+                        };
+                        let row = match self.show_stream {
+                            true => row.field("STREAM", event.stream_name.as_deref()),
+                            false => row,
+                        };
+                        row.field("MESSAGE", event.message.trim_end())
+                    })
+                    .collect();
+                out!("{}", output::to_table(&rows));
+            }
+            OutputFormat::Yaml => {
+                let records: Vec<Record> = events.iter().map(LogEvent::record).collect();
+                out!("{}", output::to_yaml_items(&records));
+            }
+        }
+    }
+
+    /// Completes the output once no more batches will be printed.
+    pub fn finish(&mut self) {
+        if self.output == OutputFormat::Yaml && !self.printed_any {
+            outln!("[]");
+        }
+    }
+}
+
+/// Formats the time between two events, e.g. `+12ms`, `+3.4s` or `+2m05s`.
+fn format_delta(millis: i64) -> String {
+    let sign = if millis < 0 { "-" } else { "+" };
+    let millis = millis.unsigned_abs();
+    match millis {
+        0..=999 => format!("{}{}ms", sign, millis),
+        1000..=59_999 => format!("{}{:.1}s", sign, millis as f64 / 1000.0),
+        60_000..=3_599_999 => format!("{}{}m{:02}s", sign, millis / 60_000, millis / 1000 % 60),
+        _ => format!(
+            "{}{}h{:02}m",
+            sign,
+            millis / 3_600_000,
+            millis / 60_000 % 60
+        ),
+    }
+}
+
+/// Prints the timestamp and message of every event in the log streams,
+/// adding the stream name when more than one is read.
+pub async fn run(
+    client: &Client,
+    opt: GetEventsOpt,
+    verbose: bool,
+    color: bool,
+) -> Result<(), Error> {
+    let group = opt.group_name();
+    let GetEventsOpt {
+        stream,
+        no_stream_name,
+        concurrency,
+        filter_pattern,
+        since,
+        until,
+        output,
+        delta,
+        grep,
+        invert_match,
+        jq,
+        fields,
+        access_log,
+        format,
+        checkpoint_file,
+        ..
+    } = opt;
+    let mut checkpoint = checkpoint_file
+        .map(|path| Checkpoint::load(&path))
+        .transpose()
+        .map_err(Error::failed)?;
+
+    if verbose {
+        eprintln!("Log group name:                {}", &group);
+        for stream_name in &stream {
+            eprintln!("Log stream name:               {}", stream_name);
+        }
+        if let Some(pattern) = &filter_pattern {
+            eprintln!("Filter pattern:                {}", pattern);
+        }
+        eprintln!();
+    }
+
+    let show_stream = stream.len() != 1 && !no_stream_name;
+    // パターンが指定された場合はサーバー側でフィルタする
+    let mut events = match filter_pattern {
+        Some(filter_pattern) => {
+            let resumed = checkpoint
+                .as_ref()
+                .and_then(|checkpoint| checkpoint.resume_group(&group, &stream));
+            let filter = EventFilter {
+                stream_names: stream,
+                filter_pattern: Some(filter_pattern),
+                start_time: resumed.map(|(timestamp, _)| timestamp).or(since),
+                end_time: until,
+                ..Default::default()
+            };
+            filter_events(client, &group, &filter).await?
+        }
+        None => {
+            let stream_names = match stream.is_empty() {
+                true => stream_names(client, &group, &Progress::new("streams")).await?,
+                false => stream,
+            };
+            get_streams_events(
+                client,
+                &group,
+                &stream_names,
+                since,
+                until,
+                concurrency.into(),
+                checkpoint.as_ref(),
+            )
+            .await?
+        }
+    };
+    if let Some(checkpoint) = &checkpoint {
+        checkpoint.retain_new(&group, &mut events);
+    }
+
+    let mut printer = EventPrinter::new(output, show_stream)
+        .with_template(format)
+        .with_grep(grep, invert_match)
+        .with_jq(jq)
+        .with_fields(fields)
+        .with_access_log(access_log)
+        .with_delta(delta)
+        .with_color(color);
+    printer.print(&events);
+    printer.finish();
+    if let (Some(checkpoint), false) = (&mut checkpoint, events.is_empty()) {
+        output::flush_output()?;
+        checkpoint.advance(&group, &events);
+        checkpoint.save().map_err(Error::failed)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs_api::MemoryLogs;
+
+    fn event(timestamp: i64, message: &str) -> LogEvent {
+        LogEvent {
+            group: None,
+            timestamp,
+            stream_name: None,
+            event_id: None,
+            message: message.to_string(),
+        }
+    }
+
+    fn messages(events: &[LogEvent]) -> Vec<&str> {
+        events.iter().map(|event| event.message.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn get_events_stops_when_the_token_repeats() {
+        let logs = (0..5)
+            .fold(MemoryLogs::new(), |logs, i| {
+                logs.with_event("app", "a", i, format!("event {}", i))
+            })
+            .with_page_size(2);
+        let events = get_events(&logs, "app", "a", None, None).await.unwrap();
+        assert_eq!(events.len(), 5);
+        // 0..2, 2..4, 4..5 と、同じトークンが返ってくる空のページ
+        assert_eq!(logs.calls(), 4);
+    }
+
+    #[tokio::test]
+    async fn filter_pattern_searches_every_stream() {
+        let logs = MemoryLogs::new()
+            .with_event("app", "b", 3, "ERROR late")
+            .with_event("app", "a", 1, "INFO start")
+            .with_event("app", "a", 2, "ERROR early")
+            .with_page_size(1);
+        let events = EventFetcher::new(&logs, "app")
+            .with_filter_pattern("\"ERROR\"")
+            .fetch()
+            .await
+            .unwrap();
+        assert_eq!(messages(&events), ["ERROR early", "ERROR late"]);
+        assert_eq!(events[1].stream_name.as_deref(), Some("b"));
+    }
+
+    #[tokio::test]
+    async fn fetch_reads_every_stream_without_a_pattern() {
+        let logs = MemoryLogs::new()
+            .with_event("app", "b", 1, "b1")
+            .with_event("app", "a", 2, "a2")
+            .with_event("app", "b", 3, "b3");
+        let events = EventFetcher::new(&logs, "app").fetch().await.unwrap();
+        assert_eq!(messages(&events), ["b1", "a2", "b3"]);
+        assert_eq!(events[1].stream_name.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn merge_keeps_timestamp_order_and_list_order_on_ties() {
+        let merged = merge_by_timestamp(vec![
+            vec![event(1, "a1"), event(3, "a3"), event(5, "a5")],
+            vec![],
+            vec![event(2, "c2"), event(3, "c3"), event(6, "c6")],
+        ]);
+        assert_eq!(messages(&merged), ["a1", "c2", "a3", "c3", "a5", "c6"]);
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use aws_sdk_cloudwatchlogs::{
+    types::{ExportTask, ExportTaskStatusCode},
+    Client,
+};
+use clap::Args;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::error::Error;
+use crate::outln;
+use crate::output;
+use crate::s3::S3Reader;
+use crate::targets::Target;
+use crate::time_range::{now_millis, parse_timestamp};
+
+#[derive(Debug, Args)]
+pub struct ExportOpt {
+    /// The log group name.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// Only exports log streams whose names start with this prefix.
+    #[arg(long)]
+    pub stream_prefix: Option<String>,
+
+    /// The beginning of the time range, e.g. `2h`, `now-10m` or `2024-05-01 13:00` (UTC).
+    #[arg(long, value_parser = parse_timestamp)]
+    pub since: i64,
+
+    /// The end of the time range, in the same formats as `--since`. Defaults to now.
+    #[arg(long, value_parser = parse_timestamp)]
+    pub until: Option<i64>,
+
+    /// The S3 bucket to export to. Its policy must allow CloudWatch Logs to write to it.
+    #[arg(long)]
+    pub bucket: String,
+
+    /// The S3 key prefix of the exported objects. The service defaults to `exportedlogs`.
+    #[arg(long)]
+    pub prefix: Option<String>,
+
+    /// The name of the export task.
+    #[arg(long)]
+    pub task_name: Option<String>,
+
+    /// Prints the task ID and returns without waiting for the export to finish.
+    #[arg(long)]
+    pub no_wait: bool,
+
+    /// Downloads the exported objects into this directory once the task completes,
+    /// as one time-ordered `<stream>.log` file per log stream.
+    #[arg(long, value_name = "DIR", conflicts_with = "no_wait")]
+    pub download: Option<PathBuf>,
+
+    /// Seconds to wait between DescribeExportTasks polls.
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+    pub interval: u64,
+}
+
+/// Polls DescribeExportTasks until the task leaves the Pending/Running states,
+/// printing each change of status.
+async fn wait_for_task(
+    client: &Client,
+    task_id: &str,
+    interval: Duration,
+) -> Result<ExportTask, Error> {
+    let started = Instant::now();
+    let mut last_status: Option<ExportTaskStatusCode> = None;
+    loop {
+        let task = client
+            .describe_export_tasks()
+            .task_id(task_id)
+            .send()
+            .await?
+            .export_tasks
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            // 一覧に出る前でもまだ作成中とみなす
+            .unwrap_or_else(|| ExportTask::builder().build());
+        let status = task
+            .status()
+            .and_then(|status| status.code())
+            .cloned()
+            .unwrap_or(ExportTaskStatusCode::Pending);
+        if last_status.as_ref() != Some(&status) {
+            outln!(
+                "Status: {} ({}s)",
+                status.as_str(),
+                started.elapsed().as_secs()
+            );
+            last_status = Some(status.clone());
+        }
+        match status {
+            ExportTaskStatusCode::Pending | ExportTaskStatusCode::Running => {
+                output::flush_output()?;
+                tokio::time::sleep(interval).await;
+            }
+            _ => return Ok(task),
+        }
+    }
+}
+
+/// Decompresses a gzip object with the system's `gzip`.
+fn gunzip(data: Vec<u8>) -> io::Result<Vec<u8>> {
+    let mut child = Command::new("gzip")
+        .arg("-dc")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    // 出力を読みながら書かないとパイプが詰まるので、入力は別スレッドで書き込む
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = thread::spawn(move || stdin.write_all(&data));
+    let mut output = Vec::new();
+    child
+        .stdout
+        .take()
+        .expect("stdout is piped")
+        .read_to_end(&mut output)?;
+    writer.join().expect("the writer thread does not panic")?;
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("gzip exited with {}", status)));
+    }
+    Ok(output)
+}
+
+/// Splits exported text into events and sorts them by timestamp.
+/// Each event starts with an RFC 3339 timestamp; lines without one continue the previous event.
+fn sort_events(text: &str) -> Vec<&str> {
+    let mut events: Vec<(Option<OffsetDateTime>, &str)> = Vec::new();
+    let mut start = 0;
+    for (offset, line) in text.split_inclusive('\n').scan(0, |offset, line| {
+        let line_start = *offset;
+        *offset += line.len();
+        Some((line_start, line))
+    }) {
+        let timestamp = line
+            .split(' ')
+            .next()
+            .and_then(|token| OffsetDateTime::parse(token, &Rfc3339).ok());
+        if timestamp.is_some() || events.is_empty() {
+            if let Some(last) = events.last_mut() {
+                last.1 = &text[start..offset];
+            }
+            events.push((timestamp, ""));
+            start = offset;
+        }
+    }
+    if let Some(last) = events.last_mut() {
+        last.1 = &text[start..];
+    }
+    // 同時刻のイベントはエクスポートされた順のまま並べる
+    events.sort_by_key(|(timestamp, _)| *timestamp);
+    events.into_iter().map(|(_, event)| event).collect()
+}
+
+/// Downloads the objects of a completed export task and writes one time-ordered file
+/// per log stream into `dir`.
+async fn download(
+    reader: &S3Reader,
+    bucket: &str,
+    task_prefix: &str,
+    dir: &Path,
+) -> Result<(), String> {
+    let keys = reader.list_keys(bucket, task_prefix).await?;
+    // キーは <prefix>/<タスクID>/<ストリーム名>/<連番>.gz の形で、ストリーム名には / を含みうる
+    let mut streams: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for key in keys {
+        let Some((stream, _)) = key[task_prefix.len()..].rsplit_once('/') else {
+            continue;
+        };
+        // 書き込み権限の確認にサービスが置くオブジェクト
+        if stream.is_empty() || stream == "aws-logs-write-test" {
+            continue;
+        }
+        streams.entry(stream.to_string()).or_default().push(key);
+    }
+    if streams.is_empty() {
+        eprintln!(
+            "Warning: No exported objects found under s3://{}/{}",
+            bucket, task_prefix
+        );
+        return Ok(());
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+    for (stream, keys) in streams {
+        let mut text = String::new();
+        for key in keys {
+            let data = reader.get_object(bucket, &key).await?;
+            let data = match key.ends_with(".gz") {
+                true => gunzip(data).map_err(|e| format!("failed to decompress {}: {}", key, e))?,
+                false => data,
+            };
+            text.push_str(&String::from_utf8_lossy(&data));
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+        }
+        let path = dir.join(format!("{}.log", stream.replace('/', "_")));
+        fs::write(&path, sort_events(&text).concat())
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+        outln!("Downloaded {}", path.display());
+    }
+    Ok(())
+}
+
+/// Exports the events of a log group in a time range to S3 with CreateExportTask,
+/// then waits for the task to finish and optionally downloads the result.
+pub async fn run(target: &Target, opt: ExportOpt, verbose: bool) -> Result<(), Error> {
+    let ExportOpt {
+        group,
+        stream_prefix,
+        since,
+        until,
+        bucket,
+        prefix,
+        task_name,
+        no_wait,
+        download: download_dir,
+        interval,
+    } = opt;
+    let client = &target.client;
+    let until = until.unwrap_or_else(now_millis);
+
+    // 認証情報がなければエクスポートを始める前に失敗させる
+    let reader = download_dir
+        .as_ref()
+        .map(|_| S3Reader::new(target))
+        .transpose()
+        .map_err(|e| Error::failed(format!("Failed to set up S3 access: {}", e)))?;
+
+    if verbose {
+        eprintln!("Log group name:                {}", &group);
+        if let Some(stream_prefix) = &stream_prefix {
+            eprintln!("Log stream prefix:             {}", stream_prefix);
+        }
+        eprintln!("Time range:                    {} - {}", since, until);
+        eprintln!(
+            "Destination:                   s3://{}/{}",
+            &bucket,
+            prefix.as_deref().unwrap_or_default()
+        );
+        if let Some(download_dir) = &download_dir {
+            eprintln!("Download directory:            {}", download_dir.display());
+        }
+        eprintln!();
+    }
+
+    let task_id = client
+        .create_export_task()
+        .log_group_name(&group)
+        .set_log_stream_name_prefix(stream_prefix)
+        .from(since)
+        .to(until)
+        .destination(&bucket)
+        .set_destination_prefix(prefix.clone())
+        .set_task_name(task_name)
+        .send()
+        .await?
+        .task_id
+        .unwrap_or_default();
+    outln!("Export task ID: {}", task_id);
+    if no_wait {
+        return Ok(());
+    }
+
+    let task = wait_for_task(client, &task_id, Duration::from_secs(interval)).await?;
+    match task.status().and_then(|status| status.code()) {
+        Some(ExportTaskStatusCode::Completed) => {
+            // オブジェクトは <prefix>/<タスクID>/<ストリーム名>/ の下に書き込まれる
+            let task_prefix = format!(
+                "{}/{}/",
+                prefix.as_deref().unwrap_or("exportedlogs"),
+                task_id
+            );
+            outln!("Exported to s3://{}/{}", bucket, task_prefix);
+            if let (Some(reader), Some(download_dir)) = (&reader, &download_dir) {
+                download(reader, &bucket, &task_prefix, download_dir)
+                    .await
+                    .map_err(|e| Error::failed(format!("Failed to download the export: {}", e)))?;
+            }
+        }
+        status => {
+            return Err(Error::failed(format!(
+                "Export task finished with status {}: {}",
+                status.map_or("unknown", |status| status.as_str()),
+                task.status()
+                    .and_then(|status| status.message())
+                    .unwrap_or_default()
+            )))
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,954 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use aws_sdk_cloudwatchlogs::{
+    operation::describe_log_groups::builders::DescribeLogGroupsFluentBuilder,
+    types::{error::ResourceNotFoundException, LogGroup},
+    Client,
+};
+use clap::Args;
+use futures_util::{future::join_all, stream, StreamExt};
+use regex_lite::Regex;
+
+use crate::apigateway::api_log_groups;
+use crate::cloudformation::stack_log_groups;
+use crate::completions;
+use crate::ecs::EcsOpt;
+use crate::error::Error;
+use crate::manage::parse_tag;
+use crate::output::{self, format_bytes, format_timestamp, OutputFormat, Record, Template};
+use crate::progress::Progress;
+use crate::report;
+use crate::targets::Target;
+use crate::time_range::parse_duration;
+use crate::watch::{Entry, Watch};
+use crate::{out, outln};
+
+/// The fields of a log group available to `--format` and structured output.
+const GROUP_FIELDS: &[&str] = &[
+    "profile",
+    "region",
+    "accountId",
+    "name",
+    "creationTime",
+    "retentionInDays",
+    "storedBytes",
+    "kmsKeyId",
+    "logGroupClass",
+    "arn",
+];
+
+fn parse_group_template(template: &str) -> Result<Template, String> {
+    Template::parse(template, GROUP_FIELDS)
+}
+
+/// Whether a `--group` value is a glob pattern. Log group names cannot contain these characters.
+fn is_glob(group: &str) -> bool {
+    group_arn(group).is_none() && group.contains(['*', '?', '['])
+}
+
+/// The `logGroupIdentifier` for a `--group` given as an ARN, which is how log groups of
+/// linked source accounts are addressed. The `:*` that DescribeLogGroups puts at the end of
+/// log group ARNs is removed. Returns `None` for a group name.
+pub fn group_arn(group: &str) -> Option<&str> {
+    group
+        .starts_with("arn:")
+        .then(|| group.strip_suffix(":*").unwrap_or(group))
+}
+
+/// The `logGroupName` for a `--group` that is not an ARN.
+pub fn group_name(group: &str) -> Option<String> {
+    group_arn(group).is_none().then(|| group.to_string())
+}
+
+/// The log group that Lambda writes a function's logs to, for a function name or ARN.
+pub fn lambda_group(function: &str) -> String {
+    // arn:aws:lambda:<リージョン>:<アカウント>:function:<関数名>[:<バージョンかエイリアス>]
+    let name = match function.strip_prefix("arn:") {
+        Some(arn) => arn.split(':').nth(5).unwrap_or(function),
+        None => function,
+    };
+    format!("/aws/lambda/{}", name)
+}
+
+/// Translates a glob such as `/aws/ecs/*-prod` into an anchored regular expression.
+/// `*` matches any characters, including `/`, `?` any one character,
+/// and `[abc]`, `[a-z]` or `[!abc]` one of (or none of) a set of characters.
+pub fn glob_regex(glob: &str) -> Result<Regex, String> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            '[' => {
+                pattern.push('[');
+                if chars.next_if_eq(&'!').is_some() {
+                    pattern.push('^');
+                }
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        closed = true;
+                        break;
+                    }
+                    match c {
+                        '\\' | '[' | '^' => pattern.push_str(&regex_lite::escape(&c.to_string())),
+                        c => pattern.push(c),
+                    }
+                }
+                if !closed {
+                    return Err(format!("unclosed `[` in `{}`", glob));
+                }
+                pattern.push(']');
+            }
+            c => pattern.push_str(&regex_lite::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).map_err(|e| format!("invalid pattern `{}`: {}", glob, e))
+}
+
+/// Checks a `--group` value: a name, or a glob that can be expanded.
+fn parse_group(group: &str) -> Result<String, String> {
+    if is_glob(group) {
+        glob_regex(group)?;
+    }
+    Ok(group.to_string())
+}
+
+/// Selects the log groups to work on, by name, glob or prefix.
+#[derive(Debug, Clone, Args)]
+pub struct GroupOpt {
+    /// The log group name or ARN, or a glob such as `/aws/ecs/*-prod` that is expanded with DescribeLogGroups.
+    /// Repeat to work on several log groups at once; their output is then labelled with the group name.
+    #[arg(short, long, required_unless_present_any = ["group_prefix", "lambda", "stack", "apigw", "ecs_cluster"], value_parser = parse_group)]
+    pub group: Vec<String>,
+
+    /// Also works on the log group of this Lambda function, given by name or ARN,
+    /// i.e. `/aws/lambda/<FUNCTION>`. Can be repeated.
+    #[arg(long, value_name = "FUNCTION")]
+    pub lambda: Vec<String>,
+
+    /// Also works on the log groups of this CloudFormation stack and its nested stacks:
+    /// its `AWS::Logs::LogGroup` resources and the groups of its Lambda functions and ECS task definitions.
+    #[arg(long, value_name = "STACK")]
+    pub stack: Option<String>,
+
+    /// Also works on the log groups of this API Gateway API, given by ID: the execution logs of
+    /// a REST API and the access logs of each stage.
+    #[arg(long, value_name = "API-ID")]
+    pub apigw: Option<String>,
+
+    /// Only uses this stage of `--apigw`, e.g. `prod`.
+    #[arg(long, requires = "apigw")]
+    pub stage: Option<String>,
+
+    /// Also works on every log group whose name starts with this prefix, e.g. `/aws/lambda/payments-`.
+    #[arg(long, value_name = "PREFIX")]
+    pub group_prefix: Option<String>,
+
+    /// Only expands globs and `--group-prefix` to log groups that have this tag, as `KEY=VALUE`.
+    /// Repeat to require several tags. Groups named in full are used as they are.
+    #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = parse_tag)]
+    pub tags: Vec<(String, String)>,
+
+    #[command(flatten)]
+    pub accounts: LinkedAccountsOpt,
+
+    #[command(flatten)]
+    pub ecs: EcsOpt,
+}
+
+/// The ARN that tagging and anomaly detection address a log group by, without the `:*` that
+/// DescribeLogGroups appends. Looks the group up by name unless it is already an ARN.
+pub async fn resolve_group_arn(
+    client: &Client,
+    group: &str,
+) -> Result<String, aws_sdk_cloudwatchlogs::Error> {
+    if let Some(arn) = group_arn(group) {
+        return Ok(arn.to_string());
+    }
+    if let Some(arn) = describe_group(client, group)
+        .await?
+        .and_then(|log_group| log_group.arn)
+    {
+        return Ok(arn.strip_suffix(":*").unwrap_or(&arn).to_string());
+    }
+    // 存在しないときは他のコマンドと同じく似た名前を提案させる
+    Err(aws_sdk_cloudwatchlogs::Error::ResourceNotFoundException(
+        ResourceNotFoundException::builder()
+            .message(format!("The log group {} does not exist.", group))
+            .build(),
+    ))
+}
+
+/// Looks up the log group with exactly this name, or `None` if there is none.
+async fn describe_group(
+    client: &Client,
+    name: &str,
+) -> Result<Option<LogGroup>, aws_sdk_cloudwatchlogs::Error> {
+    let mut pages = client
+        .describe_log_groups()
+        .log_group_name_prefix(name)
+        .into_paginator()
+        .send();
+    while let Some(page) = pages.next().await {
+        let found = page?
+            .log_groups
+            .unwrap_or_default()
+            .into_iter()
+            .find(|log_group| log_group.log_group_name() == Some(name));
+        if found.is_some() {
+            return Ok(found);
+        }
+    }
+    Ok(None)
+}
+
+/// The `storedBytes` of a log group by name, which DescribeLogStreams no longer reports per stream.
+/// `None` for a group given by ARN, which may belong to another account, or one that is gone.
+pub async fn group_stored_bytes(
+    client: &Client,
+    group: &str,
+) -> Result<Option<i64>, aws_sdk_cloudwatchlogs::Error> {
+    let Some(name) = group_name(group) else {
+        return Ok(None);
+    };
+    Ok(describe_group(client, &name)
+        .await?
+        .and_then(|log_group| log_group.stored_bytes))
+}
+
+/// Checks that an account ID has 12 digits.
+fn parse_account_id(id: &str) -> Result<String, String> {
+    match id.len() == 12 && id.bytes().all(|b| b.is_ascii_digit()) {
+        true => Ok(id.to_string()),
+        false => Err(format!("`{}` is not a 12-digit AWS account ID", id)),
+    }
+}
+
+/// Reaches the log groups of source accounts linked to a monitoring account
+/// through CloudWatch cross-account observability.
+#[derive(Debug, Clone, Default, Args)]
+pub struct LinkedAccountsOpt {
+    /// Also finds the log groups of this linked source account when listing or expanding log groups.
+    /// Repeat or give a list for several, up to 20.
+    #[arg(long, value_delimiter = ',', value_name = "ACCOUNT", value_parser = parse_account_id)]
+    pub account_id: Vec<String>,
+
+    /// Also finds the log groups of every linked source account.
+    #[arg(long)]
+    pub include_linked_accounts: bool,
+}
+
+impl LinkedAccountsOpt {
+    /// Whether log groups of source accounts are listed. They are then addressed by ARN,
+    /// since only the monitoring account's own log groups can be used by name.
+    pub fn enabled(&self) -> bool {
+        self.include_linked_accounts || !self.account_id.is_empty()
+    }
+
+    fn apply(&self, request: DescribeLogGroupsFluentBuilder) -> DescribeLogGroupsFluentBuilder {
+        if !self.enabled() {
+            return request;
+        }
+        // accountIdentifiers は includeLinkedAccounts が true のときだけ使われる
+        request
+            .include_linked_accounts(true)
+            .set_account_identifiers((!self.account_id.is_empty()).then(|| self.account_id.clone()))
+    }
+
+    /// Prints the source accounts for verbose output.
+    pub fn print_verbose(&self) {
+        match self.account_id.is_empty() {
+            true if self.include_linked_accounts => {
+                eprintln!("Source accounts:               all linked accounts")
+            }
+            true => {}
+            false => eprintln!(
+                "Source accounts:               {}",
+                self.account_id.join(", ")
+            ),
+        }
+    }
+
+    /// How a listed log group is given to later requests: by ARN when it may be in another account.
+    fn group_identifier(&self, group: LogGroup) -> Option<String> {
+        match self.enabled() {
+            true => group
+                .arn
+                .map(|arn| arn.strip_suffix(":*").map_or(arn.clone(), str::to_string)),
+            false => group.log_group_name,
+        }
+    }
+}
+
+/// The account that a log group belongs to, taken from its ARN.
+pub fn group_account(group: &LogGroup) -> Option<&str> {
+    group.arn()?.split(':').nth(4)
+}
+
+impl GroupOpt {
+    /// The log groups named in full with `--group` or `--lambda`, without expanding anything.
+    pub fn named_groups(&self) -> Vec<String> {
+        self.group
+            .iter()
+            .cloned()
+            .chain(self.lambda.iter().map(|function| lambda_group(function)))
+            .collect()
+    }
+
+    /// The named log groups, with globs expanded, followed by the ones found with `--group-prefix`,
+    /// without duplicates.
+    pub async fn resolve(
+        &self,
+        target: &Target,
+        progress: &Progress,
+    ) -> Result<Vec<String>, Error> {
+        let client = &target.client;
+        let mut names: Vec<String> = Vec::new();
+        let mut add = |name: String| {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        };
+        for group in &self.group {
+            if !is_glob(group) {
+                add(group.clone());
+                continue;
+            }
+            // ワイルドカードより前の部分はサーバー側のプレフィックス検索に使う
+            let prefix = &group[..group.find(['*', '?', '[']).unwrap_or(group.len())];
+            let request = GroupRequestOpt {
+                prefix: (!prefix.is_empty()).then(|| prefix.to_string()),
+                name_match: glob_regex(group).ok(),
+                tags: self.tags.clone(),
+                accounts: self.accounts.clone(),
+                ..Default::default()
+            };
+            let matched = get_groups(client, &request, progress).await?;
+            if matched.is_empty() {
+                progress.clear();
+                eprintln!("Warning: No log groups match `{}`", group);
+            }
+            matched
+                .into_iter()
+                .filter_map(|g| self.accounts.group_identifier(g))
+                .for_each(&mut add);
+        }
+        for function in &self.lambda {
+            // 関数名の打ち間違いは、ここで似た名前のロググループを提案させる
+            let group = lambda_group(function);
+            resolve_group_arn(client, &group).await?;
+            add(group);
+        }
+        // スタックや API から見つけたロググループは、存在するものだけを使う
+        let mut found: Vec<(String, String)> = Vec::new();
+        if let Some(stack) = &self.stack {
+            let stack_groups = stack_log_groups(target, stack).await.map_err(|e| {
+                progress.clear();
+                Error::failed(format!(
+                    "Failed to list the resources of stack {}: {}",
+                    stack, e
+                ))
+            })?;
+            found.extend(
+                stack_groups
+                    .into_iter()
+                    .map(|group| (group, format!("stack {}", stack))),
+            );
+        }
+        if let Some(api_id) = &self.apigw {
+            let api_groups = api_log_groups(target, api_id, self.stage.as_deref())
+                .await
+                .map_err(|e| {
+                    progress.clear();
+                    Error::failed(format!(
+                        "Failed to look up the stages of API {}: {}",
+                        api_id, e
+                    ))
+                })?;
+            found.extend(
+                api_groups
+                    .into_iter()
+                    .map(|group| (group, format!("API {}", api_id))),
+            );
+        }
+        for (group, source) in found {
+            // Lambda 関数や API の実行ログのロググループは最初に書き込まれるまで作られない
+            match resolve_group_arn(client, &group).await {
+                Ok(_) => add(group),
+                Err(aws_sdk_cloudwatchlogs::Error::ResourceNotFoundException(_)) => {
+                    progress.clear();
+                    eprintln!(
+                        "Warning: Log group {} of {} does not exist yet",
+                        group, source
+                    );
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if let Some(prefix) = &self.group_prefix {
+            let request = GroupRequestOpt {
+                prefix: Some(prefix.clone()),
+                tags: self.tags.clone(),
+                accounts: self.accounts.clone(),
+                ..Default::default()
+            };
+            let matched = get_groups(client, &request, progress).await?;
+            matched
+                .into_iter()
+                .filter_map(|g| self.accounts.group_identifier(g))
+                .for_each(&mut add);
+        }
+        Ok(names)
+    }
+
+    /// Fails when the globs and `--group-prefix` matched nothing and no group was named,
+    /// as there is nothing to do.
+    pub fn ensure_found(&self, found: usize) -> Result<(), Error> {
+        if found == 0 {
+            let patterns: Vec<String> = self
+                .group
+                .iter()
+                .cloned()
+                .chain(
+                    self.group_prefix
+                        .iter()
+                        .map(|prefix| format!("{}*", prefix)),
+                )
+                .chain(self.stack.iter().map(|stack| format!("stack {}", stack)))
+                .chain(self.apigw.iter().map(|api_id| format!("API {}", api_id)))
+                .collect();
+            return Err(Error::Failed {
+                message: format!("No log groups match {}", patterns.join(", ")),
+                code: report::EXIT_NOT_FOUND,
+            });
+        }
+        Ok(())
+    }
+
+    /// Prints the selection for verbose output.
+    pub fn print_verbose(&self) {
+        for group in &self.group {
+            eprintln!("Log group name:                {}", group);
+        }
+        for function in &self.lambda {
+            eprintln!("Lambda function:               {}", function);
+        }
+        if let Some(stack) = &self.stack {
+            eprintln!("CloudFormation stack:          {}", stack);
+        }
+        if let Some(api_id) = &self.apigw {
+            eprintln!("API Gateway API:               {}", api_id);
+        }
+        if let Some(stage) = &self.stage {
+            eprintln!("API Gateway stage:             {}", stage);
+        }
+        if let Some(prefix) = &self.group_prefix {
+            eprintln!("Log group prefix:              {}", prefix);
+        }
+        for (key, value) in &self.tags {
+            eprintln!("Tag:                           {}={}", key, value);
+        }
+        self.accounts.print_verbose();
+        self.ecs.print_verbose();
+    }
+}
+
+/// Options that select the listed log groups.
+#[derive(Debug, Clone, Default, Args)]
+pub struct GroupRequestOpt {
+    /// Only lists log groups whose names start with this prefix, e.g. `/aws/lambda/`.
+    #[arg(long)]
+    pub prefix: Option<String>,
+
+    /// Only lists log groups whose names match this regular expression.
+    /// Applied client-side while paginating.
+    #[arg(long = "match", value_name = "REGEX")]
+    pub name_match: Option<Regex>,
+
+    /// Stops paginating once this many log groups have been listed.
+    #[arg(long, value_name = "N")]
+    pub limit: Option<usize>,
+
+    /// Only lists log groups that have this tag, as `KEY=VALUE`. Repeat to require several tags.
+    /// Looked up with ListTagsForResource for each group that passes the other filters.
+    #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = parse_tag)]
+    pub tags: Vec<(String, String)>,
+
+    #[command(flatten)]
+    pub accounts: LinkedAccountsOpt,
+}
+
+impl GroupRequestOpt {
+    /// Whether the log group passes the client-side filters.
+    fn accepts(&self, group: &LogGroup) -> bool {
+        match &self.name_match {
+            Some(re) => re.is_match(group.log_group_name().unwrap_or_default()),
+            None => true,
+        }
+    }
+
+    /// Whether `count` log groups are enough to stop paginating.
+    fn reached_limit(&self, count: usize) -> bool {
+        self.limit.is_some_and(|limit| count >= limit)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ListGroupsOpt {
+    #[command(flatten)]
+    pub request: GroupRequestOpt,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+
+    /// Prints each log group with a template such as `{name}\t{retentionInDays}\t{storedBytes}`.
+    /// Fields: name, creationTime, retentionInDays, storedBytes, kmsKeyId, logGroupClass, arn,
+    /// profile and region when listing with several profiles or Regions,
+    /// and accountId when listing linked source accounts.
+    #[arg(long, conflicts_with = "output", value_parser = parse_group_template)]
+    pub format: Option<Template>,
+
+    /// Lists the log groups again every INTERVAL, e.g. `30s`, until interrupted.
+    /// Each listing marks the log groups that are new (`+`) or gone (`-`). Text output only.
+    #[arg(long, value_name = "INTERVAL", value_parser = parse_duration, conflicts_with = "format")]
+    pub watch: Option<Duration>,
+}
+
+impl ListGroupsOpt {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.watch.is_some() && self.output != OutputFormat::Text {
+            true => Err("--watch only prints text output".to_string()),
+            false => Ok(()),
+        }
+    }
+}
+
+/// How many ListTagsForResource calls are made at the same time when filtering by tag.
+const TAG_CONCURRENCY: usize = 10;
+
+/// The tags of each log group ARN looked up so far, so that groups found by several globs
+/// or prefixes are only looked up once.
+static TAG_CACHE: Mutex<BTreeMap<String, HashMap<String, String>>> = Mutex::new(BTreeMap::new());
+
+/// The tags of a log group, or `None` if they cannot be read.
+async fn group_tags(
+    client: &Client,
+    group: &LogGroup,
+    progress: &Progress,
+) -> Option<HashMap<String, String>> {
+    let arn = group.arn()?;
+    let arn = arn.strip_suffix(":*").unwrap_or(arn);
+    if let Some(tags) = TAG_CACHE.lock().unwrap().get(arn) {
+        return Some(tags.clone());
+    }
+    match client
+        .list_tags_for_resource()
+        .resource_arn(arn)
+        .send()
+        .await
+    {
+        Ok(output) => {
+            let tags = output.tags.unwrap_or_default();
+            TAG_CACHE
+                .lock()
+                .unwrap()
+                .insert(arn.to_string(), tags.clone());
+            Some(tags)
+        }
+        Err(e) => {
+            progress.clear();
+            report::warn(&format!("Failed to retrieve the tags of {}", arn), &e);
+            progress.error();
+            None
+        }
+    }
+}
+
+/// Keeps the log groups that have every one of `tags`, in their original order.
+async fn filter_by_tags(
+    client: &Client,
+    groups: Vec<LogGroup>,
+    tags: &[(String, String)],
+    progress: &Progress,
+) -> Vec<LogGroup> {
+    stream::iter(groups)
+        .map(|group| async move {
+            let group_tags = group_tags(client, &group, progress).await?;
+            tags.iter()
+                .all(|(key, value)| group_tags.get(key) == Some(value))
+                .then_some(group)
+        })
+        .buffered(TAG_CONCURRENCY)
+        .filter_map(|group| async move { group })
+        .collect()
+        .await
+}
+
+/// Lists the log groups that pass the request's filters, up to its limit.
+pub async fn get_groups(
+    client: &Client,
+    request: &GroupRequestOpt,
+    progress: &Progress,
+) -> Result<Vec<LogGroup>, aws_sdk_cloudwatchlogs::Error> {
+    let mut pages = request
+        .accounts
+        .apply(client.describe_log_groups())
+        .set_log_group_name_prefix(request.prefix.clone())
+        .into_paginator()
+        .send();
+
+    let mut groups: Vec<LogGroup> = Vec::new();
+
+    'pages: while !request.reached_limit(groups.len()) {
+        let Some(result) = pages.next().await else {
+            break;
+        };
+        match result {
+            Ok(page) => {
+                let before = groups.len();
+                let mut accepted: Vec<LogGroup> = page
+                    .log_groups
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|log_group| request.accepts(log_group))
+                    .collect();
+                if !request.tags.is_empty() {
+                    accepted = filter_by_tags(client, accepted, &request.tags, progress).await;
+                }
+                for log_group in accepted {
+                    if request.reached_limit(groups.len()) {
+                        progress.page(groups.len() - before);
+                        break 'pages;
+                    }
+                    groups.push(log_group);
+                }
+                progress.page(groups.len() - before);
+            }
+            Err(e) => {
+                progress.clear();
+                report::warn("Failed to retrieve a log group", &e);
+                progress.error();
+            }
+        }
+    }
+    Ok(groups)
+}
+
+/// How many suggestions are printed for a log group that does not exist.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// How many log groups are compared when no group shares the missing group's path.
+const MAX_SUGGESTION_CANDIDATES: usize = 5000;
+
+/// Explains a ResourceNotFoundException: prints `did you mean` suggestions for each of the named
+/// log groups that does not exist. Returns whether any does not, as otherwise the missing
+/// resource was something else, such as a log stream.
+pub async fn report_missing(client: &Client, names: &[String]) -> bool {
+    let progress = Progress::new("groups");
+    let mut missing = false;
+    for name in names
+        .iter()
+        .filter(|name| !is_glob(name) && group_arn(name).is_none())
+    {
+        let Some(suggestions) = suggest_groups(client, name, &progress).await else {
+            continue;
+        };
+        progress.clear();
+        missing = true;
+        eprintln!("Error: Log group `{}` does not exist.", name);
+        if !suggestions.is_empty() {
+            eprintln!("Did you mean:");
+            for suggestion in suggestions {
+                eprintln!("    {}", suggestion);
+            }
+        }
+    }
+    missing
+}
+
+/// Finds existing log groups with names like `name`, or `None` if `name` exists.
+/// Groups under the same path, such as `/aws/lambda/`, are compared first.
+async fn suggest_groups(client: &Client, name: &str, progress: &Progress) -> Option<Vec<String>> {
+    let parent = &name[..name.rfind('/').map_or(0, |i| i + 1)];
+    let listed = |prefix: Option<&str>| {
+        let request = GroupRequestOpt {
+            prefix: prefix.map(str::to_string),
+            limit: Some(MAX_SUGGESTION_CANDIDATES),
+            ..Default::default()
+        };
+        async move {
+            get_groups(client, &request, progress)
+                .await
+                .unwrap_or_default()
+        }
+    };
+    let mut candidates = match parent.is_empty() {
+        true => Vec::new(),
+        false => listed(Some(parent)).await,
+    };
+    if candidates.is_empty() {
+        candidates = listed(None).await;
+    }
+    let names: Vec<String> = candidates
+        .into_iter()
+        .filter_map(|group| group.log_group_name)
+        .collect();
+    if names.iter().any(|candidate| candidate == name) {
+        return None;
+    }
+
+    // 編集距離が近いか、名前を含むものを候補にする
+    let wanted = name.to_lowercase();
+    let leaf = &wanted[parent.len()..];
+    let threshold = (wanted.chars().count() / 4).max(2);
+    let mut scored: Vec<(usize, String)> = names
+        .into_iter()
+        .filter_map(|candidate| {
+            let lower = candidate.to_lowercase();
+            let distance = edit_distance(&wanted, &lower);
+            let related = !leaf.is_empty() && lower.contains(leaf);
+            (distance <= threshold || related).then_some((distance, candidate))
+        })
+        .collect();
+    scored.sort();
+    Some(
+        scored
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, candidate)| candidate)
+            .collect(),
+    )
+}
+
+/// The Levenshtein distance between two strings, counted in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// How long the log group keeps events, e.g. `30 days`.
+pub fn format_retention(group: &LogGroup) -> String {
+    match group.retention_in_days() {
+        Some(days) => format!("{} days", days),
+        None => "Never expire".to_string(),
+    }
+}
+
+/// Describes a log group for structured output. Times are epoch milliseconds.
+/// `show_account` adds the account, for listings that include linked source accounts.
+fn group_record(target: &Target, group: &LogGroup, show_account: bool) -> Record {
+    let record = match show_account {
+        true => target.record().field("accountId", group_account(group)),
+        false => target.record(),
+    };
+    record
+        .field("name", group.log_group_name())
+        .field("creationTime", group.creation_time())
+        .field("retentionInDays", group.retention_in_days().map(i64::from))
+        .field("storedBytes", group.stored_bytes())
+        .field("kmsKeyId", group.kms_key_id())
+        .field("logGroupClass", group.log_group_class().map(|c| c.as_str()))
+        .field("arn", group.arn())
+}
+
+/// Describes a log group as a row of the `table` output.
+fn group_table_row(target: &Target, group: &LogGroup, show_account: bool) -> Record {
+    let row = match show_account {
+        true => target.table_row().field("ACCOUNT", group_account(group)),
+        false => target.table_row(),
+    };
+    row.field("NAME", group.log_group_name())
+        .field("RETENTION", format_retention(group).as_str())
+        .field(
+            "SIZE",
+            format_bytes(group.stored_bytes().unwrap_or_default()).as_str(),
+        )
+        .field(
+            "CREATED",
+            group.creation_time().map(format_timestamp).as_deref(),
+        )
+        .field("CLASS", group.log_group_class().map(|c| c.as_str()))
+        .field("KMS KEY", group.kms_key_id())
+}
+
+/// Prints every log group in each target's Region.
+/// Verbose text output adds each group's retention, size and KMS key.
+pub async fn run(
+    targets: &[Target],
+    opt: ListGroupsOpt,
+    verbose: bool,
+    color: bool,
+) -> Result<(), Error> {
+    let ListGroupsOpt {
+        request,
+        output,
+        format,
+        watch,
+    } = opt;
+
+    if verbose {
+        if let Some(prefix) = &request.prefix {
+            eprintln!("Log group prefix:              {}", prefix);
+        }
+        for (key, value) in &request.tags {
+            eprintln!("Tag:                           {}={}", key, value);
+        }
+        request.accounts.print_verbose();
+        eprintln!();
+    }
+    let show_account = request.accounts.enabled();
+    if let Some(interval) = watch {
+        return watch_groups(targets, &request, interval, color).await;
+    }
+
+    let progress = Progress::new("groups");
+    let results = join_all(
+        targets
+            .iter()
+            .map(|target| get_groups(&target.client, &request, &progress)),
+    )
+    .await;
+    progress.clear();
+    let mut listed = Vec::new();
+    for (target, result) in targets.iter().zip(results) {
+        listed.push((target, result?));
+    }
+    completions::remember_groups(
+        listed
+            .iter()
+            .flat_map(|(_, groups)| groups.iter().filter_map(|group| group.log_group_name())),
+    );
+
+    if let Some(template) = &format {
+        for (target, groups) in &listed {
+            for group in groups {
+                outln!(
+                    "{}",
+                    template.render(&group_record(target, group, show_account))
+                );
+            }
+        }
+        return Ok(());
+    }
+    let records = || {
+        listed
+            .iter()
+            .flat_map(|(target, groups)| {
+                groups
+                    .iter()
+                    .map(|group| group_record(target, group, show_account))
+            })
+            .collect::<Vec<_>>()
+    };
+    match output {
+        OutputFormat::Text => {
+            let count: usize = listed.iter().map(|(_, groups)| groups.len()).sum();
+            if !output::is_quiet() {
+                outln!("Found {} groups:", count);
+            }
+
+            for (target, groups) in &listed {
+                for group in groups {
+                    let prefix = match show_account {
+                        true => format!(
+                            "{}{}\t",
+                            target.text_prefix(),
+                            group_account(group).unwrap_or("-")
+                        ),
+                        false => target.text_prefix(),
+                    };
+                    match group.log_group_name() {
+                        Some(group_name) if verbose => outln!(
+                            "{}{}\t{}\t{}\t{}",
+                            prefix,
+                            group_name,
+                            format_retention(group),
+                            format_bytes(group.stored_bytes().unwrap_or_default()),
+                            group.kms_key_id().unwrap_or("-")
+                        ),
+                        Some(group_name) => outln!("{}{}", prefix, group_name),
+                        None => outln!("{}No group name found", prefix),
+                    }
+                }
+            }
+        }
+        OutputFormat::Json => outln!("{}", output::to_json_array(&records())),
+        OutputFormat::Ndjson => {
+            for record in records() {
+                outln!("{}", record.to_json());
+            }
+        }
+        OutputFormat::Csv => out!("{}", output::to_csv(&records(), true)),
+        OutputFormat::Yaml => {
+            let records = records();
+            match records.is_empty() {
+                true => outln!("[]"),
+                false => out!("{}", output::to_yaml_items(&records)),
+            }
+        }
+        OutputFormat::Table => {
+            let rows: Vec<Record> = listed
+                .iter()
+                .flat_map(|(target, groups)| {
+                    groups
+                        .iter()
+                        .map(|group| group_table_row(target, group, show_account))
+                })
+                .collect();
+            out!("{}", output::to_table(&rows));
+        }
+    }
+    Ok(())
+}
+
+/// Lists the log groups every `interval` and prints what changed, until interrupted.
+async fn watch_groups(
+    targets: &[Target],
+    request: &GroupRequestOpt,
+    interval: Duration,
+    color: bool,
+) -> Result<(), Error> {
+    let show_account = request.accounts.enabled();
+    let mut watch = Watch::new(interval, color);
+    let progress = Progress::hidden();
+    loop {
+        let results = join_all(
+            targets
+                .iter()
+                .map(|target| get_groups(&target.client, request, &progress)),
+        )
+        .await;
+        let mut entries = Vec::new();
+        for (target, result) in targets.iter().zip(results) {
+            for group in result? {
+                let mut key = target.text_prefix();
+                if show_account {
+                    key.push_str(&format!("{}\t", group_account(&group).unwrap_or("-")));
+                }
+                key.push_str(group.log_group_name().unwrap_or("-"));
+                entries.push(Entry {
+                    line: key.clone(),
+                    key,
+                    last_event: None,
+                });
+            }
+        }
+        watch.print("groups", entries)?;
+        tokio::time::sleep(interval).await;
+    }
+}
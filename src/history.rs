@@ -0,0 +1,285 @@
+//! A history of the Logs Insights queries and filtered searches that were run, kept like a shell
+//! history in `$XDG_STATE_HOME/print-log-streams/history`, or the same under `~/.local/state`.
+//! Each line is a JSON object with the time and the arguments, so that `history run` can run
+//! the command again exactly as it was typed, bookmarks included.
+
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use clap::{Args, Subcommand};
+
+use crate::jq::Json;
+use crate::outln;
+use crate::output::{self, format_timestamp, OutputFormat, Record};
+use crate::report;
+use crate::time_range::now_millis;
+
+/// The most commands kept. Older ones are dropped when a new one is recorded.
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Debug, Args)]
+pub struct HistoryOpt {
+    #[command(subcommand)]
+    pub command: HistoryCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum HistoryCommand {
+    /// Lists the recorded commands, the newest last, numbered for `history run`.
+    List(ListHistoryOpt),
+
+    /// Runs a recorded command again, with the same arguments.
+    Run(RunHistoryOpt),
+
+    /// Forgets every recorded command.
+    Clear,
+}
+
+#[derive(Debug, Args)]
+pub struct ListHistoryOpt {
+    /// Only lists the commands that contain this text, e.g. a log group or a query field.
+    #[arg(long)]
+    pub grep: Option<String>,
+
+    /// Lists only the newest commands.
+    #[arg(short = 'n', long, default_value_t = 20)]
+    pub limit: usize,
+
+    /// Lists every recorded command.
+    #[arg(long, conflicts_with = "limit")]
+    pub all: bool,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct RunHistoryOpt {
+    /// The number of the command in `history list`. Negative numbers count back from the newest,
+    /// so `-1` is the last command.
+    #[arg(allow_negative_numbers = true)]
+    pub number: i64,
+
+    /// Prints the command line instead of running it.
+    #[arg(long)]
+    pub print: bool,
+}
+
+/// A recorded command.
+struct Entry {
+    timestamp: i64,
+    args: Vec<String>,
+}
+
+impl Entry {
+    fn to_json(&self) -> String {
+        Json::Object(vec![
+            ("timestamp".to_string(), Json::Number(self.timestamp as f64)),
+            (
+                "args".to_string(),
+                Json::Array(self.args.iter().cloned().map(Json::String).collect()),
+            ),
+        ])
+        .to_json()
+    }
+
+    fn from_json(line: &str) -> Option<Entry> {
+        let json = Json::parse(line).ok()?;
+        let Some(Json::Number(timestamp)) = json.get_path("timestamp") else {
+            return None;
+        };
+        let Some(Json::Array(args)) = json.get_path("args") else {
+            return None;
+        };
+        let args = args
+            .iter()
+            .map(|arg| match arg {
+                Json::String(arg) => Some(arg.clone()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Entry {
+            timestamp: *timestamp as i64,
+            args,
+        })
+    }
+
+    /// The arguments quoted for a POSIX shell where needed.
+    fn command_line(&self) -> String {
+        self.args
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg.chars().all(|c| {
+            c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | ':' | '=' | '@' | ',')
+        });
+    match plain {
+        true => arg.to_string(),
+        false => format!("'{}'", arg.replace('\'', r"'\''")),
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?)
+            .join(".local")
+            .join("state"),
+    };
+    Some(dir.join("print-log-streams").join("history"))
+}
+
+/// The recorded commands, the oldest first. Lines that cannot be read are skipped.
+fn entries() -> Vec<Entry> {
+    path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|text| text.lines().filter_map(Entry::from_json).collect())
+        .unwrap_or_default()
+}
+
+/// Records a command with its arguments, without the program name.
+/// Failing to write the history is not worth a warning, as the command itself can still run.
+pub fn record(args: &[OsString]) {
+    let Some(path) = path() else {
+        return;
+    };
+    let entry = Entry {
+        timestamp: now_millis(),
+        args: args
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect(),
+    };
+    let _ = append(&path, &entry);
+}
+
+fn append(path: &Path, entry: &Entry) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut entries = entries();
+    if entries.len() < MAX_ENTRIES {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        return writeln!(file, "{}", entry.to_json());
+    }
+    // 上限を超えたら古いものを落として書き直す
+    entries.drain(..entries.len() + 1 - MAX_ENTRIES);
+    let mut text: String = entries
+        .iter()
+        .map(|entry| format!("{}\n", entry.to_json()))
+        .collect();
+    text.push_str(&format!("{}\n", entry.to_json()));
+    fs::write(path, text)
+}
+
+/// Runs a `history` subcommand, and returns the code to exit with.
+pub fn run(opt: HistoryOpt) -> Result<i32, String> {
+    match opt.command {
+        HistoryCommand::List(opt) => list(opt).map(|()| 0),
+        HistoryCommand::Run(opt) => rerun(opt),
+        HistoryCommand::Clear => {
+            let Some(path) = path() else {
+                return Ok(0);
+            };
+            match fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    Err(format!("Failed to remove {}: {}", path.display(), e))
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+}
+
+fn list(opt: ListHistoryOpt) -> Result<(), String> {
+    let ListHistoryOpt {
+        grep,
+        limit,
+        all,
+        output,
+    } = opt;
+    let mut numbered: Vec<(usize, Entry)> = entries()
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| (i + 1, entry))
+        .filter(|(_, entry)| {
+            grep.as_ref()
+                .is_none_or(|text| entry.command_line().contains(text.as_str()))
+        })
+        .collect();
+    if !all && numbered.len() > limit {
+        numbered.drain(..numbered.len() - limit);
+    }
+
+    match output {
+        OutputFormat::Text => {
+            for (number, entry) in &numbered {
+                outln!(
+                    "{:>5}  {}  {}",
+                    number,
+                    format_timestamp(entry.timestamp),
+                    entry.command_line()
+                );
+            }
+        }
+        format => {
+            let records: Vec<Record> = numbered
+                .iter()
+                .map(|(number, entry)| {
+                    Record::new()
+                        .field("number", *number as i64)
+                        .field("timestamp", entry.timestamp)
+                        .field("command", entry.command_line().as_str())
+                })
+                .collect();
+            let rows: Vec<Record> = numbered
+                .iter()
+                .map(|(number, entry)| {
+                    Record::new()
+                        .field("#", *number as i64)
+                        .field("TIME", format_timestamp(entry.timestamp).as_str())
+                        .field("COMMAND", entry.command_line().as_str())
+                })
+                .collect();
+            output::print_records(format, &records, &rows);
+        }
+    }
+    Ok(())
+}
+
+/// Runs the recorded arguments with this program, and returns its exit status.
+fn rerun(opt: RunHistoryOpt) -> Result<i32, String> {
+    let entries = entries();
+    let index = match opt.number {
+        n if n > 0 => n as usize - 1,
+        n if n < 0 => entries.len().wrapping_sub(n.unsigned_abs() as usize),
+        _ => usize::MAX,
+    };
+    let entry = entries
+        .get(index)
+        .ok_or_else(|| format!("there is no command {} in the history", opt.number))?;
+    if opt.print {
+        outln!("{}", entry.command_line());
+        return Ok(0);
+    }
+    if !output::is_quiet() {
+        eprintln!("{}", entry.command_line());
+    }
+    let program = std::env::current_exe()
+        .map_err(|e| format!("cannot find this program to run it again: {}", e))?;
+    let status = process::Command::new(program)
+        .args(&entry.args)
+        .status()
+        .map_err(|e| format!("Failed to run the command again: {}", e))?;
+    Ok(status.code().unwrap_or(report::EXIT_FAILURE))
+}
@@ -0,0 +1,883 @@
+//! A small subset of the jq language, applied to JSON log messages by `--jq`.
+//!
+//! Supported: `.`, `.foo`, `."foo"`, `.[0]`, `.["foo"]`, `.[]`, `|`, `,`, `( )`,
+//! `[ ]` and `{ }` construction, literals, `==`, `!=`, `<`, `<=`, `>`, `>=`, `and`, `or`,
+//! and the functions `select`, `map`, `not`, `length`, `keys`, `has`, `test`, `type`,
+//! `tostring`, `ascii_downcase` and `empty`.
+
+use std::cmp::Ordering;
+
+use aws_smithy_json::deserialize::{error::DeserializeError, json_token_iter, EscapedStr, Token};
+use aws_smithy_json::serialize::JsonValueWriter;
+use aws_smithy_types::Number;
+use regex_lite::Regex;
+
+/// How deeply a JSON value or a filter can nest. A message nested deeper is treated as not
+/// being JSON and a filter nested deeper is rejected, so that neither can exhaust the stack while
+/// it is parsed, evaluated, compared or written out: every value is parsed or built by a filter.
+pub const MAX_DEPTH: usize = 128;
+
+/// A JSON value. Objects keep the order of their keys so that reshaped output reads naturally.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a JSON document, rejecting anything after the first value.
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut tokens = json_token_iter(text.as_bytes());
+        let value = Self::parse_value(&mut tokens, 0)?;
+        match tokens.next() {
+            None => Ok(value),
+            Some(_) => Err("unexpected data after the JSON value".to_string()),
+        }
+    }
+
+    /// Parses the next value, inside `depth` arrays and objects.
+    fn parse_value<'a>(
+        tokens: &mut impl Iterator<Item = Result<Token<'a>, DeserializeError>>,
+        depth: usize,
+    ) -> Result<Json, String> {
+        let token = tokens
+            .next()
+            .ok_or("unexpected end of JSON")?
+            .map_err(|e| e.to_string())?;
+        Self::from_token(token, tokens, depth)
+    }
+
+    fn from_token<'a>(
+        token: Token<'a>,
+        tokens: &mut impl Iterator<Item = Result<Token<'a>, DeserializeError>>,
+        depth: usize,
+    ) -> Result<Json, String> {
+        if matches!(token, Token::StartArray { .. } | Token::StartObject { .. })
+            && depth >= MAX_DEPTH
+        {
+            return Err(format!("JSON nested more than {} levels deep", MAX_DEPTH));
+        }
+        let unescape = |s: EscapedStr<'_>| {
+            s.to_unescaped()
+                .map(|s| s.into_owned())
+                .map_err(|e| e.to_string())
+        };
+        Ok(match token {
+            Token::ValueNull { .. } => Json::Null,
+            Token::ValueBool { value, .. } => Json::Bool(value),
+            Token::ValueNumber { value, .. } => Json::Number(match value {
+                Number::PosInt(n) => n as f64,
+                Number::NegInt(n) => n as f64,
+                Number::Float(f) => f,
+            }),
+            Token::ValueString { value, .. } => Json::String(unescape(value)?),
+            Token::StartArray { .. } => {
+                let mut items = Vec::new();
+                loop {
+                    let token = tokens
+                        .next()
+                        .ok_or("unexpected end of JSON")?
+                        .map_err(|e| e.to_string())?;
+                    if let Token::EndArray { .. } = token {
+                        break;
+                    }
+                    items.push(Self::from_token(token, tokens, depth + 1)?);
+                }
+                Json::Array(items)
+            }
+            Token::StartObject { .. } => {
+                let mut fields = Vec::new();
+                loop {
+                    let token = tokens
+                        .next()
+                        .ok_or("unexpected end of JSON")?
+                        .map_err(|e| e.to_string())?;
+                    match token {
+                        Token::EndObject { .. } => break,
+                        Token::ObjectKey { key, .. } => {
+                            let key = unescape(key)?;
+                            let value = Self::parse_value(tokens, depth + 1)?;
+                            fields.push((key, value));
+                        }
+                        _ => return Err("expected an object key".to_string()),
+                    }
+                }
+                Json::Object(fields)
+            }
+            Token::EndArray { .. } | Token::EndObject { .. } | Token::ObjectKey { .. } => {
+                return Err("unexpected token in JSON".to_string())
+            }
+        })
+    }
+
+    /// Looks up a dotted path of object keys such as `ctx.requestId`.
+    pub fn get_path(&self, path: &str) -> Option<&Json> {
+        path.split('.').try_fold(self, |value, key| match value {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        })
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Bool(_) => "boolean",
+            Json::Number(_) => "number",
+            Json::String(_) => "string",
+            Json::Array(_) => "array",
+            Json::Object(_) => "object",
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        !matches!(self, Json::Null | Json::Bool(false))
+    }
+
+    /// Serializes the value as compact JSON.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(JsonValueWriter::new(&mut out));
+        out
+    }
+
+    fn write_json(&self, writer: JsonValueWriter<'_>) {
+        match self {
+            Json::Null => writer.null(),
+            Json::Bool(b) => writer.boolean(*b),
+            // 整数として表せる値は小数点なしで出力する
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 9e15 && *n < 0.0 => {
+                writer.number(Number::NegInt(*n as i64))
+            }
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 9e15 => {
+                writer.number(Number::PosInt(*n as u64))
+            }
+            Json::Number(n) => writer.number(Number::Float(*n)),
+            Json::String(s) => writer.string(s),
+            Json::Array(items) => {
+                let mut array = writer.start_array();
+                for item in items {
+                    item.write_json(array.value());
+                }
+                array.finish();
+            }
+            Json::Object(fields) => {
+                let mut object = writer.start_object();
+                for (key, value) in fields {
+                    value.write_json(object.key(key));
+                }
+                object.finish();
+            }
+        }
+    }
+
+    /// Renders a filter result as a line of output: strings as-is, like `jq -r`, and
+    /// everything else as compact JSON.
+    pub fn to_text(&self) -> String {
+        match self {
+            Json::String(s) => s.clone(),
+            other => other.to_json(),
+        }
+    }
+
+    /// jq's ordering: null < false < true < numbers < strings < arrays < objects.
+    fn compare(&self, other: &Json) -> Ordering {
+        fn rank(value: &Json) -> u8 {
+            match value {
+                Json::Null => 0,
+                Json::Bool(false) => 1,
+                Json::Bool(true) => 2,
+                Json::Number(_) => 3,
+                Json::String(_) => 4,
+                Json::Array(_) => 5,
+                Json::Object(_) => 6,
+            }
+        }
+        match (self, other) {
+            (Json::Number(a), Json::Number(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+            (Json::String(a), Json::String(b)) => a.cmp(b),
+            (Json::Array(a), Json::Array(b)) => {
+                for (x, y) in a.iter().zip(b) {
+                    match x.compare(y) {
+                        Ordering::Equal => {}
+                        other => return other,
+                    }
+                }
+                a.len().cmp(&b.len())
+            }
+            (Json::Object(a), Json::Object(b)) => {
+                // キーの順序は比較に影響しない
+                let sorted = |fields: &[(String, Json)]| {
+                    let mut fields = fields.to_vec();
+                    fields.sort_by(|x, y| x.0.cmp(&y.0));
+                    fields
+                };
+                let (a, b) = (sorted(a), sorted(b));
+                let keys = |fields: &[(String, Json)]| {
+                    Json::Array(
+                        fields
+                            .iter()
+                            .map(|(k, _)| Json::String(k.clone()))
+                            .collect(),
+                    )
+                };
+                match keys(&a).compare(&keys(&b)) {
+                    Ordering::Equal => {
+                        let values = |fields: &[(String, Json)]| {
+                            Json::Array(fields.iter().map(|(_, v)| v.clone()).collect())
+                        };
+                        values(&a).compare(&values(&b))
+                    }
+                    other => other,
+                }
+            }
+            (a, b) => rank(a).cmp(&rank(b)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A parsed filter expression.
+#[derive(Debug, Clone)]
+enum Expr {
+    Identity,
+    Literal(Json),
+    /// `.foo`, `.["foo"]` or `.[0]` applied to the output of the inner expression.
+    Index(Box<Expr>, Box<Expr>),
+    /// `.[]`
+    Iterate(Box<Expr>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Compare(CompareOp, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Array(Option<Box<Expr>>),
+    Object(Vec<(Expr, Expr)>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Lexeme {
+    Dot,
+    Field(String),
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(CompareOp),
+    Punct(char),
+}
+
+fn lex(source: &str) -> Result<Vec<Lexeme>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut lexemes = Vec::new();
+    let mut i = 0;
+    let is_ident_start = |c: char| c.is_ascii_alphabetic() || c == '_';
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '.' if chars.get(i + 1).copied().is_some_and(is_ident_start) => {
+                let start = i + 1;
+                i = start;
+                while i < chars.len() && is_ident(chars[i]) {
+                    i += 1;
+                }
+                lexemes.push(Lexeme::Field(chars[start..i].iter().collect()));
+            }
+            '.' => {
+                lexemes.push(Lexeme::Dot);
+                i += 1;
+            }
+            '"' => {
+                let mut s = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err("unterminated string".to_string()),
+                        Some('"') => break,
+                        Some('\\') => {
+                            i += 1;
+                            match chars.get(i) {
+                                Some('n') => s.push('\n'),
+                                Some('t') => s.push('\t'),
+                                Some('r') => s.push('\r'),
+                                Some(&c @ ('"' | '\\' | '/')) => s.push(c),
+                                Some('u') => {
+                                    let hex: String = chars
+                                        .get(i + 1..i + 5)
+                                        .unwrap_or_default()
+                                        .iter()
+                                        .collect();
+                                    let code = u32::from_str_radix(&hex, 16)
+                                        .ok()
+                                        .and_then(char::from_u32)
+                                        .ok_or_else(|| format!("invalid escape `\\u{}`", hex))?;
+                                    s.push(code);
+                                    i += 4;
+                                }
+                                _ => return Err("invalid escape in string".to_string()),
+                            }
+                        }
+                        Some(&c) => s.push(c),
+                    }
+                    i += 1;
+                }
+                i += 1;
+                lexemes.push(Lexeme::Str(s));
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let n = text
+                    .parse()
+                    .map_err(|_| format!("invalid number `{}`", text))?;
+                lexemes.push(Lexeme::Num(n));
+            }
+            c if is_ident_start(c) => {
+                let start = i;
+                while i < chars.len() && is_ident(chars[i]) {
+                    i += 1;
+                }
+                lexemes.push(Lexeme::Ident(chars[start..i].iter().collect()));
+            }
+            '=' | '!' | '<' | '>' => {
+                let next_is_eq = chars.get(i + 1) == Some(&'=');
+                let op = match (c, next_is_eq) {
+                    ('=', true) => CompareOp::Eq,
+                    ('!', true) => CompareOp::Ne,
+                    ('<', true) => CompareOp::Le,
+                    ('>', true) => CompareOp::Ge,
+                    ('<', false) => CompareOp::Lt,
+                    ('>', false) => CompareOp::Gt,
+                    _ => return Err(format!("unexpected `{}`", c)),
+                };
+                i += if next_is_eq { 2 } else { 1 };
+                lexemes.push(Lexeme::Op(op));
+            }
+            '|' | ',' | ':' | '(' | ')' | '[' | ']' | '{' | '}' | '-' => {
+                lexemes.push(Lexeme::Punct(c));
+                i += 1;
+            }
+            other => return Err(format!("unexpected `{}`", other)),
+        }
+    }
+    Ok(lexemes)
+}
+
+struct Parser {
+    lexemes: Vec<Lexeme>,
+    pos: usize,
+    /// The nesting so far, counting each chained operator as a level, as it nests the expression
+    /// before it one level deeper.
+    depth: usize,
+}
+
+impl Parser {
+    /// Goes one level deeper, failing past [`MAX_DEPTH`].
+    fn nest(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        match self.depth > MAX_DEPTH {
+            true => Err(format!(
+                "the filter is nested more than {} levels deep",
+                MAX_DEPTH
+            )),
+            false => Ok(()),
+        }
+    }
+
+    fn peek(&self) -> Option<&Lexeme> {
+        self.lexemes.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Lexeme> {
+        let lexeme = self.lexemes.get(self.pos).cloned();
+        self.pos += 1;
+        lexeme
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Lexeme::Punct(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(format!("expected `{}`", c)),
+        }
+    }
+
+    fn eat_ident(&mut self, ident: &str) -> bool {
+        if matches!(self.peek(), Some(Lexeme::Ident(i)) if i == ident) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn pipe(&mut self) -> Result<Expr, String> {
+        let depth = self.depth;
+        let mut left = self.comma()?;
+        while self.eat('|') {
+            self.nest()?;
+            left = Expr::Pipe(Box::new(left), Box::new(self.comma()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn comma(&mut self) -> Result<Expr, String> {
+        let depth = self.depth;
+        let mut left = self.or()?;
+        while self.eat(',') {
+            self.nest()?;
+            left = Expr::Comma(Box::new(left), Box::new(self.or()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    /// Every nested expression, inside brackets, braces or the arguments of a function,
+    /// starts here, so this is where the nesting is counted.
+    fn or(&mut self) -> Result<Expr, String> {
+        let depth = self.depth;
+        self.nest()?;
+        let mut left = self.and()?;
+        while self.eat_ident("or") {
+            self.nest()?;
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let depth = self.depth;
+        let mut left = self.compare()?;
+        while self.eat_ident("and") {
+            self.nest()?;
+            left = Expr::And(Box::new(left), Box::new(self.compare()?));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn compare(&mut self) -> Result<Expr, String> {
+        let left = self.postfix()?;
+        if let Some(Lexeme::Op(op)) = self.peek().cloned() {
+            self.pos += 1;
+            let right = self.postfix()?;
+            return Ok(Expr::Compare(op, Box::new(left), Box::new(right)));
+        }
+        Ok(left)
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let depth = self.depth;
+        let mut expr = self.primary()?;
+        loop {
+            if matches!(
+                self.peek(),
+                Some(Lexeme::Field(_) | Lexeme::Dot | Lexeme::Punct('['))
+            ) {
+                self.nest()?;
+            }
+            match self.peek() {
+                Some(Lexeme::Field(name)) => {
+                    let key = Expr::Literal(Json::String(name.clone()));
+                    self.pos += 1;
+                    expr = Expr::Index(Box::new(expr), Box::new(key));
+                }
+                Some(Lexeme::Dot)
+                    if matches!(
+                        self.lexemes.get(self.pos + 1),
+                        Some(Lexeme::Str(_) | Lexeme::Punct('['))
+                    ) =>
+                {
+                    self.pos += 1;
+                    expr = self.suffix(expr)?;
+                }
+                Some(Lexeme::Punct('[')) => expr = self.suffix(expr)?,
+                _ => {
+                    self.depth = depth;
+                    return Ok(expr);
+                }
+            }
+        }
+    }
+
+    /// Parses `"foo"`, `[expr]` or `[]` after a value.
+    fn suffix(&mut self, expr: Expr) -> Result<Expr, String> {
+        if let Some(Lexeme::Str(s)) = self.peek().cloned() {
+            self.pos += 1;
+            return Ok(Expr::Index(
+                Box::new(expr),
+                Box::new(Expr::Literal(Json::String(s))),
+            ));
+        }
+        self.expect('[')?;
+        if self.eat(']') {
+            return Ok(Expr::Iterate(Box::new(expr)));
+        }
+        let key = self.pipe()?;
+        self.expect(']')?;
+        Ok(Expr::Index(Box::new(expr), Box::new(key)))
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Lexeme::Dot) => match self.peek() {
+                Some(Lexeme::Str(_) | Lexeme::Punct('[')) => self.suffix(Expr::Identity),
+                _ => Ok(Expr::Identity),
+            },
+            Some(Lexeme::Field(name)) => Ok(Expr::Index(
+                Box::new(Expr::Identity),
+                Box::new(Expr::Literal(Json::String(name))),
+            )),
+            Some(Lexeme::Str(s)) => Ok(Expr::Literal(Json::String(s))),
+            Some(Lexeme::Num(n)) => Ok(Expr::Literal(Json::Number(n))),
+            Some(Lexeme::Punct('-')) => match self.next() {
+                Some(Lexeme::Num(n)) => Ok(Expr::Literal(Json::Number(-n))),
+                _ => Err("expected a number after `-`".to_string()),
+            },
+            Some(Lexeme::Punct('(')) => {
+                let expr = self.pipe()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some(Lexeme::Punct('[')) => {
+                if self.eat(']') {
+                    return Ok(Expr::Array(None));
+                }
+                let expr = self.pipe()?;
+                self.expect(']')?;
+                Ok(Expr::Array(Some(Box::new(expr))))
+            }
+            Some(Lexeme::Punct('{')) => self.object(),
+            Some(Lexeme::Ident(name)) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Json::Bool(true))),
+                "false" => Ok(Expr::Literal(Json::Bool(false))),
+                "null" => Ok(Expr::Literal(Json::Null)),
+                _ => {
+                    let mut args = Vec::new();
+                    if self.eat('(') {
+                        args.push(self.pipe()?);
+                        self.expect(')')?;
+                    }
+                    check_function(&name, args.len())?;
+                    Ok(Expr::Call(name, args))
+                }
+            },
+            Some(other) => Err(format!("unexpected {:?}", other)),
+            None => Err("unexpected end of filter".to_string()),
+        }
+    }
+
+    /// Parses `{a: .x, "b": .y, c}` after the opening brace.
+    fn object(&mut self) -> Result<Expr, String> {
+        let mut entries = Vec::new();
+        if self.eat('}') {
+            return Ok(Expr::Object(entries));
+        }
+        loop {
+            let key = match self.next() {
+                Some(Lexeme::Ident(name)) => name,
+                Some(Lexeme::Str(s)) => s,
+                _ => return Err("expected an object key".to_string()),
+            };
+            let value = match self.eat(':') {
+                // 値には `,` を含められないので comma より一段下から読む
+                true => self.or()?,
+                false => Expr::Index(
+                    Box::new(Expr::Identity),
+                    Box::new(Expr::Literal(Json::String(key.clone()))),
+                ),
+            };
+            entries.push((Expr::Literal(Json::String(key)), value));
+            if self.eat('}') {
+                return Ok(Expr::Object(entries));
+            }
+            self.expect(',')?;
+        }
+    }
+}
+
+fn check_function(name: &str, arity: usize) -> Result<(), String> {
+    let expected = match name {
+        "select" | "map" | "has" | "test" => 1,
+        "not" | "length" | "keys" | "type" | "tostring" | "ascii_downcase" | "empty" => 0,
+        _ => return Err(format!("unknown function `{}`", name)),
+    };
+    match arity == expected {
+        true => Ok(()),
+        false => Err(format!("{} takes {} argument(s)", name, expected)),
+    }
+}
+
+fn index(value: &Json, key: &Json) -> Result<Json, String> {
+    match (value, key) {
+        (Json::Null, _) => Ok(Json::Null),
+        (Json::Object(fields), Json::String(key)) => Ok(fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map_or(Json::Null, |(_, v)| v.clone())),
+        (Json::Array(items), Json::Number(n)) => {
+            let i = if *n < 0.0 { items.len() as f64 + n } else { *n };
+            Ok(items
+                .get(i as usize)
+                .filter(|_| i >= 0.0)
+                .cloned()
+                .unwrap_or(Json::Null))
+        }
+        (value, key) => Err(format!(
+            "cannot index {} with {}",
+            value.type_name(),
+            key.type_name()
+        )),
+    }
+}
+
+impl Expr {
+    fn eval(&self, input: &Json) -> Result<Vec<Json>, String> {
+        Ok(match self {
+            Expr::Identity => vec![input.clone()],
+            Expr::Literal(value) => vec![value.clone()],
+            Expr::Index(target, key) => {
+                let mut out = Vec::new();
+                for value in target.eval(input)? {
+                    for key in key.eval(input)? {
+                        out.push(index(&value, &key)?);
+                    }
+                }
+                out
+            }
+            Expr::Iterate(target) => {
+                let mut out = Vec::new();
+                for value in target.eval(input)? {
+                    match value {
+                        Json::Array(items) => out.extend(items),
+                        Json::Object(fields) => out.extend(fields.into_iter().map(|(_, v)| v)),
+                        other => return Err(format!("cannot iterate over {}", other.type_name())),
+                    }
+                }
+                out
+            }
+            Expr::Pipe(left, right) => {
+                let mut out = Vec::new();
+                for value in left.eval(input)? {
+                    out.extend(right.eval(&value)?);
+                }
+                out
+            }
+            Expr::Comma(left, right) => {
+                let mut out = left.eval(input)?;
+                out.extend(right.eval(input)?);
+                out
+            }
+            Expr::Compare(op, left, right) => {
+                let mut out = Vec::new();
+                for l in left.eval(input)? {
+                    for r in right.eval(input)? {
+                        let ordering = l.compare(&r);
+                        out.push(Json::Bool(match op {
+                            CompareOp::Eq => ordering == Ordering::Equal,
+                            CompareOp::Ne => ordering != Ordering::Equal,
+                            CompareOp::Lt => ordering == Ordering::Less,
+                            CompareOp::Le => ordering != Ordering::Greater,
+                            CompareOp::Gt => ordering == Ordering::Greater,
+                            CompareOp::Ge => ordering != Ordering::Less,
+                        }));
+                    }
+                }
+                out
+            }
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                let is_and = matches!(self, Expr::And(..));
+                let mut out = Vec::new();
+                for l in left.eval(input)? {
+                    // 左辺だけで結果が決まる場合は右辺を評価しない
+                    if l.is_truthy() != is_and {
+                        out.push(Json::Bool(!is_and));
+                        continue;
+                    }
+                    for r in right.eval(input)? {
+                        out.push(Json::Bool(r.is_truthy()));
+                    }
+                }
+                out
+            }
+            Expr::Array(None) => vec![Json::Array(Vec::new())],
+            Expr::Array(Some(inner)) => vec![Json::Array(inner.eval(input)?)],
+            Expr::Object(entries) => {
+                // 値が複数出力される場合は jq と同じく組み合わせごとにオブジェクトを作る
+                let mut objects = vec![Vec::new()];
+                for (key, value) in entries {
+                    let mut next = Vec::new();
+                    for fields in &objects {
+                        for key in key.eval(input)? {
+                            let Json::String(key) = key else {
+                                return Err("object keys must be strings".to_string());
+                            };
+                            for value in value.eval(input)? {
+                                let mut fields: Vec<(String, Json)> = fields.clone();
+                                fields.retain(|(k, _)| *k != key);
+                                fields.push((key.clone(), value));
+                                next.push(fields);
+                            }
+                        }
+                    }
+                    objects = next;
+                }
+                objects.into_iter().map(Json::Object).collect()
+            }
+            Expr::Call(name, args) => call(name, args, input)?,
+        })
+    }
+}
+
+fn call(name: &str, args: &[Expr], input: &Json) -> Result<Vec<Json>, String> {
+    Ok(match name {
+        "empty" => Vec::new(),
+        "select" => {
+            let mut out = Vec::new();
+            for condition in args[0].eval(input)? {
+                if condition.is_truthy() {
+                    out.push(input.clone());
+                }
+            }
+            out
+        }
+        "map" => {
+            let items = match input {
+                Json::Array(items) => items.clone(),
+                Json::Object(fields) => fields.iter().map(|(_, v)| v.clone()).collect(),
+                other => return Err(format!("cannot iterate over {}", other.type_name())),
+            };
+            let mut out = Vec::new();
+            for item in &items {
+                out.extend(args[0].eval(item)?);
+            }
+            vec![Json::Array(out)]
+        }
+        "not" => vec![Json::Bool(!input.is_truthy())],
+        "length" => vec![Json::Number(match input {
+            Json::Null => 0.0,
+            Json::Bool(_) => return Err("boolean has no length".to_string()),
+            Json::Number(n) => n.abs(),
+            Json::String(s) => s.chars().count() as f64,
+            Json::Array(items) => items.len() as f64,
+            Json::Object(fields) => fields.len() as f64,
+        })],
+        "keys" => match input {
+            Json::Object(fields) => {
+                let mut keys: Vec<String> = fields.iter().map(|(k, _)| k.clone()).collect();
+                keys.sort();
+                vec![Json::Array(keys.into_iter().map(Json::String).collect())]
+            }
+            Json::Array(items) => vec![Json::Array(
+                (0..items.len()).map(|i| Json::Number(i as f64)).collect(),
+            )],
+            other => return Err(format!("{} has no keys", other.type_name())),
+        },
+        "has" => {
+            let mut out = Vec::new();
+            for key in args[0].eval(input)? {
+                out.push(Json::Bool(match (input, &key) {
+                    (Json::Object(fields), Json::String(key)) => {
+                        fields.iter().any(|(k, _)| k == key)
+                    }
+                    (Json::Array(items), Json::Number(n)) => {
+                        *n >= 0.0 && (*n as usize) < items.len()
+                    }
+                    (value, key) => {
+                        return Err(format!(
+                            "cannot check whether {} has a {} key",
+                            value.type_name(),
+                            key.type_name()
+                        ))
+                    }
+                }));
+            }
+            out
+        }
+        "test" => {
+            let Json::String(text) = input else {
+                return Err(format!(
+                    "{} cannot be matched, as it is not a string",
+                    input.type_name()
+                ));
+            };
+            let mut out = Vec::new();
+            for pattern in args[0].eval(input)? {
+                let Json::String(pattern) = pattern else {
+                    return Err("test() needs a string pattern".to_string());
+                };
+                let re = Regex::new(&pattern).map_err(|e| e.to_string())?;
+                out.push(Json::Bool(re.is_match(text)));
+            }
+            out
+        }
+        "type" => vec![Json::String(input.type_name().to_string())],
+        "tostring" => vec![Json::String(input.to_text())],
+        "ascii_downcase" => match input {
+            Json::String(s) => vec![Json::String(s.to_ascii_lowercase())],
+            other => return Err(format!("{} cannot be lowercased", other.type_name())),
+        },
+        _ => unreachable!("functions are checked while parsing"),
+    })
+}
+
+/// A compiled `--jq` filter.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    /// Parses a filter such as `select(.level == "error") | {time, msg: .message}`.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            lexemes: lex(source)?,
+            pos: 0,
+            depth: 0,
+        };
+        let expr = parser.pipe()?;
+        if let Some(lexeme) = parser.peek() {
+            return Err(format!("unexpected {:?}", lexeme));
+        }
+        Ok(Filter { expr })
+    }
+
+    /// Runs the filter on a JSON message and renders each output as a line.
+    /// Messages that are not JSON, and filters that fail on them, produce nothing.
+    pub fn apply(&self, message: &str) -> Vec<String> {
+        let Ok(input) = Json::parse(message.trim()) else {
+            return Vec::new();
+        };
+        match self.expr.eval(&input) {
+            Ok(outputs) => outputs.iter().map(Json::to_text).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
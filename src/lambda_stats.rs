@@ -0,0 +1,262 @@
+use aws_sdk_cloudwatchlogs::Client;
+use clap::{Args, ValueEnum};
+
+use crate::error::Error;
+use crate::events::{filter_events, EventFilter};
+use crate::groups::lambda_group;
+use crate::outln;
+use crate::output::{self, OutputFormat, Record};
+use crate::time_range::{now_millis, parse_timestamp};
+
+/// The price of a million Lambda requests in US East (N. Virginia), in US dollars.
+const PRICE_PER_MILLION_REQUESTS: f64 = 0.20;
+
+/// The instruction set that a Lambda function runs on, which sets its price per GB-second.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Architecture {
+    #[default]
+    #[value(name = "x86_64")]
+    X86_64,
+    Arm64,
+}
+
+impl Architecture {
+    /// The price of a GB-second of compute in US East (N. Virginia), in US dollars.
+    fn price_per_gb_second(self) -> f64 {
+        match self {
+            Architecture::X86_64 => 0.000_016_666_7,
+            Architecture::Arm64 => 0.000_013_333_4,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct LambdaStatsOpt {
+    /// The log group of the function.
+    #[arg(
+        short,
+        long,
+        required_unless_present = "lambda",
+        conflicts_with = "lambda"
+    )]
+    pub group: Option<String>,
+
+    /// The function, given by name or ARN, instead of `--group`.
+    #[arg(long, value_name = "FUNCTION")]
+    pub lambda: Option<String>,
+
+    /// The beginning of the time range, e.g. `2h`, `now-10m` or `2024-05-01 13:00` (UTC).
+    #[arg(long, value_parser = parse_timestamp)]
+    pub since: i64,
+
+    /// The end of the time range, in the same formats as `--since`. Defaults to now.
+    #[arg(long, value_parser = parse_timestamp)]
+    pub until: Option<i64>,
+
+    /// The architecture of the function, for the cost estimate.
+    #[arg(long, value_enum, default_value_t)]
+    pub architecture: Architecture,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+impl LambdaStatsOpt {
+    /// The log group to read, from `--group` or `--lambda`.
+    pub fn group_name(&self) -> String {
+        match (&self.group, &self.lambda) {
+            (Some(group), _) => group.clone(),
+            (None, Some(function)) => lambda_group(function),
+            (None, None) => unreachable!("clap requires --group or --lambda"),
+        }
+    }
+}
+
+/// The figures that Lambda logs in the REPORT line at the end of each invocation.
+#[derive(Debug, Default, PartialEq)]
+struct Report {
+    duration_ms: f64,
+    billed_duration_ms: f64,
+    memory_size_mb: f64,
+    max_memory_used_mb: f64,
+    /// Only present for cold starts.
+    init_duration_ms: Option<f64>,
+}
+
+/// Parses a line such as
+/// `REPORT RequestId: … Duration: 12.34 ms Billed Duration: 13 ms Memory Size: 128 MB Max Memory Used: 70 MB`,
+/// whose fields are separated by tabs.
+fn parse_report(message: &str) -> Option<Report> {
+    let fields = message.trim_end().strip_prefix("REPORT ")?;
+    let mut report = Report::default();
+    let mut has_duration = false;
+    for field in fields.split('\t') {
+        let Some((key, value)) = field.split_once(": ") else {
+            continue;
+        };
+        // 値は「12.34 ms」や「128 MB」のように単位が付く
+        let Some(number) = value
+            .split_whitespace()
+            .next()
+            .and_then(|n| n.parse::<f64>().ok())
+        else {
+            continue;
+        };
+        match key.trim() {
+            "Duration" => {
+                report.duration_ms = number;
+                has_duration = true;
+            }
+            "Billed Duration" => report.billed_duration_ms = number,
+            "Memory Size" => report.memory_size_mb = number,
+            "Max Memory Used" => report.max_memory_used_mb = number,
+            "Init Duration" => report.init_duration_ms = Some(number),
+            _ => {}
+        }
+    }
+    has_duration.then_some(report)
+}
+
+/// The nearest-rank percentile of sorted values.
+fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    let rank = ((p / 100.0 * sorted.len() as f64).ceil() as usize).max(1);
+    sorted.get(rank - 1).copied()
+}
+
+/// Sorts values for `percentile`.
+fn sorted(values: impl Iterator<Item = f64>) -> Vec<f64> {
+    let mut values: Vec<f64> = values.collect();
+    values.sort_by(f64::total_cmp);
+    values
+}
+
+/// The estimated cost of an invocation: its billed GB-seconds and the request charge.
+fn invocation_cost(report: &Report, architecture: Architecture) -> f64 {
+    let gb_seconds = report.memory_size_mb / 1024.0 * report.billed_duration_ms / 1000.0;
+    gb_seconds * architecture.price_per_gb_second() + PRICE_PER_MILLION_REQUESTS / 1e6
+}
+
+/// Formats milliseconds with two decimals, or `-` when there are none.
+fn format_ms(ms: Option<f64>) -> String {
+    ms.map_or_else(|| "-".to_string(), |ms| format!("{:.2}", ms))
+}
+
+/// Summarizes the REPORT lines that a Lambda function logged in a time range: duration
+/// percentiles, memory use against the configured size, cold starts and the estimated cost.
+pub async fn run(client: &Client, opt: LambdaStatsOpt, verbose: bool) -> Result<(), Error> {
+    let group = opt.group_name();
+    let LambdaStatsOpt {
+        since,
+        until,
+        architecture,
+        output,
+        ..
+    } = opt;
+    let until = until.unwrap_or_else(now_millis);
+
+    if verbose {
+        eprintln!("Log group name:                {}", &group);
+        eprintln!("Time range:                    {} - {}", since, until);
+        eprintln!();
+    }
+
+    let filter = EventFilter {
+        filter_pattern: Some("\"REPORT RequestId\"".to_string()),
+        start_time: Some(since),
+        end_time: Some(until),
+        ..Default::default()
+    };
+    let reports: Vec<Report> = filter_events(client, &group, &filter)
+        .await?
+        .iter()
+        .filter_map(|event| parse_report(&event.message))
+        .collect();
+
+    let durations = sorted(reports.iter().map(|r| r.duration_ms));
+    let init_durations = sorted(reports.iter().filter_map(|r| r.init_duration_ms));
+    let memory_used = sorted(reports.iter().map(|r| r.max_memory_used_mb));
+    let memory_size = reports.iter().map(|r| r.memory_size_mb).fold(0.0, f64::max);
+    let max_memory_used = memory_used.last().copied();
+    let total_cost: f64 = reports
+        .iter()
+        .map(|report| invocation_cost(report, architecture))
+        .sum();
+    let invocations = reports.len();
+    let cold_starts = init_durations.len();
+    let average_cost = (invocations > 0).then(|| total_cost / invocations as f64);
+
+    match output {
+        OutputFormat::Text => {
+            if invocations == 0 {
+                outln!("No REPORT lines found in {}", group);
+                return Ok(());
+            }
+            outln!(
+                "Invocations:                   {} ({} cold starts, {:.1}%)",
+                invocations,
+                cold_starts,
+                cold_starts as f64 * 100.0 / invocations as f64
+            );
+            outln!(
+                "Duration (ms):                 p50 {}  p90 {}  p99 {}  max {}",
+                format_ms(percentile(&durations, 50.0)),
+                format_ms(percentile(&durations, 90.0)),
+                format_ms(percentile(&durations, 99.0)),
+                format_ms(durations.last().copied())
+            );
+            if cold_starts > 0 {
+                outln!(
+                    "Init duration (ms):            p50 {}  p99 {}  max {}",
+                    format_ms(percentile(&init_durations, 50.0)),
+                    format_ms(percentile(&init_durations, 99.0)),
+                    format_ms(init_durations.last().copied())
+                );
+            }
+            outln!(
+                "Memory (MB):                   max used {} of {} ({:.1}%), p99 used {}",
+                max_memory_used.unwrap_or_default(),
+                memory_size,
+                max_memory_used.unwrap_or_default() * 100.0 / memory_size.max(1.0),
+                percentile(&memory_used, 99.0).unwrap_or_default()
+            );
+            outln!(
+                "Estimated cost:                ${:.8} per invocation, ${:.6} in total",
+                average_cost.unwrap_or_default(),
+                total_cost
+            );
+        }
+        format => {
+            let record = Record::new()
+                .field("group", group.as_str())
+                .field("invocations", invocations as i64)
+                .field("coldStarts", cold_starts as i64)
+                .field("durationP50Ms", percentile(&durations, 50.0))
+                .field("durationP90Ms", percentile(&durations, 90.0))
+                .field("durationP99Ms", percentile(&durations, 99.0))
+                .field("durationMaxMs", durations.last().copied())
+                .field("initDurationP50Ms", percentile(&init_durations, 50.0))
+                .field("initDurationMaxMs", init_durations.last().copied())
+                .field("memorySizeMb", (invocations > 0).then_some(memory_size))
+                .field("maxMemoryUsedMb", max_memory_used)
+                .field("costPerInvocationUsd", average_cost)
+                .field("totalCostUsd", total_cost);
+            let row = Record::new()
+                .field("INVOCATIONS", invocations as i64)
+                .field("COLD STARTS", cold_starts as i64)
+                .field("P50 MS", format_ms(percentile(&durations, 50.0)).as_str())
+                .field("P99 MS", format_ms(percentile(&durations, 99.0)).as_str())
+                .field("MAX MS", format_ms(durations.last().copied()).as_str())
+                .field(
+                    "MEMORY MB",
+                    format!("{}/{}", max_memory_used.unwrap_or_default(), memory_size).as_str(),
+                )
+                .field(
+                    "COST/INVOCATION",
+                    format!("${:.8}", average_cost.unwrap_or_default()).as_str(),
+                );
+            output::print_records(format, &[record], &[row]);
+        }
+    }
+    Ok(())
+}
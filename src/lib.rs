@@ -0,0 +1,109 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lists CloudWatch Logs log streams and reads their events.
+//!
+//! The `print-log-stream-names` binary is built on this crate. Other tools can embed the same
+//! fetching logic through [`StreamLister`] and [`EventFetcher`], which take an SDK [`Client`]:
+//!
+//! ```no_run
+//! use print_log_stream_names::{EventFetcher, StreamLister, StreamOrder};
+//!
+//! # async fn example(client: aws_sdk_cloudwatchlogs::Client) -> Result<(), print_log_stream_names::Error> {
+//! let streams = StreamLister::new(client.clone(), "/aws/lambda/orders-api")
+//!     .with_order_by(StreamOrder::LastEvent)
+//!     .with_descending(true)
+//!     .with_limit(10)
+//!     .list()
+//!     .await?;
+//! let events = EventFetcher::new(client, "/aws/lambda/orders-api")
+//!     .with_filter_pattern("ERROR")
+//!     .fetch()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Both are generic over [`LogsApi`], the CloudWatch Logs calls they make. [`MemoryLogs`] implements it
+//! over events held in memory, so that code paging through them can be exercised without AWS:
+//!
+//! ```no_run
+//! use print_log_stream_names::{EventFetcher, MemoryLogs};
+//!
+//! # async fn example() -> Result<(), print_log_stream_names::Error> {
+//! let logs = MemoryLogs::new()
+//!     .with_event("app", "a", 1, "ERROR one")
+//!     .with_event("app", "b", 2, "INFO two")
+//!     .with_page_size(1);
+//! let events = EventFetcher::new(logs, "app")
+//!     .with_filter_pattern("ERROR")
+//!     .fetch()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The other modules hold the subcommands of the binary. Their options are clap `Args`
+//! and change along with the command line, so they are not a stable API.
+//!
+//! [`Client`]: aws_sdk_cloudwatchlogs::Client
+
+#![allow(clippy::result_large_err)]
+
+mod access_log;
+pub mod account_policies;
+pub mod anomalies;
+mod apigateway;
+pub mod audit;
+pub mod bookmarks;
+pub mod browse;
+mod checkpoint;
+mod cloudformation;
+pub mod color;
+pub mod completions;
+pub mod config;
+mod confirm;
+pub mod correlate;
+pub mod data_protection;
+mod ecs;
+mod error;
+pub mod events;
+pub mod export;
+pub mod groups;
+pub mod history;
+mod jq;
+pub mod lambda_stats;
+pub mod logs_api;
+pub mod manage;
+pub mod metric_filters;
+pub mod output;
+pub mod pager;
+pub mod picker;
+mod progress;
+pub mod queries;
+pub mod query;
+pub mod query_defs;
+mod query_templates;
+mod rate_limit;
+pub mod repl;
+pub mod report;
+pub mod resource_policies;
+mod s3;
+pub mod signals;
+mod signed;
+pub mod step_functions;
+pub mod streams;
+pub mod subscriptions;
+pub mod tags;
+pub mod tail;
+pub mod targets;
+mod terminal;
+pub mod time_range;
+pub mod trace;
+pub mod tz;
+mod watch;
+
+pub use error::{Error, ErrorKind};
+pub use events::{EventFetcher, LogEvent};
+pub use logs_api::{LogsApi, MemoryLogs};
+pub use streams::{StreamLister, StreamOrder};
@@ -0,0 +1,458 @@
+//! The CloudWatch Logs calls that stream listing and event fetching make, behind a trait,
+//! so that the pagination and error handling can run against [`MemoryLogs`] instead of AWS.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use aws_sdk_cloudwatchlogs::{
+    operation::{
+        describe_log_streams::DescribeLogStreamsOutput, filter_log_events::FilterLogEventsOutput,
+        get_log_events::GetLogEventsOutput,
+    },
+    types::{
+        error::{ResourceNotFoundException, ServiceUnavailableException},
+        FilteredLogEvent, LogStream, OutputLogEvent,
+    },
+    Client, Error,
+};
+
+use crate::events::EventFilter;
+use crate::groups::{group_arn, group_name};
+use crate::streams::{StreamOrder, StreamRequestOpt};
+
+/// One page of each read-only call. `next_token` is the token of the previous page, if any.
+pub trait LogsApi {
+    /// DescribeLogStreams for a log group name or ARN.
+    fn describe_log_streams(
+        &self,
+        group: &str,
+        request: &StreamRequestOpt,
+        next_token: Option<String>,
+    ) -> impl Future<Output = Result<DescribeLogStreamsOutput, Error>> + Send;
+
+    /// GetLogEvents from the start of the stream.
+    fn get_log_events(
+        &self,
+        group: &str,
+        stream: &str,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        next_token: Option<String>,
+    ) -> impl Future<Output = Result<GetLogEventsOutput, Error>> + Send;
+
+    /// FilterLogEvents for a log group name or ARN.
+    fn filter_log_events(
+        &self,
+        group: &str,
+        filter: &EventFilter,
+        next_token: Option<String>,
+    ) -> impl Future<Output = Result<FilterLogEventsOutput, Error>> + Send;
+}
+
+impl LogsApi for Client {
+    fn describe_log_streams(
+        &self,
+        group: &str,
+        request: &StreamRequestOpt,
+        next_token: Option<String>,
+    ) -> impl Future<Output = Result<DescribeLogStreamsOutput, Error>> + Send {
+        let call = self
+            .describe_log_streams()
+            .set_log_group_name(group_name(group))
+            .set_log_group_identifier(group_arn(group).map(str::to_string))
+            .order_by(request.order_by.into())
+            .descending(request.descending)
+            .set_log_stream_name_prefix(request.prefix.clone())
+            .set_limit(request.page_size)
+            .set_next_token(next_token);
+        async move { call.send().await.map_err(Error::from) }
+    }
+
+    fn get_log_events(
+        &self,
+        group: &str,
+        stream: &str,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        next_token: Option<String>,
+    ) -> impl Future<Output = Result<GetLogEventsOutput, Error>> + Send {
+        let call = self
+            .get_log_events()
+            .set_log_group_name(group_name(group))
+            .set_log_group_identifier(group_arn(group).map(str::to_string))
+            .log_stream_name(stream)
+            .set_start_time(start_time)
+            .set_end_time(end_time)
+            .start_from_head(true)
+            .set_next_token(next_token);
+        async move { call.send().await.map_err(Error::from) }
+    }
+
+    fn filter_log_events(
+        &self,
+        group: &str,
+        filter: &EventFilter,
+        next_token: Option<String>,
+    ) -> impl Future<Output = Result<FilterLogEventsOutput, Error>> + Send {
+        let stream_names = (!filter.stream_names.is_empty()).then(|| filter.stream_names.clone());
+        let call = self
+            .filter_log_events()
+            .set_log_group_name(group_name(group))
+            .set_log_group_identifier(group_arn(group).map(str::to_string))
+            .set_log_stream_names(stream_names)
+            .set_log_stream_name_prefix(filter.stream_prefix.clone())
+            .set_filter_pattern(filter.filter_pattern.clone())
+            .set_start_time(filter.start_time)
+            .set_end_time(filter.end_time)
+            .set_next_token(next_token);
+        async move { call.send().await.map_err(Error::from) }
+    }
+}
+
+/// Lets a lister or fetcher borrow a client, e.g. to count the calls made to a [`MemoryLogs`] afterwards.
+impl<T: LogsApi + Sync> LogsApi for &T {
+    fn describe_log_streams(
+        &self,
+        group: &str,
+        request: &StreamRequestOpt,
+        next_token: Option<String>,
+    ) -> impl Future<Output = Result<DescribeLogStreamsOutput, Error>> + Send {
+        (**self).describe_log_streams(group, request, next_token)
+    }
+
+    fn get_log_events(
+        &self,
+        group: &str,
+        stream: &str,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        next_token: Option<String>,
+    ) -> impl Future<Output = Result<GetLogEventsOutput, Error>> + Send {
+        (**self).get_log_events(group, stream, start_time, end_time, next_token)
+    }
+
+    fn filter_log_events(
+        &self,
+        group: &str,
+        filter: &EventFilter,
+        next_token: Option<String>,
+    ) -> impl Future<Output = Result<FilterLogEventsOutput, Error>> + Send {
+        (**self).filter_log_events(group, filter, next_token)
+    }
+}
+
+/// Whether a service error means the log group or stream does not exist.
+pub fn is_not_found(error: &Error) -> bool {
+    matches!(error, Error::ResourceNotFoundException(_))
+}
+
+/// The streams of a log group by name, each with its events as (timestamp, message).
+type Streams = BTreeMap<String, Vec<(i64, String)>>;
+
+/// An in-memory [`LogsApi`] that pages through a fixed set of log groups.
+///
+/// Filter patterns are matched as plain text: an event matches when its message contains the
+/// pattern, with any surrounding double quotes removed. JSON and space-delimited patterns are not parsed.
+#[derive(Debug)]
+pub struct MemoryLogs {
+    groups: BTreeMap<String, Streams>,
+    page_size: usize,
+    failing_call: Option<usize>,
+    calls: AtomicUsize,
+}
+
+impl Default for MemoryLogs {
+    fn default() -> Self {
+        MemoryLogs {
+            groups: BTreeMap::new(),
+            page_size: 50,
+            failing_call: None,
+            calls: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl MemoryLogs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an empty log group.
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.groups.entry(group.into()).or_default();
+        self
+    }
+
+    /// Adds an event, creating its log group and stream if needed.
+    pub fn with_event(
+        mut self,
+        group: impl Into<String>,
+        stream: impl Into<String>,
+        timestamp: i64,
+        message: impl Into<String>,
+    ) -> Self {
+        let events = self
+            .groups
+            .entry(group.into())
+            .or_default()
+            .entry(stream.into())
+            .or_default();
+        events.push((timestamp, message.into()));
+        events.sort_by_key(|(timestamp, _)| *timestamp);
+        self
+    }
+
+    /// How many streams or events each page holds at most. The default is 50.
+    /// A smaller `--page-size` of DescribeLogStreams still applies.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Makes the `call`-th call, counting from 0 across all operations,
+    /// fail with a ServiceUnavailableException.
+    pub fn with_failing_call(mut self, call: usize) -> Self {
+        self.failing_call = Some(call);
+        self
+    }
+
+    /// How many calls have been made so far.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// Counts the call and returns the streams of the group, or the error it fails with.
+    fn start_call(&self, group: &str) -> Result<&Streams, Error> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        if self.failing_call == Some(call) {
+            return Err(Error::ServiceUnavailableException(
+                ServiceUnavailableException::builder()
+                    .message("The service is unavailable")
+                    .build(),
+            ));
+        }
+        // ARN は arn:aws:logs:<リージョン>:<アカウント>:log-group:<名前>[:*]
+        let name = match group_arn(group) {
+            Some(arn) => arn.split(":log-group:").nth(1).unwrap_or(arn),
+            None => group,
+        };
+        self.groups.get(name).ok_or_else(|| not_found(name))
+    }
+}
+
+fn not_found(resource: &str) -> Error {
+    Error::ResourceNotFoundException(
+        ResourceNotFoundException::builder()
+            .message(format!(
+                "The specified resource {} does not exist.",
+                resource
+            ))
+            .build(),
+    )
+}
+
+/// The offset in a token of the form `<kind>/<offset>`.
+fn token_offset(next_token: Option<&str>) -> usize {
+    next_token
+        .and_then(|token| token.split_once('/'))
+        .and_then(|(_, offset)| offset.parse().ok())
+        .unwrap_or(0)
+}
+
+fn in_range(timestamp: i64, start_time: Option<i64>, end_time: Option<i64>) -> bool {
+    start_time.is_none_or(|start| timestamp >= start) && end_time.is_none_or(|end| timestamp < end)
+}
+
+impl LogsApi for MemoryLogs {
+    async fn describe_log_streams(
+        &self,
+        group: &str,
+        request: &StreamRequestOpt,
+        next_token: Option<String>,
+    ) -> Result<DescribeLogStreamsOutput, Error> {
+        let streams = self.start_call(group)?;
+        let mut matching: Vec<LogStream> = streams
+            .iter()
+            .filter(|(name, _)| {
+                request
+                    .prefix
+                    .as_ref()
+                    .is_none_or(|prefix| name.starts_with(prefix.as_str()))
+            })
+            .map(|(name, events)| {
+                LogStream::builder()
+                    .log_stream_name(name)
+                    .set_creation_time(events.first().map(|(timestamp, _)| *timestamp))
+                    .set_first_event_timestamp(events.first().map(|(timestamp, _)| *timestamp))
+                    .set_last_event_timestamp(events.last().map(|(timestamp, _)| *timestamp))
+                    .arn(format!(
+                        "arn:aws:logs:us-east-1:123456789012:log-group:{}:log-stream:{}",
+                        group, name
+                    ))
+                    .build()
+            })
+            .collect();
+        if request.order_by == StreamOrder::LastEvent {
+            matching.sort_by_key(|stream| stream.last_event_timestamp().unwrap_or_default());
+        }
+        if request.descending {
+            matching.reverse();
+        }
+
+        let page_size = request
+            .page_size
+            .map_or(self.page_size, |size| self.page_size.min(size as usize));
+        let offset = token_offset(next_token.as_deref());
+        let end = (offset + page_size).min(matching.len());
+        Ok(DescribeLogStreamsOutput::builder()
+            .set_log_streams(Some(matching[offset.min(end)..end].to_vec()))
+            .set_next_token((end < matching.len()).then(|| format!("s/{}", end)))
+            .build())
+    }
+
+    async fn get_log_events(
+        &self,
+        group: &str,
+        stream: &str,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        next_token: Option<String>,
+    ) -> Result<GetLogEventsOutput, Error> {
+        let events = self
+            .start_call(group)?
+            .get(stream)
+            .ok_or_else(|| not_found(stream))?;
+        let matching: Vec<&(i64, String)> = events
+            .iter()
+            .filter(|(timestamp, _)| in_range(*timestamp, start_time, end_time))
+            .collect();
+        // 本物と同じく、最後のページでは受け取ったトークンをそのまま返す
+        let offset = token_offset(next_token.as_deref()).min(matching.len());
+        let end = (offset + self.page_size).min(matching.len());
+        let page = matching[offset..end]
+            .iter()
+            .map(|(timestamp, message)| {
+                OutputLogEvent::builder()
+                    .timestamp(*timestamp)
+                    .ingestion_time(*timestamp)
+                    .message(message)
+                    .build()
+            })
+            .collect();
+        Ok(GetLogEventsOutput::builder()
+            .set_events(Some(page))
+            .next_forward_token(format!("f/{}", end))
+            .next_backward_token(format!("b/{}", offset))
+            .build())
+    }
+
+    async fn filter_log_events(
+        &self,
+        group: &str,
+        filter: &EventFilter,
+        next_token: Option<String>,
+    ) -> Result<FilterLogEventsOutput, Error> {
+        let streams = self.start_call(group)?;
+        let pattern = filter
+            .filter_pattern
+            .as_deref()
+            .map(|pattern| pattern.trim().trim_matches('"'));
+        let mut matching: Vec<FilteredLogEvent> = Vec::new();
+        for (name, events) in streams {
+            let selected = match &filter.stream_prefix {
+                Some(prefix) => name.starts_with(prefix.as_str()),
+                None => filter.stream_names.is_empty() || filter.stream_names.contains(name),
+            };
+            if !selected {
+                continue;
+            }
+            for (i, (timestamp, message)) in events.iter().enumerate() {
+                if !in_range(*timestamp, filter.start_time, filter.end_time)
+                    || pattern.is_some_and(|pattern| !message.contains(pattern))
+                {
+                    continue;
+                }
+                matching.push(
+                    FilteredLogEvent::builder()
+                        .log_stream_name(name)
+                        .timestamp(*timestamp)
+                        .ingestion_time(*timestamp)
+                        .message(message)
+                        .event_id(format!("{}/{}", name, i))
+                        .build(),
+                );
+            }
+        }
+        matching.sort_by_key(|event| event.timestamp().unwrap_or_default());
+
+        let offset = token_offset(next_token.as_deref());
+        let end = (offset + self.page_size).min(matching.len());
+        Ok(FilterLogEventsOutput::builder()
+            .set_events(Some(matching[offset.min(end)..end].to_vec()))
+            .set_next_token((end < matching.len()).then(|| format!("e/{}", end)))
+            .build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn group_arn_resolves_to_its_name() {
+        let logs = MemoryLogs::new().with_event("app", "a", 1, "one");
+        let arn = "arn:aws:logs:us-east-1:123456789012:log-group:app:*";
+        let request = StreamRequestOpt::default();
+        let page = logs
+            .describe_log_streams(arn, &request, None)
+            .await
+            .unwrap();
+        assert_eq!(page.log_streams().len(), 1);
+        let missing = logs.describe_log_streams("other", &request, None).await;
+        assert!(is_not_found(&missing.unwrap_err()));
+        assert_eq!(logs.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn failing_call_is_counted_across_operations() {
+        let logs = MemoryLogs::new()
+            .with_event("app", "a", 1, "one")
+            .with_failing_call(1);
+        let request = StreamRequestOpt::default();
+        assert!(logs
+            .describe_log_streams("app", &request, None)
+            .await
+            .is_ok());
+        let failed = logs.get_log_events("app", "a", None, None, None).await;
+        assert!(matches!(failed, Err(Error::ServiceUnavailableException(_))));
+        assert!(logs
+            .get_log_events("app", "a", None, None, None)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn last_page_of_events_returns_the_token_it_was_given() {
+        let logs = MemoryLogs::new()
+            .with_event("app", "a", 1, "one")
+            .with_event("app", "a", 2, "two")
+            .with_page_size(1);
+        let first = logs
+            .get_log_events("app", "a", None, None, None)
+            .await
+            .unwrap();
+        let token = first.next_forward_token;
+        let last = logs
+            .get_log_events("app", "a", None, None, token)
+            .await
+            .unwrap();
+        let end = last.next_forward_token;
+        let again = logs
+            .get_log_events("app", "a", None, None, end.clone())
+            .await
+            .unwrap();
+        assert!(again.events().is_empty());
+        assert_eq!(again.next_forward_token, end);
+    }
+}
@@ -3,114 +3,897 @@
 
 #![allow(clippy::result_large_err)]
 
-use aws_config::Region;
-use aws_sdk_cloudwatchlogs::{meta::PKG_VERSION, types::LogStream, Client};
-use clap::Parser;
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::time::Duration;
 
+use aws_sdk_cloudwatchlogs::meta::PKG_VERSION;
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::parser::ValueSource;
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::prelude::*;
+
+use print_log_stream_names::{
+    account_policies, anomalies, audit, bookmarks, browse, color, completions, config, correlate,
+    data_protection, events, export, groups, history, lambda_stats, manage, metric_filters, output,
+    pager, picker, queries, query, query_defs, repl, report, resource_policies, signals,
+    step_functions, streams, subscriptions, tags, tail, targets, time_range, trace, tz, Error,
+};
+
+/// Lists and reads the log streams of CloudWatch Logs groups.
 #[derive(Debug, Parser)]
 struct Opt {
     /// The AWS Region. Overrides environment variable `AWS_REGION` and profile's default region.
-    #[structopt(short, long)]
-    region: Option<String>,
+    /// Repeat to run `list-streams` or `list-groups` in several Regions at once.
+    #[arg(short, long, global = true)]
+    region: Vec<String>,
 
-    /// The log group name.
-    #[structopt(short, long)]
-    group: String,
+    /// Runs `list-streams` or `list-groups` in every Region where CloudWatch Logs is available.
+    #[arg(long, global = true, conflicts_with = "region")]
+    all_regions: bool,
 
-    /// Whether to display additional information.
-    #[structopt(short, long)]
-    verbose: bool,
+    /// Displays additional information. Repeat to also log what the SDK does on stderr:
+    /// `-vv` logs at the info level, `-vvv` at debug, which shows retries when throttled,
+    /// and `-vvvv` at trace, which shows every request and response.
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Which SDK logs to print on stderr, as `LEVEL` or `TARGET=LEVEL,...`,
+    /// e.g. `aws_smithy_runtime=debug,warn`. Overrides the logging that `-v` turns on.
+    #[arg(long, global = true, value_name = "FILTER", value_parser = parse_log_level)]
+    log_level: Option<Targets>,
+
+    /// Prints only the data lines, leaving out counts such as `Found 12 streams.`, totals and progress,
+    /// so that the output can be piped into `xargs`. Warnings and errors are still printed.
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
 
     /// The name of the AWS profile. if not supplied, uses default.
-    #[structopt(short, long)]
-    profile_name: Option<String>,
+    /// Repeat or give a list such as `--profiles dev,staging,prod` to list in each account at once.
+    #[arg(
+        short,
+        long,
+        global = true,
+        visible_alias = "profiles",
+        value_delimiter = ','
+    )]
+    profile_name: Vec<String>,
+
+    #[command(flatten)]
+    role: targets::RoleOpt,
+
+    #[command(flatten)]
+    connection: targets::ConnectionOpt,
+
+    /// When to color the output.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    color: color::ColorChoice,
+
+    /// The time zone of printed timestamps: `utc`, `local` or an IANA name such as `Asia/Tokyo`.
+    #[arg(long, global = true, value_name = "ZONE", default_value = "utc", value_parser = tz::TimeZone::parse)]
+    tz: tz::TimeZone,
+
+    /// How to print timestamps in text and table output.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    timestamps: output::TimestampStyle,
+
+    /// Shows the output in `$PAGER`, or `less -FRX`, when stdout is a terminal.
+    #[arg(long, global = true, conflicts_with = "output_file")]
+    pager: bool,
+
+    /// Writes the output to this file instead of stdout. Warnings still go to the terminal.
+    #[arg(long, global = true, value_name = "PATH")]
+    output_file: Option<PathBuf>,
+
+    /// Compresses the `--output-file` with the system's `gzip`.
+    #[arg(long, global = true, requires = "output_file")]
+    gzip: bool,
+
+    /// Starts a new output file after this much output, e.g. `100MB`.
+    /// Rotated files are named after the time they were started, e.g. `dump.20240501T130000Z.log`.
+    #[arg(long, global = true, value_name = "SIZE", requires = "output_file", value_parser = output::parse_size)]
+    rotate_size: Option<u64>,
+
+    /// Starts a new output file after this long, e.g. `1h`.
+    #[arg(long, global = true, value_name = "DURATION", requires = "output_file", value_parser = time_range::parse_duration)]
+    rotate_interval: Option<Duration>,
+
+    #[command(subcommand)]
+    command: Command,
 }
 
-async fn get_streams(
-    client: &aws_sdk_cloudwatchlogs::Client,
-    log_group_name: &str,
-) -> Result<Vec<LogStream>, aws_sdk_cloudwatchlogs::Error> {
-    let streams_result = client
-        .describe_log_streams()
-        .log_group_name(log_group_name)
-        .into_paginator()
-        .items()
-        .send()
-        .collect::<Vec<_>>()
-        .await;
-
-    let mut streams: Vec<LogStream> = Vec::new();
-
-    for result in streams_result {
-        match result {
-            Ok(log_stream) => streams.push(log_stream), // 成功した場合はVecに追加
-            Err(e) => println!("Warning: Failed to retrieve a log stream: {:?}", e), // エラーの場合は警告を表示
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Lists the log streams in a log group.
+    ListStreams(streams::ListStreamsOpt),
+
+    /// Lists the log groups in the Region with their retention, size and encryption.
+    ListGroups(groups::ListGroupsOpt),
+
+    /// Prints the log events in log streams, or in every stream of a log group.
+    GetEvents(events::GetEventsOpt),
+
+    /// Prints the recent events in log groups, optionally following new ones.
+    /// The events of several groups are merged into one timeline.
+    Tail(tail::TailOpt),
+
+    /// Runs a CloudWatch Logs Insights query and prints the results.
+    Query(query::QueryOpt),
+
+    /// Exports the events of a log group to S3 and waits for the export task to finish.
+    Export(export::ExportOpt),
+
+    /// Creates a log group, optionally with a retention period, KMS key, class and tags.
+    CreateGroup(manage::CreateGroupOpt),
+
+    /// Deletes log groups and all of their events.
+    DeleteGroup(manage::DeleteGroupOpt),
+
+    /// Creates log streams in a log group.
+    CreateStream(manage::CreateStreamOpt),
+
+    /// Deletes log streams, given on the command line or on standard input.
+    DeleteStream(manage::DeleteStreamOpt),
+
+    /// Sets how long a log group keeps its events, or makes them never expire.
+    SetRetention(manage::SetRetentionOpt),
+
+    /// Encrypts the new events of a log group with a KMS key.
+    AssociateKmsKey(manage::AssociateKmsKeyOpt),
+
+    /// Stops encrypting the new events of a log group with its KMS key.
+    DisassociateKmsKey(manage::DisassociateKmsKeyOpt),
+
+    /// Deletes the log streams that have had no events for a while.
+    PruneStreams(manage::PruneStreamsOpt),
+
+    /// Checks the log groups for compliance problems, such as unbounded retention.
+    Audit(audit::AuditOpt),
+
+    /// Lists, adds or removes the tags of a log group.
+    Tags(tags::TagsOpt),
+
+    /// Lists, creates or deletes the metric filters of a log group.
+    MetricFilters(metric_filters::MetricFiltersOpt),
+
+    /// Lists, creates or deletes the subscription filters that forward events to
+    /// Kinesis, Firehose or Lambda.
+    Subscriptions(subscriptions::SubscriptionsOpt),
+
+    /// Lists the log anomaly detectors and the anomalies they have found.
+    Anomalies(anomalies::AnomaliesOpt),
+
+    /// Reports and shows the data protection policies that mask sensitive data in log groups.
+    DataProtection(data_protection::DataProtectionOpt),
+
+    /// Manages the resource policies that let other AWS services write to CloudWatch Logs.
+    ResourcePolicies(resource_policies::ResourcePoliciesOpt),
+
+    /// Manages the data protection and subscription filter policies that apply to the whole account.
+    AccountPolicies(account_policies::AccountPoliciesOpt),
+
+    /// Lists and stops Logs Insights queries.
+    Queries(queries::QueriesOpt),
+
+    /// Manages saved Logs Insights queries.
+    QueryDefs(query_defs::QueryDefsOpt),
+
+    /// Summarizes the REPORT lines of a Lambda function: durations, memory, cold starts and cost.
+    LambdaStats(lambda_stats::LambdaStatsOpt),
+
+    /// Finds the events that mention an X-Ray trace ID across log groups, in time order.
+    Trace(trace::TraceOpt),
+
+    /// Finds the events of one request across log groups by a correlation ID, in time order.
+    Correlate(correlate::CorrelateOpt),
+
+    /// Prints the state transitions that a Step Functions execution logged, in order.
+    SfnLogs(step_functions::SfnLogsOpt),
+
+    /// Prints a completion script for bash, zsh, fish or PowerShell.
+    /// `--group` completes the log groups that `list-groups` has printed before.
+    Completions(completions::CompletionsOpt),
+
+    /// Saves, lists and removes named bookmarks of log groups, given as `-g @NAME`.
+    Bookmark(bookmarks::BookmarkOpt),
+
+    /// Lists and runs again the queries and filtered searches run before.
+    History(history::HistoryOpt),
+
+    /// Opens a prompt to run subcommands one after another on the same client,
+    /// in a current log group, with tab completion of log group and stream names.
+    Repl(repl::ReplOpt),
+
+    /// Browses log groups and streams full-screen, following the events of the selected one,
+    /// with keys to search them and to change the time range and filter pattern.
+    Browse(browse::BrowseOpt),
+}
+
+/// A line typed at the `repl` prompt: a subcommand, without the program name and global options.
+#[derive(Debug, Parser)]
+#[command(name = "repl", no_binary_name = true, disable_version_flag = true)]
+struct ReplLine {
+    #[command(subcommand)]
+    command: Command,
+}
+
+impl Command {
+    /// Checks option combinations that clap cannot express on its own.
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            Command::ListStreams(opt) => {
+                opt.groups.ecs.unsupported()?;
+                opt.validate()
+            }
+            Command::ListGroups(opt) => opt.validate(),
+            Command::Tail(opt) => opt.validate(),
+            Command::Trace(opt) => opt.groups.ecs.unsupported(),
+            Command::Correlate(opt) => opt.groups.ecs.unsupported(),
+            Command::Query(opt) => opt.validate(),
+            Command::Subscriptions(opt) => opt.validate(),
+            Command::AccountPolicies(opt) => opt.validate(),
+            _ => Ok(()),
+        }
+    }
+
+    /// The log groups named on the command line, to look for when one does not exist.
+    fn group_names(&self) -> Vec<String> {
+        match self {
+            Command::ListStreams(opt) => opt.groups.named_groups(),
+            Command::ListGroups(_) => Vec::new(),
+            Command::GetEvents(opt) => vec![opt.group_name()],
+            Command::Tail(opt) => opt.groups.named_groups(),
+            Command::Query(opt) => opt.groups.named_groups(),
+            Command::Export(opt) => vec![opt.group.clone()],
+            Command::CreateGroup(_) => Vec::new(),
+            Command::DeleteGroup(opt) => opt.group.clone(),
+            Command::CreateStream(opt) => vec![opt.group.clone()],
+            Command::DeleteStream(opt) => vec![opt.group.clone()],
+            Command::SetRetention(opt) => vec![opt.group.clone()],
+            Command::AssociateKmsKey(opt) => vec![opt.group.clone()],
+            Command::DisassociateKmsKey(opt) => vec![opt.group.clone()],
+            Command::PruneStreams(opt) => vec![opt.group.clone()],
+            Command::Audit(_) | Command::ResourcePolicies(_) | Command::AccountPolicies(_) => {
+                Vec::new()
+            }
+            Command::Tags(opt) => vec![opt.group().to_string()],
+            Command::MetricFilters(opt) => vec![opt.group().to_string()],
+            Command::Subscriptions(opt) => opt.group().map(str::to_string).into_iter().collect(),
+            Command::Anomalies(opt) => opt.group.iter().cloned().collect(),
+            Command::DataProtection(opt) => opt.group().map(str::to_string).into_iter().collect(),
+            Command::Queries(opt) => opt.group().map(str::to_string).into_iter().collect(),
+            Command::QueryDefs(_) => Vec::new(),
+            Command::LambdaStats(opt) => vec![opt.group_name()],
+            Command::Trace(opt) => opt.groups.named_groups(),
+            Command::Correlate(opt) => opt.groups.named_groups(),
+            Command::SfnLogs(opt) => opt.group.iter().cloned().collect(),
+            Command::Completions(_) | Command::Bookmark(_) | Command::History(_) => Vec::new(),
+            Command::Repl(opt) => opt.group.iter().cloned().collect(),
+            Command::Browse(opt) => opt.group.iter().cloned().collect(),
+        }
+    }
+
+    /// The output format of the subcommands that print structured output.
+    fn output(&self) -> Option<output::OutputFormat> {
+        match self {
+            Command::ListStreams(opt) => Some(opt.output),
+            Command::ListGroups(opt) => Some(opt.output),
+            Command::GetEvents(opt) => Some(opt.output),
+            Command::Tail(opt) => Some(opt.output),
+            Command::Query(opt) => opt.output,
+            Command::Anomalies(opt) => Some(opt.output),
+            Command::LambdaStats(opt) => Some(opt.output),
+            Command::Trace(opt) => Some(opt.output),
+            Command::Correlate(opt) => Some(opt.output),
+            Command::SfnLogs(opt) => Some(opt.output),
+            Command::Audit(audit::AuditOpt {
+                command: audit::AuditCommand::Retention(opt),
+            }) => Some(opt.output),
+            Command::Tags(tags::TagsOpt {
+                command: tags::TagsCommand::List(opt),
+            }) => Some(opt.output),
+            Command::MetricFilters(metric_filters::MetricFiltersOpt {
+                command: metric_filters::MetricFiltersCommand::List(opt),
+            }) => Some(opt.output),
+            Command::Subscriptions(subscriptions::SubscriptionsOpt {
+                command: subscriptions::SubscriptionsCommand::List(opt),
+                ..
+            }) => Some(opt.output),
+            Command::DataProtection(data_protection::DataProtectionOpt {
+                command: data_protection::DataProtectionCommand::List(opt),
+            }) => Some(opt.output),
+            Command::ResourcePolicies(resource_policies::ResourcePoliciesOpt {
+                command: resource_policies::ResourcePoliciesCommand::List(opt),
+            }) => Some(opt.output),
+            Command::AccountPolicies(account_policies::AccountPoliciesOpt {
+                command: account_policies::AccountPoliciesCommand::List(opt),
+                ..
+            }) => Some(opt.output),
+            Command::Queries(queries::QueriesOpt {
+                command: queries::QueriesCommand::List(opt),
+            }) => Some(opt.output),
+            Command::QueryDefs(query_defs::QueryDefsOpt {
+                command: query_defs::QueryDefsCommand::List(opt),
+            }) => Some(opt.output),
+            Command::Bookmark(bookmarks::BookmarkOpt {
+                command: bookmarks::BookmarkCommand::List(opt),
+            }) => Some(opt.output),
+            Command::History(history::HistoryOpt {
+                command: history::HistoryCommand::List(opt),
+            }) => Some(opt.output),
+            _ => None,
+        }
+    }
+
+    /// Whether the subcommand searches events, and so is recorded in the history:
+    /// a Logs Insights query, or reading events with a filter pattern.
+    fn is_search(&self) -> bool {
+        match self {
+            Command::Query(_) | Command::Trace(_) | Command::Correlate(_) => true,
+            Command::GetEvents(opt) => opt.filter_pattern.is_some(),
+            Command::Tail(opt) => opt.filter_pattern.is_some(),
+            _ => false,
+        }
+    }
+
+    /// Whether the subcommand can run in several Regions or profiles and merge the results.
+    fn fans_out(&self) -> bool {
+        matches!(
+            self,
+            Command::ListStreams(_) | Command::ListGroups(_) | Command::Audit(_)
+        )
+    }
+}
+
+fn parse_log_level(filter: &str) -> Result<Targets, String> {
+    filter.parse().map_err(|e| format!("{}", e))
+}
+
+/// Prints the SDK's logs on stderr with the `--log-level` filter, or at the level that `-v` is repeated to.
+/// A single `-v` only logs warnings, so that verbose output stays readable.
+fn init_logging(verbose: u8, log_level: Option<Targets>) {
+    let filter = match (log_level, verbose) {
+        (Some(filter), _) => filter,
+        (None, 0) => return,
+        (None, 1) => Targets::new().with_default(LevelFilter::WARN),
+        (None, 2) => Targets::new().with_default(LevelFilter::INFO),
+        (None, 3) => Targets::new().with_default(LevelFilter::DEBUG),
+        (None, _) => Targets::new().with_default(LevelFilter::TRACE),
+    };
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_ansi(std::io::stderr().is_terminal())
+                .with_filter(filter),
+        )
+        .init();
+}
+
+/// Picks the log group, and then the log stream, that `error` says are missing,
+/// when they are the only required arguments missing and stdin and stderr are a terminal.
+/// Returns the arguments to add, or `None` to report the error instead.
+async fn pick_missing(
+    config: &'static config::Config,
+    args: &[OsString],
+    error: &clap::Error,
+) -> Option<Vec<OsString>> {
+    if error.kind() != ErrorKind::MissingRequiredArgument || !picker::is_interactive() {
+        return None;
+    }
+    let Some(ContextValue::Strings(missing)) = error.get(ContextKind::InvalidArg) else {
+        return None;
+    };
+    let is_missing = |flag: &str| missing.iter().any(|arg| arg.starts_with(flag));
+    if !missing
+        .iter()
+        .all(|arg| arg.starts_with("--group ") || arg.starts_with("--stream "))
+    {
+        return None;
+    }
+
+    // 一覧を取るためのクライアントだけを、グローバルオプションから作る
+    let matches = config
+        .apply(Opt::command())
+        .ignore_errors(true)
+        .try_get_matches_from(args)
+        .ok()?;
+    let first = |id: &str| {
+        matches
+            .get_many::<String>(id)
+            .and_then(|mut values| values.next().cloned())
+    };
+    let regions: Vec<String> = first("region").into_iter().collect();
+    let profiles: Vec<String> = first("profile_name").into_iter().collect();
+    let role = targets::RoleOpt::from_arg_matches(&matches).ok()?;
+    let connection = targets::ConnectionOpt::from_arg_matches(&matches).ok()?;
+    let targets = match targets::load_targets(&regions, &profiles, &role, &connection).await {
+        Ok(targets) => targets,
+        Err(e) => {
+            report::error(Some("Failed to assume the role"), &e);
+            std::process::exit(report::exit_code(&e));
+        }
+    };
+    let client = &targets[0].client;
+
+    let exit_on_error = |e: aws_sdk_cloudwatchlogs::Error| -> ! {
+        report::error(None, &e);
+        std::process::exit(report::exit_code(&e));
+    };
+    let mut picked = Vec::new();
+    let group = match is_missing("--group ") {
+        true => {
+            let group = picker::pick_group(client)
+                .await
+                .unwrap_or_else(|e| exit_on_error(e))?;
+            picked.push(format!("--group={}", group).into());
+            group
+        }
+        false => {
+            let mut subcommand = &matches;
+            while let Some((_, next)) = subcommand.subcommand() {
+                subcommand = next;
+            }
+            let mut groups = subcommand.try_get_raw("group").ok()??;
+            groups.next()?.to_str()?.to_string()
+        }
+    };
+    if is_missing("--stream ") {
+        let stream = picker::pick_stream(client, &group)
+            .await
+            .unwrap_or_else(|e| exit_on_error(e))?;
+        picked.push(format!("--stream={}", stream).into());
+    }
+    Some(picked)
+}
+
+/// Adds `picked` to the end of the arguments, before a `--` that ends the options.
+fn insert_args(args: &mut Vec<OsString>, picked: &[OsString]) {
+    let at = args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(args.len());
+    args.splice(at..at, picked.iter().cloned());
+}
+
+/// Runs a subcommand that connects to CloudWatch Logs.
+async fn run_command(
+    targets: &[targets::Target],
+    command: Command,
+    verbose: bool,
+    color: bool,
+) -> Result<(), Error> {
+    // 他のコマンドではクライアントは1つだけ
+    let client = &targets[0].client;
+    match command {
+        Command::ListStreams(opt) => streams::run(targets, opt, verbose, color).await,
+        Command::ListGroups(opt) => groups::run(targets, opt, verbose, color).await,
+        Command::GetEvents(opt) => events::run(client, opt, verbose, color).await,
+        Command::Tail(opt) => tail::run(&targets[0], opt, verbose, color).await,
+        Command::Query(opt) => query::run(&targets[0], opt, verbose).await,
+        Command::Export(opt) => export::run(&targets[0], opt, verbose).await,
+        Command::CreateGroup(opt) => manage::create_group(client, opt, verbose).await,
+        Command::DeleteGroup(opt) => manage::delete_group(client, opt, verbose).await,
+        Command::CreateStream(opt) => manage::create_stream(client, opt, verbose).await,
+        Command::DeleteStream(opt) => manage::delete_stream(client, opt, verbose).await,
+        Command::SetRetention(opt) => manage::set_retention(client, opt, verbose).await,
+        Command::AssociateKmsKey(opt) => manage::associate_kms_key(client, opt, verbose).await,
+        Command::DisassociateKmsKey(opt) => {
+            manage::disassociate_kms_key(client, opt, verbose).await
+        }
+        Command::PruneStreams(opt) => manage::prune_streams(client, opt, verbose).await,
+        Command::Audit(opt) => audit::run(targets, opt, verbose).await,
+        Command::Tags(opt) => tags::run(client, opt, verbose).await,
+        Command::MetricFilters(opt) => metric_filters::run(client, opt, verbose).await,
+        Command::Subscriptions(opt) => subscriptions::run(client, opt, verbose).await,
+        Command::Anomalies(opt) => anomalies::run(client, opt, verbose).await,
+        Command::DataProtection(opt) => data_protection::run(client, opt, verbose).await,
+        Command::ResourcePolicies(opt) => resource_policies::run(client, opt, verbose).await,
+        Command::AccountPolicies(opt) => account_policies::run(client, opt, verbose).await,
+        Command::Queries(opt) => queries::run(client, opt, verbose).await,
+        Command::QueryDefs(opt) => query_defs::run(client, opt, verbose).await,
+        Command::LambdaStats(opt) => lambda_stats::run(client, opt, verbose).await,
+        Command::Trace(opt) => trace::run(&targets[0], opt, verbose, color).await,
+        Command::Correlate(opt) => correlate::run(&targets[0], opt, verbose, color).await,
+        Command::SfnLogs(opt) => step_functions::run(&targets[0], opt, verbose, color).await,
+        Command::Browse(opt) => browse::run(client, opt, color).await,
+        Command::Completions(_) => unreachable!("completions are printed before connecting"),
+        Command::Bookmark(_) => unreachable!("bookmarks are handled before connecting"),
+        Command::History(_) => unreachable!("the history is handled before connecting"),
+        Command::Repl(_) => unreachable!("the REPL does not run inside itself"),
+    }
+}
+
+/// Runs the subcommands typed at the `repl` prompt until `exit` or the end of input.
+/// A failed subcommand is reported and the prompt comes back.
+async fn run_repl(
+    targets: &[targets::Target],
+    config: &'static config::Config,
+    opt: repl::ReplOpt,
+    verbose: bool,
+    color: bool,
+) -> Result<(), Error> {
+    let line_command = || config.apply_output(ReplLine::command());
+    let mut session = repl::Session::new(targets[0].client.clone(), line_command(), opt, color);
+    loop {
+        let words = match session.read().await {
+            repl::Input::Command(words) => words,
+            repl::Input::Next => continue,
+            repl::Input::Exit => return Ok(()),
+        };
+        let line = line_command()
+            .try_get_matches_from(words)
+            .and_then(|matches| ReplLine::from_arg_matches(&matches));
+        let mut command = match line {
+            Ok(line) => line.command,
+            Err(e) => {
+                let _ = e.print();
+                continue;
+            }
+        };
+        if let Command::Query(opt) = &mut command {
+            opt.output = opt.output.or_else(|| config.output());
+        }
+        if let Err(message) = command.validate() {
+            eprintln!("Error: {}", message);
+            continue;
+        }
+        // 警告と JSON での報告はコマンドごとに決める
+        report::reset_warnings();
+        report::set_json(command.output() == Some(output::OutputFormat::Json));
+        let result = match command {
+            Command::Repl(_)
+            | Command::Browse(_)
+            | Command::Completions(_)
+            | Command::Bookmark(_)
+            | Command::History(_) => {
+                eprintln!("Error: This subcommand cannot run in the REPL.");
+                continue;
+            }
+            // Ctrl-C で止めて (実行中のクエリは StopQuery して) プロンプトに戻る
+            command => tokio::select! {
+                result = run_command(targets, command, verbose, color) => result,
+                _ = tokio::signal::ctrl_c() => {
+                    query::stop_running().await;
+                    eprintln!("Interrupted.");
+                    Ok(())
+                }
+            },
+        };
+        // 出力を書き出してから報告し、書き出せなかったこともコマンドの失敗として扱う
+        let flushed = output::flush_output();
+        if let Err(e) = result.and(flushed) {
+            report::failure(&e);
         }
     }
-    println!("Found {} streams:", streams.len());
-    Ok(streams)
 }
 
-// snippet-end:[cloudwatchlogs.rust.list-log-streams]
+/// Writes out the output of a subcommand that runs without connecting, exiting if it cannot.
+fn flush_or_exit() {
+    if let Err(e) = output::flush_output() {
+        std::process::exit(report::failure(&e));
+    }
+}
 
-/// Lists the log streams for a log group in the Region.
+/// Runs a CloudWatch Logs subcommand in the Region.
 /// # Arguments
 ///
-/// * `-g LOG-GROUP` - The name of the log group.
-/// * `[-r REGION]` - The Region in which the client is created.
+/// * `list-streams -g LOG-GROUP... [--group-prefix PREFIX]` - Lists the log streams in the log groups.
+///   Group names can be globs such as `/aws/ecs/*-prod`, `--lambda FUNCTION` selects `/aws/lambda/FUNCTION`,
+///   `--stack STACK` the log groups of a CloudFormation stack, and `--apigw API-ID [--stage STAGE]`
+///   the execution and access logs of an API Gateway API.
+///   `--watch INTERVAL` lists them again on an interval, marking what changed.
+/// * `list-groups [--prefix PREFIX] [--watch INTERVAL]` - Lists the log groups with their metadata.
+/// * `get-events (-g LOG-GROUP | --lambda FUNCTION) [-s LOG-STREAM]...` - Prints the events in the log streams.
+/// * `tail -g LOG-GROUP... [--group-prefix PREFIX] [-f]` - Prints the recent events in the log groups.
+///   `--access-log` splits API Gateway access logs into fields.
+///   `--ecs-cluster CLUSTER (--ecs-task TASK | --ecs-service SERVICE)` tails the streams of ECS containers.
+/// * `query -g LOG-GROUP... [--group-prefix PREFIX] (-Q QUERY | --template NAME [--param NAME=VALUE]...) --start START` -
+///   Runs a Logs Insights query, or one of the built-in templates.
+/// * `export -g LOG-GROUP --since START --bucket BUCKET [--download DIR]` - Exports the log group to S3,
+///   optionally downloading the result.
+/// * `create-group -g LOG-GROUP [--retention DAYS] [--kms-key-id ARN] [--log-class CLASS] [--tag KEY=VALUE]...` -
+///   Creates a log group.
+/// * `delete-group -g LOG-GROUP...` - Deletes the log groups.
+/// * `create-stream -g LOG-GROUP -s LOG-STREAM...` - Creates the log streams.
+/// * `delete-stream -g LOG-GROUP (-s LOG-STREAM... | --stdin)` - Deletes the log streams.
+/// * `set-retention -g LOG-GROUP (--days DAYS | --never-expire)` - Sets the retention of the log group.
+/// * `associate-kms-key -g LOG-GROUP --kms-key-id ARN` / `disassociate-kms-key -g LOG-GROUP` -
+///   Starts or stops encrypting the log group with a KMS key.
+/// * `prune-streams -g LOG-GROUP --older-than DURATION [--dry-run]` - Deletes the streams
+///   without recent events.
+/// * `audit retention [--max-days DAYS]` - Reports the log groups that keep events forever or too long.
+/// * `tags (list | add | remove) -g LOG-GROUP` - Manages the tags of the log group.
+/// * `metric-filters (list | put | delete) -g LOG-GROUP` - Manages the metric filters of the log group.
+/// * `subscriptions (list [-g LOG-GROUP] | put | delete)` - Manages subscription filters.
+/// * `anomalies [-g LOG-GROUP] [--min-severity SEVERITY]` - Lists the anomalies found by anomaly detection.
+/// * `data-protection (list [--require GLOB] | show -g LOG-GROUP)` - Audits data protection policies.
+/// * `resource-policies (list | put | delete)` - Manages the resource policies of the Region.
+/// * `account-policies (list | put | delete) [--type TYPE]` - Manages the account-wide data protection
+///   and subscription filter policies.
+/// * `queries (list [--status STATUS] | stop QUERY-ID...)` - Finds and stops Logs Insights queries.
+/// * `query-defs (list | put | delete)` - Manages saved Logs Insights queries.
+/// * `lambda-stats (-g LOG-GROUP | --lambda FUNCTION) --since START` - Summarizes Lambda invocations.
+/// * `trace TRACE-ID -g LOG-GROUP... [--before DURATION] [--after DURATION]` - Finds the events of an X-Ray trace.
+/// * `correlate --id ID [--field FIELD] -g LOG-GROUP... [--since START]` - Finds the events of one request.
+/// * `sfn-logs EXECUTION-ARN [-g LOG-GROUP]` - Prints the logged history of a Step Functions execution.
+/// * `completions (bash | zsh | fish | powershell)` - Prints a shell completion script.
+/// * `bookmark (add NAME -g LOG-GROUP... [-r REGION]... | list | rm NAME...)` - Manages named
+///   bookmarks of log groups, given as `-g @NAME`.
+/// * `repl [-g LOG-GROUP]` - Runs subcommands typed at a prompt, in a current log group.
+/// * `history (list [--grep TEXT] [-n N] | run NUMBER [--print] | clear)` - Lists and runs again
+///   the queries and filtered searches run before.
+/// * `browse [-g LOG-GROUP] [--since START] [--filter-pattern PATTERN]` - Browses log groups and
+///   streams full-screen, following the events of the selected one.
+/// * `[-r REGION]...` - The Regions in which the clients are created.
 ///   If not supplied, uses the value of the **AWS_REGION** environment variable.
-/// * `[-v]` - Whether to display additional information.
-/// * `[-p PROFILE]` - The name of the AWS profile.
+/// * `[--all-regions]` - Whether to list in every Region.
+/// * `[-v]...` - Whether to display additional information, and how much the SDK logs.
+/// * `[--log-level FILTER]` - Which SDK logs to print, e.g. `aws_smithy_runtime=debug`.
+/// * `[--quiet]` - Whether to print only the data lines, without counts and totals.
+/// * `[--color WHEN]` - When to color the output. Honors **NO_COLOR**.
+/// * `[--tz ZONE]` - The time zone of printed timestamps.
+/// * `[--timestamps absolute|relative]` - Whether to print times or how long ago they were.
+/// * `[--pager]` - Whether to show the output in a pager.
+/// * `[--output-file PATH [--gzip] [--rotate-size SIZE] [--rotate-interval DURATION]]` -
+///   The file to write the output to, optionally compressed and rotated.
+/// * `[-p PROFILE]...` - The names of the AWS profiles.
 ///   If not supplied, uses the default profile.
-/// # Returns
+/// * `[--role-arn ROLE [--external-id ID] [--mfa-serial SERIAL --mfa-code CODE]]` -
+///   The IAM role to assume with the profile's credentials.
+/// * `[--endpoint-url URL]` - The endpoint to send requests to instead of AWS.
+/// * `[--max-attempts N] [--retry-mode MODE]` - How throttled requests are retried.
+/// * `[--max-tps N]` - The most CloudWatch Logs requests to send per second.
 ///
+/// # Configuration
+///
+/// `~/.config/print-log-streams/config.toml` (or `$PRINT_LOG_STREAMS_CONFIG`) can set the defaults
+/// of `--region`, `--profile`, `--output`, `--color`, `--tz` and `--timestamps`, and name favorite
+/// groups to give as `-g @NAME`. A flag wins over **AWS_REGION**, **AWS_PROFILE** and **NO_COLOR**,
+/// which win over the file, which wins over the profile's Region and the built-in defaults.
+///
+/// # Exit Status
+///
+/// * `0` - The command succeeded.
+/// * `1` - The command failed for any other reason.
+/// * `2` - The command line was invalid.
+/// * `3` - The log group or stream does not exist.
+/// * `4` - The command finished, but some calls failed and the output is incomplete.
+/// * `5` - The credentials are missing, expired or not allowed to make a call.
+/// * `130` - The command was stopped with Ctrl-C.
+/// * `141` - The reader of the output went away, e.g. `| head`.
+/// * `143` - The command was stopped with SIGTERM.
 #[tokio::main]
-async fn main() -> Result<(), aws_sdk_cloudwatchlogs::Error> {
+async fn main() {
+    let config = config::load().unwrap_or_else(|message| {
+        eprintln!("Error: {}", message);
+        std::process::exit(report::EXIT_FAILURE);
+    });
+    let bookmarks = bookmarks::Bookmarks::load().unwrap_or_else(|message| {
+        eprintln!("Error: {}", message);
+        std::process::exit(report::EXIT_FAILURE);
+    });
+    let mut raw_args: Vec<OsString> = std::env::args_os().collect();
+    let mut args = bookmarks
+        .expand(config, raw_args.iter().cloned())
+        .unwrap_or_else(|message| {
+            Opt::command()
+                .error(ErrorKind::InvalidValue, message)
+                .exit()
+        });
+    let matches = match config.apply(Opt::command()).try_get_matches_from(&args) {
+        Ok(matches) => matches,
+        // --group や --stream だけが足りなければ、端末で選ばせて続ける
+        Err(e) => {
+            let Some(picked) = pick_missing(config, &args, &e).await else {
+                e.exit()
+            };
+            insert_args(&mut args, &picked);
+            insert_args(&mut raw_args, &picked);
+            config.apply(Opt::command()).get_matches_from(&args)
+        }
+    };
     let Opt {
         region,
-        group,
+        all_regions,
         verbose,
+        log_level,
+        quiet,
         profile_name,
-    } = Opt::parse();
+        role,
+        connection,
+        color,
+        tz,
+        timestamps,
+        pager,
+        output_file,
+        gzip,
+        rotate_size,
+        rotate_interval,
+        mut command,
+    } = Opt::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // ファイルに書く場合、自動では色を付けない
+    let color = match output_file {
+        Some(_) if color == color::ColorChoice::Auto => false,
+        _ => color.enabled(),
+    };
+    if let (Command::Query(opt), Some(path)) = (&mut command, &output_file) {
+        opt.output = opt.output.or_else(|| output::OutputFormat::for_path(path));
+    }
+    if let Command::Query(opt) = &mut command {
+        opt.output = opt.output.or_else(|| config.output());
+    }
+    if let Err(message) = command.validate() {
+        Opt::command()
+            .error(ErrorKind::ArgumentConflict, message)
+            .exit();
+    }
+    output::set_quiet(quiet);
+    tz::set(tz);
+    output::set_timestamp_style(timestamps);
 
-    if verbose {
-        tracing_subscriber::fmt::init();
+    // 補完スクリプト、ブックマークと履歴は AWS に接続せずに扱う
+    if let Command::Completions(opt) = command {
+        completions::run(Opt::command(), opt);
+        flush_or_exit();
+        return;
+    }
+    if let Command::Bookmark(opt) = command {
+        // config.toml の region ではなく、コマンドラインで指定したものだけを保存する
+        let regions = match matches.value_source("region") {
+            Some(ValueSource::CommandLine) => region,
+            _ => Vec::new(),
+        };
+        let result = bookmarks::run(opt, regions);
+        flush_or_exit();
+        if let Err(message) = result {
+            eprintln!("Error: {}", message);
+            std::process::exit(report::EXIT_FAILURE);
+        }
+        return;
+    }
+    if let Command::History(opt) = command {
+        let result = history::run(opt);
+        flush_or_exit();
+        match result {
+            Ok(0) => return,
+            Ok(code) => std::process::exit(code),
+            Err(message) => {
+                eprintln!("Error: {}", message);
+                std::process::exit(report::EXIT_FAILURE);
+            }
+        }
+    }
+    if command.is_search() {
+        history::record(&raw_args[1..]);
     }
 
-    let mut config_loader = aws_config::from_env();
-    if let Some(profile_name) = profile_name {
-        config_loader = config_loader.profile_name(profile_name);
+    let regions: Vec<String> = match all_regions {
+        true => targets::ALL_REGIONS.iter().map(|r| r.to_string()).collect(),
+        false => region,
+    };
+    if role.mfa_serial.is_some() && profile_name.len() > 1 {
+        Opt::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--mfa-serial cannot be used with several profiles",
+            )
+            .exit();
     }
-    if let Some(region) = region {
-        config_loader = config_loader.region(Region::new(region));
+    if (regions.len() > 1 || profile_name.len() > 1) && !command.fans_out() {
+        Opt::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "only list-streams and list-groups can run in several Regions or profiles",
+            )
+            .exit();
     }
 
-    let shared_config = config_loader.load().await;
+    report::set_json(command.output() == Some(output::OutputFormat::Json));
+
+    init_logging(verbose, log_level);
+    let verbose = verbose > 0;
+
+    let targets = match targets::load_targets(&regions, &profile_name, &role, &connection).await {
+        Ok(targets) => targets,
+        Err(e) => {
+            report::error(Some("Failed to assume the role"), &e);
+            std::process::exit(report::exit_code(&e));
+        }
+    };
+    // 終了時に出力を閉じてページャーを待つので、main の最後まで保持する
+    let _pager = if pager { pager::Pager::start() } else { None };
+    if let Some(path) = &output_file {
+        let rotation = output::Rotation {
+            max_bytes: rotate_size,
+            interval: rotate_interval,
+        };
+        if let Err(e) = output::open_output_file(path, gzip, rotation) {
+            eprintln!("Error: Failed to create {}: {}", path.display(), e);
+            std::process::exit(report::EXIT_FAILURE);
+        }
+    }
 
     if verbose {
-        println!();
-        println!("CloudWatchLogs client version: {}", PKG_VERSION);
-        println!(
-            "Region:                        {}",
-            shared_config.region().unwrap().as_ref()
-        );
-        println!("Log group name:                {}", &group);
-        println!();
-    }
-
-    let client = Client::new(&shared_config);
-    let streams = get_streams(&client, &group).await?;
-    println!("Found {} streams:", streams.len());
-
-    for stream in streams.into_iter() {
-        if let Some(stream_name) = stream.log_stream_name() {
-            println!(
-                "{}",
-                stream_name
+        eprintln!();
+        eprintln!("CloudWatchLogs client version: {}", PKG_VERSION);
+        if let Some(path) = config.path() {
+            eprintln!("Config file:                   {}", path.display());
+        }
+        if let Some(endpoint_url) = &connection.endpoint_url {
+            eprintln!("Endpoint URL:                  {}", endpoint_url);
+        }
+        for target in &targets {
+            if let Some(profile) = &target.profile {
+                eprintln!("Profile:                       {}", profile);
+            }
+            eprintln!(
+                "Region:                        {}",
+                target.client.config().region().unwrap().as_ref()
             );
-        } else {
-            println!("No stream name found");
         }
+        eprintln!();
+    }
+
+    let group_names = command.group_names();
+    let result = match command {
+        Command::Repl(opt) => run_repl(&targets, config, opt, verbose, color).await,
+        // 全画面のコマンドは Ctrl-C をキーとして読み、自分で終わる
+        command @ Command::Browse(_) => {
+            let terminated = tokio::select! {
+                result = run_command(&targets, command, verbose, color) => Ok(result),
+                code = signals::terminated() => Err(code),
+            };
+            // select! を抜けた時点でコマンドは破棄され、端末は元に戻っている
+            match terminated {
+                Ok(result) => result,
+                Err(code) => {
+                    if let Err(e) = output::flush_output() {
+                        report::failure(&e);
+                    }
+                    drop(_pager);
+                    std::process::exit(code);
+                }
+            }
+        }
+        command => tokio::select! {
+            result = run_command(&targets, command, verbose, color) => result,
+            code = signals::interrupted() => {
+                query::stop_running().await;
+                // 書きかけの出力を書き出し、gzip の末尾まで書いてから終了する
+                if let Err(e) = output::flush_output() {
+                    report::failure(&e);
+                }
+                if let Err(e) = output::close_output_file() {
+                    eprintln!("Error: Failed to write the output file: {}", e);
+                }
+                drop(_pager);
+                std::process::exit(code);
+            }
+        },
+    };
+    let flushed = output::flush_output();
+    let result = result.and(flushed);
+    if let Err(e) = output::close_output_file() {
+        eprintln!("Error: Failed to write the output file: {}", e);
+        std::process::exit(report::EXIT_FAILURE);
+    }
+    let code = match result {
+        Ok(()) => report::warned_exit_code(),
+        // SDK のエラーをそのまま出す代わりに、似た名前のロググループを提案する
+        Err(Error::Service(aws_sdk_cloudwatchlogs::Error::ResourceNotFoundException(_)))
+            if !report::is_json()
+                && groups::report_missing(&targets[0].client, &group_names).await =>
+        {
+            report::EXIT_NOT_FOUND
+        }
+        Err(e) => report::failure(&e),
+    };
+    if code != 0 {
+        // exit はデストラクタを呼ばないので、先にページャーの終了を待つ
+        drop(_pager);
+        std::process::exit(code);
     }
-    Ok(())
 }
@@ -0,0 +1,647 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use std::time::Duration;
+
+use aws_sdk_cloudwatchlogs::{types::LogGroupClass, Client};
+use clap::{Args, ValueEnum};
+use futures_util::{stream, StreamExt};
+use regex_lite::Regex;
+
+use crate::confirm::ConfirmOpt;
+use crate::error::Error;
+use crate::outln;
+use crate::output::{self, format_timestamp};
+use crate::progress::Progress;
+use crate::report;
+use crate::streams::{for_each_stream, StreamRequestOpt};
+use crate::time_range::{now_millis, parse_duration};
+
+/// The retention periods, in days, that PutRetentionPolicy accepts.
+const RETENTION_DAYS: &[i32] = &[
+    1, 3, 5, 7, 14, 30, 60, 90, 120, 150, 180, 365, 400, 545, 731, 1096, 1827, 2192, 2557, 2922,
+    3288, 3653,
+];
+
+/// Parses a retention period, rejecting the values that CloudWatch Logs does not support.
+pub fn parse_retention(s: &str) -> Result<i32, String> {
+    let days: i32 = s.parse().map_err(|_| format!("invalid number `{}`", s))?;
+    match RETENTION_DAYS.contains(&days) {
+        true => Ok(days),
+        false => Err(format!(
+            "unsupported retention of {} days; use one of {}",
+            days,
+            RETENTION_DAYS
+                .iter()
+                .map(|days| days.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Parses a `KEY=VALUE` tag.
+pub fn parse_tag(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some(("", _)) => Err(format!("tag `{}` has no key", s)),
+        Some((key, value)) => Ok((key.to_string(), value.to_string())),
+        None => Err(format!("tag `{}` is not of the form KEY=VALUE", s)),
+    }
+}
+
+/// The storage class of a new log group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogClass {
+    /// Supports every CloudWatch Logs feature.
+    Standard,
+    /// Cheaper ingestion for logs that are rarely queried, without live tail or metric filters.
+    InfrequentAccess,
+}
+
+impl From<LogClass> for LogGroupClass {
+    fn from(class: LogClass) -> Self {
+        match class {
+            LogClass::Standard => LogGroupClass::Standard,
+            LogClass::InfrequentAccess => LogGroupClass::InfrequentAccess,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CreateGroupOpt {
+    /// The name of the log group to create.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// The number of days to keep events for. Events never expire if not supplied.
+    #[arg(long, value_name = "DAYS", value_parser = parse_retention)]
+    pub retention: Option<i32>,
+
+    /// The ARN of the KMS key to encrypt the log group's events with.
+    #[arg(long, value_name = "ARN")]
+    pub kms_key_id: Option<String>,
+
+    /// The storage class of the log group. It cannot be changed later.
+    #[arg(long, value_enum)]
+    pub log_class: Option<LogClass>,
+
+    /// A tag to add to the log group, as `KEY=VALUE`. Can be repeated.
+    #[arg(long = "tag", value_name = "KEY=VALUE", value_parser = parse_tag)]
+    pub tags: Vec<(String, String)>,
+}
+
+#[derive(Debug, Args)]
+pub struct DeleteGroupOpt {
+    /// The names of the log groups to delete, along with all of their streams and events.
+    #[arg(short, long, required = true)]
+    pub group: Vec<String>,
+
+    #[command(flatten)]
+    pub confirm: ConfirmOpt,
+}
+
+#[derive(Debug, Args)]
+pub struct CreateStreamOpt {
+    /// The name of the log group to create the stream in.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// The names of the log streams to create.
+    #[arg(short, long, required = true)]
+    pub stream: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct DeleteStreamOpt {
+    /// The name of the log group that contains the streams.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// The names of the log streams to delete.
+    #[arg(
+        short,
+        long,
+        required_unless_present = "stdin",
+        conflicts_with = "stdin"
+    )]
+    pub stream: Vec<String>,
+
+    /// Reads the names of the streams to delete from standard input, one per line,
+    /// e.g. from `list-streams --prefix test-`. Streams that fail to delete are reported
+    /// and skipped.
+    #[arg(long)]
+    pub stdin: bool,
+
+    /// How many streams are deleted at the same time.
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u16).range(1..))]
+    pub concurrency: u16,
+
+    #[command(flatten)]
+    pub confirm: ConfirmOpt,
+}
+
+#[derive(Debug, Args)]
+pub struct SetRetentionOpt {
+    /// The name of the log group.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// The number of days to keep events for.
+    #[arg(long, value_parser = parse_retention, required_unless_present = "never_expire")]
+    pub days: Option<i32>,
+
+    /// Removes the retention policy so that events are kept forever.
+    #[arg(long, conflicts_with = "days")]
+    pub never_expire: bool,
+
+    #[command(flatten)]
+    pub confirm: ConfirmOpt,
+}
+
+#[derive(Debug, Args)]
+pub struct AssociateKmsKeyOpt {
+    /// The name of the log group.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// The ARN of the KMS key to encrypt the log group's new events with.
+    /// The key policy must allow CloudWatch Logs to use it.
+    #[arg(long, value_name = "ARN")]
+    pub kms_key_id: String,
+}
+
+#[derive(Debug, Args)]
+pub struct DisassociateKmsKeyOpt {
+    /// The name of the log group.
+    #[arg(short, long)]
+    pub group: String,
+
+    #[command(flatten)]
+    pub confirm: ConfirmOpt,
+}
+
+#[derive(Debug, Args)]
+pub struct PruneStreamsOpt {
+    /// The name of the log group to prune.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// Deletes the streams whose last event is older than this, e.g. `90d`.
+    /// Streams without any events are judged by when they were created.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub older_than: Duration,
+
+    /// Only prunes streams whose names start with this prefix.
+    #[arg(long)]
+    pub prefix: Option<String>,
+
+    /// Only prunes streams whose names match this regular expression.
+    #[arg(long = "match", value_name = "REGEX")]
+    pub name_match: Option<Regex>,
+
+    /// Prints the streams that would be deleted without deleting them.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// How many streams are deleted at the same time.
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u16).range(1..))]
+    pub concurrency: u16,
+
+    #[command(flatten)]
+    pub confirm: ConfirmOpt,
+}
+
+/// Creates a log group with CreateLogGroup, then sets its retention with PutRetentionPolicy.
+pub async fn create_group(
+    client: &Client,
+    opt: CreateGroupOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let CreateGroupOpt {
+        group,
+        retention,
+        kms_key_id,
+        log_class,
+        tags,
+    } = opt;
+
+    if verbose {
+        eprintln!("Log group name:                {}", &group);
+        if let Some(retention) = retention {
+            eprintln!("Retention:                     {} days", retention);
+        }
+        if let Some(kms_key_id) = &kms_key_id {
+            eprintln!("KMS key ID:                    {}", kms_key_id);
+        }
+        if let Some(log_class) = log_class {
+            eprintln!(
+                "Log group class:               {}",
+                LogGroupClass::from(log_class).as_str()
+            );
+        }
+        for (key, value) in &tags {
+            eprintln!("Tag:                           {}={}", key, value);
+        }
+        eprintln!();
+    }
+
+    client
+        .create_log_group()
+        .log_group_name(&group)
+        .set_kms_key_id(kms_key_id)
+        .set_log_group_class(log_class.map(LogGroupClass::from))
+        .set_tags((!tags.is_empty()).then(|| tags.into_iter().collect::<HashMap<_, _>>()))
+        .send()
+        .await?;
+    outln!("Created log group {}", group);
+
+    // 作成時には保持期間を指定できない
+    if let Some(retention) = retention {
+        client
+            .put_retention_policy()
+            .log_group_name(&group)
+            .retention_in_days(retention)
+            .send()
+            .await?;
+        outln!("Set the retention of {} to {} days", group, retention);
+    }
+    Ok(())
+}
+
+/// Deletes log groups with DeleteLogGroup, stopping at the first that fails.
+pub async fn delete_group(
+    client: &Client,
+    opt: DeleteGroupOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let DeleteGroupOpt { group, confirm } = opt;
+
+    if verbose {
+        for group in &group {
+            eprintln!("Log group name:                {}", group);
+        }
+        eprintln!();
+    }
+
+    confirm.confirm(
+        &format!(
+            "About to delete {} log groups with all of their streams and events:",
+            group.len()
+        ),
+        &group,
+    )?;
+    for group in group {
+        client
+            .delete_log_group()
+            .log_group_name(&group)
+            .send()
+            .await?;
+        outln!("Deleted log group {}", group);
+    }
+    Ok(())
+}
+
+/// Creates log streams with CreateLogStream, stopping at the first that fails.
+pub async fn create_stream(
+    client: &Client,
+    opt: CreateStreamOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let CreateStreamOpt { group, stream } = opt;
+
+    if verbose {
+        eprintln!("Log group name:                {}", &group);
+        for stream in &stream {
+            eprintln!("Log stream name:               {}", stream);
+        }
+        eprintln!();
+    }
+
+    for stream in stream {
+        client
+            .create_log_stream()
+            .log_group_name(&group)
+            .log_stream_name(&stream)
+            .send()
+            .await?;
+        outln!("Created log stream {}", stream);
+    }
+    Ok(())
+}
+
+/// Reads stream names from standard input, skipping blank lines.
+fn read_stream_names() -> Result<Vec<String>, Error> {
+    let mut names = Vec::new();
+    for line in io::stdin().lock().lines() {
+        let line =
+            line.map_err(|e| Error::failed(format!("Failed to read the stream names: {}", e)))?;
+        let line = line.trim();
+        if !line.is_empty() {
+            names.push(line.to_string());
+        }
+    }
+    Ok(names)
+}
+
+/// Deletes log streams with DeleteLogStream, at most `concurrency` at a time,
+/// and returns the result for each stream in order.
+async fn delete_log_streams<'a>(
+    client: &Client,
+    group: &str,
+    streams: &'a [String],
+    concurrency: u16,
+) -> Vec<(&'a str, Result<(), aws_sdk_cloudwatchlogs::Error>)> {
+    stream::iter(streams)
+        .map(|stream| async move {
+            let result = client
+                .delete_log_stream()
+                .log_group_name(group)
+                .log_stream_name(stream)
+                .send()
+                .await;
+            (stream.as_str(), result.map(|_| ()).map_err(Into::into))
+        })
+        .buffered(usize::from(concurrency))
+        .collect()
+        .await
+}
+
+/// Deletes log streams, at most `concurrency` at a time.
+/// Streams named on the command line fail the command when they cannot be deleted, once every
+/// stream has been tried and reported; streams read from standard input are reported with
+/// a warning and skipped.
+pub async fn delete_stream(
+    client: &Client,
+    opt: DeleteStreamOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let DeleteStreamOpt {
+        group,
+        stream: streams,
+        stdin,
+        concurrency,
+        confirm,
+    } = opt;
+    let streams = match stdin {
+        true => read_stream_names()?,
+        false => streams,
+    };
+
+    if verbose {
+        eprintln!("Log group name:                {}", &group);
+        eprintln!("Log streams:                   {}", streams.len());
+        eprintln!("Concurrency:                   {}", concurrency);
+        eprintln!();
+    }
+
+    confirm.confirm(
+        &format!(
+            "About to delete {} log streams from {}:",
+            streams.len(),
+            group
+        ),
+        &streams,
+    )?;
+    let mut deleted = 0;
+    // 失敗した後のストリームも消えているので、最後まで報告してから失敗させる
+    let mut failures = 0;
+    let mut code = None;
+    for (stream, result) in delete_log_streams(client, &group, &streams, concurrency).await {
+        let context = format!("Failed to delete log stream {}", stream);
+        match result {
+            Ok(()) => {
+                outln!("Deleted log stream {}", stream);
+                deleted += 1;
+            }
+            Err(e) if stdin => report::warn(&context, &e),
+            Err(e) => {
+                report::error(Some(&context), &e);
+                failures += 1;
+                // 最初の失敗の終了コードで終わる
+                code.get_or_insert(report::exit_code(&e));
+            }
+        }
+    }
+    if stdin && !output::is_quiet() {
+        outln!("Deleted {} of {} streams", deleted, streams.len());
+    }
+    match code {
+        Some(code) => Err(Error::Failed {
+            message: format!(
+                "Failed to delete {} of {} log streams",
+                failures,
+                streams.len()
+            ),
+            code,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Sets the retention of a log group with PutRetentionPolicy,
+/// or removes it with DeleteRetentionPolicy.
+pub async fn set_retention(
+    client: &Client,
+    opt: SetRetentionOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let SetRetentionOpt {
+        group,
+        days,
+        never_expire: _,
+        confirm,
+    } = opt;
+
+    if verbose {
+        eprintln!("Log group name:                {}", &group);
+        match days {
+            Some(days) => eprintln!("Retention:                     {} days", days),
+            None => eprintln!("Retention:                     Never expire"),
+        }
+        eprintln!();
+    }
+
+    // 短くすると、それより古いイベントは削除される
+    let summary = match days {
+        Some(days) => format!(
+            "About to set the retention of {} to {} days. Older events will be deleted.",
+            group, days
+        ),
+        None => format!(
+            "About to remove the retention policy of {}. Its events will never expire.",
+            group
+        ),
+    };
+    confirm.confirm(&summary, &[])?;
+    match days {
+        Some(days) => {
+            client
+                .put_retention_policy()
+                .log_group_name(&group)
+                .retention_in_days(days)
+                .send()
+                .await?;
+            outln!("Set the retention of {} to {} days", group, days);
+        }
+        None => {
+            client
+                .delete_retention_policy()
+                .log_group_name(&group)
+                .send()
+                .await?;
+            outln!("Removed the retention policy of {}", group);
+        }
+    }
+    Ok(())
+}
+
+/// Encrypts the new events of a log group with a KMS key. Existing events stay encrypted with the
+/// key they were written with.
+pub async fn associate_kms_key(
+    client: &Client,
+    opt: AssociateKmsKeyOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let AssociateKmsKeyOpt { group, kms_key_id } = opt;
+
+    if verbose {
+        eprintln!("Log group name:                {}", &group);
+        eprintln!("KMS key ID:                    {}", &kms_key_id);
+        eprintln!();
+    }
+
+    client
+        .associate_kms_key()
+        .log_group_name(&group)
+        .kms_key_id(&kms_key_id)
+        .send()
+        .await?;
+    outln!("Associated {} with {}", group, kms_key_id);
+    Ok(())
+}
+
+/// Stops encrypting the new events of a log group with its KMS key.
+pub async fn disassociate_kms_key(
+    client: &Client,
+    opt: DisassociateKmsKeyOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let DisassociateKmsKeyOpt { group, confirm } = opt;
+
+    if verbose {
+        eprintln!("Log group name:                {}", &group);
+        eprintln!();
+    }
+
+    // 既存のイベントは元のキーで暗号化されたままなので、キーは無効にしないよう伝える
+    confirm.confirm(
+        &format!(
+            "About to stop encrypting new events of {} with its KMS key. \
+             Keep the key enabled to read the existing events.",
+            group
+        ),
+        &[],
+    )?;
+    client
+        .disassociate_kms_key()
+        .log_group_name(&group)
+        .send()
+        .await?;
+    outln!("Disassociated the KMS key from {}", group);
+    Ok(())
+}
+
+/// Deletes the streams of a log group that have had no events for `--older-than`.
+/// Streams that fail to delete are reported and skipped.
+pub async fn prune_streams(
+    client: &Client,
+    opt: PruneStreamsOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let PruneStreamsOpt {
+        group,
+        older_than,
+        prefix,
+        name_match,
+        dry_run,
+        concurrency,
+        confirm,
+    } = opt;
+    let cutoff = now_millis() - older_than.as_millis() as i64;
+
+    if verbose {
+        eprintln!("Log group name:                {}", &group);
+        eprintln!(
+            "Last event before:             {}",
+            format_timestamp(cutoff)
+        );
+        if let Some(prefix) = &prefix {
+            eprintln!("Log stream prefix:             {}", prefix);
+        }
+        eprintln!("Concurrency:                   {}", concurrency);
+        eprintln!();
+    }
+
+    let request = StreamRequestOpt {
+        prefix,
+        name_match,
+        ..Default::default()
+    };
+    let progress = Progress::new("streams");
+    let mut stale: Vec<(String, i64)> = Vec::new();
+    let scanned = for_each_stream(client, &group, &request, &progress, |stream| {
+        // 空のストリームには lastEventTimestamp がないので作成時刻で判断する
+        let last = stream
+            .last_event_timestamp()
+            .or(stream.creation_time())
+            .unwrap_or_default();
+        if last < cutoff {
+            stale.push((
+                stream.log_stream_name().unwrap_or_default().to_string(),
+                last,
+            ));
+        }
+    })
+    .await?;
+    progress.clear();
+
+    if dry_run {
+        for (stream, last) in &stale {
+            outln!("{}\t{}", stream, format_timestamp(*last));
+        }
+        if !output::is_quiet() {
+            outln!("Would delete {} of {} streams", stale.len(), scanned);
+        }
+        return Ok(());
+    }
+    if !stale.is_empty() {
+        let listed: Vec<String> = stale
+            .iter()
+            .map(|(stream, last)| format!("{}\t{}", stream, format_timestamp(*last)))
+            .collect();
+        confirm.confirm(
+            &format!(
+                "About to delete {} of {} log streams from {}:",
+                stale.len(),
+                scanned,
+                group
+            ),
+            &listed,
+        )?;
+    }
+
+    let names: Vec<String> = stale.into_iter().map(|(stream, _)| stream).collect();
+    let mut deleted = 0;
+    for (stream, result) in delete_log_streams(client, &group, &names, concurrency).await {
+        match result {
+            Ok(()) => {
+                outln!("Deleted log stream {}", stream);
+                deleted += 1;
+            }
+            Err(e) => report::warn(&format!("Failed to delete log stream {}", stream), &e),
+        }
+    }
+    if !output::is_quiet() {
+        outln!("Deleted {} of {} streams", deleted, scanned);
+    }
+    Ok(())
+}
@@ -0,0 +1,286 @@
+use aws_sdk_cloudwatchlogs::{
+    types::{MetricFilter, MetricTransformation, StandardUnit},
+    Client,
+};
+use clap::{Args, Subcommand};
+
+use crate::confirm::ConfirmOpt;
+use crate::error::Error;
+use crate::outln;
+use crate::output::{self, format_timestamp, OutputFormat, Record};
+
+#[derive(Debug, Args)]
+pub struct MetricFiltersOpt {
+    #[command(subcommand)]
+    pub command: MetricFiltersCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MetricFiltersCommand {
+    /// Lists the metric filters of a log group.
+    List(ListMetricFiltersOpt),
+
+    /// Creates or replaces a metric filter that turns matching events into a CloudWatch metric.
+    Put(PutMetricFilterOpt),
+
+    /// Deletes a metric filter.
+    Delete(DeleteMetricFilterOpt),
+}
+
+#[derive(Debug, Args)]
+pub struct ListMetricFiltersOpt {
+    /// The log group name.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// Only lists filters whose names start with this prefix.
+    #[arg(long)]
+    pub prefix: Option<String>,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+/// Checks a CloudWatch metric unit such as `Count` or `Milliseconds`.
+fn parse_unit(s: &str) -> Result<StandardUnit, String> {
+    match StandardUnit::values().contains(&s) {
+        true => Ok(StandardUnit::from(s)),
+        false => Err(format!(
+            "unknown unit `{}`; use one of {}",
+            s,
+            StandardUnit::values().join(", ")
+        )),
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct PutMetricFilterOpt {
+    /// The log group name.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// The name of the filter. An existing filter with this name is replaced.
+    #[arg(long)]
+    pub name: String,
+
+    /// The filter pattern that selects the events, e.g. `ERROR` or `{ $.status >= 500 }`.
+    #[arg(long)]
+    pub pattern: String,
+
+    /// The CloudWatch namespace of the metric, e.g. `MyApp`.
+    #[arg(long)]
+    pub namespace: String,
+
+    /// The name of the metric, e.g. `ErrorCount`.
+    #[arg(long)]
+    pub metric_name: String,
+
+    /// The value published for each matching event: a number, or a field such as `$.latency`.
+    #[arg(long, default_value = "1")]
+    pub value: String,
+
+    /// The value published when no event matches in a period. Nothing is published if not supplied.
+    #[arg(long)]
+    pub default_value: Option<f64>,
+
+    /// The unit of the metric, e.g. `Count` or `Milliseconds`.
+    #[arg(long, value_parser = parse_unit)]
+    pub unit: Option<StandardUnit>,
+}
+
+#[derive(Debug, Args)]
+pub struct DeleteMetricFilterOpt {
+    /// The log group name.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// The name of the filter to delete.
+    #[arg(long)]
+    pub name: String,
+
+    #[command(flatten)]
+    pub confirm: ConfirmOpt,
+}
+
+impl MetricFiltersOpt {
+    /// The log group that the subcommand works on.
+    pub fn group(&self) -> &str {
+        match &self.command {
+            MetricFiltersCommand::List(opt) => &opt.group,
+            MetricFiltersCommand::Put(opt) => &opt.group,
+            MetricFiltersCommand::Delete(opt) => &opt.group,
+        }
+    }
+}
+
+/// Describes a metric filter for structured output, with its first metric transformation.
+/// Metric filters have exactly one.
+fn filter_record(filter: &MetricFilter) -> Record {
+    let transformation = filter.metric_transformations().first();
+    Record::new()
+        .field("name", filter.filter_name())
+        .field("logGroupName", filter.log_group_name())
+        .field("filterPattern", filter.filter_pattern())
+        .field(
+            "metricNamespace",
+            transformation.map(|t| t.metric_namespace()),
+        )
+        .field("metricName", transformation.map(|t| t.metric_name()))
+        .field("metricValue", transformation.map(|t| t.metric_value()))
+        .field(
+            "defaultValue",
+            transformation
+                .and_then(|t| t.default_value())
+                .map(|value| value.to_string())
+                .as_deref(),
+        )
+        .field(
+            "unit",
+            transformation
+                .and_then(|t| t.unit())
+                .map(|unit| unit.as_str()),
+        )
+        .field("creationTime", filter.creation_time())
+}
+
+/// Describes a metric filter as a row of the `table` output.
+fn filter_table_row(filter: &MetricFilter) -> Record {
+    let metric = filter
+        .metric_transformations()
+        .first()
+        .map(|t| format!("{}/{}", t.metric_namespace(), t.metric_name()));
+    Record::new()
+        .field("NAME", filter.filter_name())
+        .field("METRIC", metric.as_deref())
+        .field(
+            "VALUE",
+            filter
+                .metric_transformations()
+                .first()
+                .map(|t| t.metric_value()),
+        )
+        .field(
+            "CREATED",
+            filter.creation_time().map(format_timestamp).as_deref(),
+        )
+        .field("PATTERN", filter.filter_pattern())
+}
+
+/// Prints the metric filters of a log group with DescribeMetricFilters.
+async fn list_filters(client: &Client, opt: ListMetricFiltersOpt) -> Result<(), Error> {
+    let ListMetricFiltersOpt {
+        group,
+        prefix,
+        output,
+    } = opt;
+    let mut pages = client
+        .describe_metric_filters()
+        .log_group_name(&group)
+        .set_filter_name_prefix(prefix)
+        .into_paginator()
+        .send();
+    let mut filters: Vec<MetricFilter> = Vec::new();
+    while let Some(page) = pages.next().await {
+        filters.extend(page?.metric_filters.unwrap_or_default());
+    }
+
+    match output {
+        OutputFormat::Text => {
+            for filter in &filters {
+                let metric = filter.metric_transformations().first();
+                outln!(
+                    "{}\t{}\t{}",
+                    filter.filter_name().unwrap_or_default(),
+                    metric.map_or_else(String::new, |t| format!(
+                        "{}/{}",
+                        t.metric_namespace(),
+                        t.metric_name()
+                    )),
+                    filter.filter_pattern().unwrap_or_default()
+                );
+            }
+        }
+        format => output::print_records(
+            format,
+            &filters.iter().map(filter_record).collect::<Vec<_>>(),
+            &filters.iter().map(filter_table_row).collect::<Vec<_>>(),
+        ),
+    }
+    Ok(())
+}
+
+/// Creates or replaces a metric filter with PutMetricFilter.
+async fn put_filter(client: &Client, opt: PutMetricFilterOpt) -> Result<(), Error> {
+    let PutMetricFilterOpt {
+        group,
+        name,
+        pattern,
+        namespace,
+        metric_name,
+        value,
+        default_value,
+        unit,
+    } = opt;
+    let transformation = MetricTransformation::builder()
+        .metric_namespace(&namespace)
+        .metric_name(&metric_name)
+        .metric_value(value)
+        .set_default_value(default_value)
+        .set_unit(unit)
+        .build()
+        .expect("the required fields are set");
+    client
+        .put_metric_filter()
+        .log_group_name(&group)
+        .filter_name(&name)
+        .filter_pattern(pattern)
+        .metric_transformations(transformation)
+        .send()
+        .await?;
+    outln!(
+        "Put metric filter {} on {} publishing {}/{}",
+        name,
+        group,
+        namespace,
+        metric_name
+    );
+    Ok(())
+}
+
+/// Deletes a metric filter with DeleteMetricFilter.
+async fn delete_filter(client: &Client, opt: DeleteMetricFilterOpt) -> Result<(), Error> {
+    let DeleteMetricFilterOpt {
+        group,
+        name,
+        confirm,
+    } = opt;
+    confirm.confirm(
+        &format!(
+            "About to delete the metric filter {} of {}. Its metric stops receiving data.",
+            name, group
+        ),
+        &[],
+    )?;
+    client
+        .delete_metric_filter()
+        .log_group_name(&group)
+        .filter_name(&name)
+        .send()
+        .await?;
+    outln!("Deleted metric filter {} of {}", name, group);
+    Ok(())
+}
+
+/// Manages the metric filters of a log group.
+pub async fn run(client: &Client, opt: MetricFiltersOpt, verbose: bool) -> Result<(), Error> {
+    if verbose {
+        eprintln!("Log group name:                {}", opt.group());
+        eprintln!();
+    }
+    match opt.command {
+        MetricFiltersCommand::List(opt) => list_filters(client, opt).await,
+        MetricFiltersCommand::Put(opt) => put_filter(client, opt).await,
+        MetricFiltersCommand::Delete(opt) => delete_filter(client, opt).await,
+    }
+}
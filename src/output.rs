@@ -0,0 +1,802 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use aws_smithy_json::serialize::JsonValueWriter;
+use aws_smithy_types::Number;
+use clap::ValueEnum;
+use time::{format_description::FormatItem, macros::format_description, OffsetDateTime, UtcOffset};
+
+use crate::error::Error;
+use crate::report;
+use crate::tz;
+
+const TIMESTAMP_FORMAT: &[FormatItem<'_>] =
+    format_description!("[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z");
+const TIMESTAMP_OFFSET_FORMAT: &[FormatItem<'_>] = format_description!(
+    "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3][offset_hour sign:mandatory]:[offset_minute]"
+);
+
+/// Prints command output like `print!`, to stdout or to the `--output-file`.
+#[macro_export]
+macro_rules! out {
+    ($($arg:tt)*) => {
+        $crate::output::write_output(format_args!($($arg)*))
+    };
+}
+
+/// Prints a line of command output like `println!`, to stdout or to the `--output-file`.
+/// Warnings and verbose information go to stderr with `eprintln!`, so that stdout only carries data.
+#[macro_export]
+macro_rules! outln {
+    () => {
+        $crate::output::write_output(format_args!("\n"))
+    };
+    ($($arg:tt)*) => {
+        $crate::output::write_output(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+/// The file that command output goes to instead of stdout, if any.
+static OUTPUT_FILE: Mutex<Option<OutputFile>> = Mutex::new(None);
+
+/// The UTC start time inserted into the names of rotated files.
+const SEGMENT_TIME_FORMAT: &[FormatItem<'_>] =
+    format_description!("[year][month][day]T[hour][minute][second]Z");
+
+/// When to move on to a new output file.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rotation {
+    /// After this many bytes, counted before compression.
+    pub max_bytes: Option<u64>,
+    /// After the file has been open this long.
+    pub interval: Option<Duration>,
+}
+
+impl Rotation {
+    fn is_enabled(&self) -> bool {
+        self.max_bytes.is_some() || self.interval.is_some()
+    }
+}
+
+struct OutputFile {
+    path: PathBuf,
+    gzip: bool,
+    rotation: Rotation,
+    segment: Segment,
+}
+
+/// One file being written. Without rotation there is only ever one.
+struct Segment {
+    writer: BufWriter<Box<dyn Write + Send>>,
+    /// The `gzip` process that compresses into the file when `--gzip` is set.
+    gzip: Option<Child>,
+    bytes: u64,
+    opened_at: Instant,
+}
+
+impl Segment {
+    fn create(path: &Path, gzip: bool) -> io::Result<Self> {
+        let file = File::create(path)?;
+        let (writer, gzip): (Box<dyn Write + Send>, _) = match gzip {
+            true => {
+                let mut child = Command::new("gzip")
+                    .arg("-c")
+                    .stdin(Stdio::piped())
+                    .stdout(file)
+                    .spawn()?;
+                let stdin = child.stdin.take().expect("stdin is piped");
+                (Box::new(stdin), Some(child))
+            }
+            false => (Box::new(file), None),
+        };
+        Ok(Segment {
+            writer: BufWriter::new(writer),
+            gzip,
+            bytes: 0,
+            opened_at: Instant::now(),
+        })
+    }
+
+    /// Finishes the file, waiting for `gzip` to write the end of the archive.
+    fn close(self) -> io::Result<()> {
+        // パイプを閉じないと gzip が入力の終わりを検知できない
+        drop(self.writer.into_inner().map_err(|e| e.into_error())?);
+        if let Some(mut gzip) = self.gzip {
+            let status = gzip.wait()?;
+            if !status.success() {
+                return Err(io::Error::other(format!("gzip exited with {}", status)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl OutputFile {
+    fn open(path: &Path, gzip: bool, rotation: Rotation) -> io::Result<Self> {
+        let segment = Segment::create(&Self::segment_path(path, rotation), gzip)?;
+        Ok(OutputFile {
+            path: path.to_path_buf(),
+            gzip,
+            rotation,
+            segment,
+        })
+    }
+
+    /// With rotation, every file is named after the time it was started, inserted before
+    /// the extensions: `dump.ndjson.gz` becomes `dump.20240501T130000Z.ndjson.gz`.
+    fn segment_path(path: &Path, rotation: Rotation) -> PathBuf {
+        if !rotation.is_enabled() {
+            return path.to_path_buf();
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (stem, extensions) = match name.find('.') {
+            Some(i) if i > 0 => name.split_at(i),
+            _ => (name.as_str(), ""),
+        };
+        let started = OffsetDateTime::now_utc()
+            .format(SEGMENT_TIME_FORMAT)
+            .unwrap_or_default();
+        // 同じ秒に複数回ローテーションした場合は連番を付けて上書きを避ける
+        let mut candidate = path.with_file_name(format!("{}.{}{}", stem, started, extensions));
+        let mut n = 1;
+        while candidate.exists() {
+            candidate = path.with_file_name(format!("{}.{}-{}{}", stem, started, n, extensions));
+            n += 1;
+        }
+        candidate
+    }
+
+    fn should_rotate(&self) -> bool {
+        let full = self
+            .rotation
+            .max_bytes
+            .is_some_and(|max| self.segment.bytes >= max);
+        let expired = self
+            .rotation
+            .interval
+            .is_some_and(|interval| self.segment.opened_at.elapsed() >= interval);
+        full || expired
+    }
+
+    /// Writes a chunk of output, first starting a new file if the current one is due.
+    /// Chunks are whole lines, so a file never ends in the middle of one.
+    fn write(&mut self, text: &str) -> io::Result<()> {
+        if self.segment.bytes > 0 && self.should_rotate() {
+            let next = Segment::create(&Self::segment_path(&self.path, self.rotation), self.gzip)?;
+            std::mem::replace(&mut self.segment, next).close()?;
+        }
+        self.segment.writer.write_all(text.as_bytes())?;
+        self.segment.bytes += text.len() as u64;
+        Ok(())
+    }
+}
+
+/// Sends command output to a new file at `path`, compressed with the system's `gzip` if asked,
+/// and moving on to a new file whenever `rotation` says so.
+pub fn open_output_file(path: &Path, gzip: bool, rotation: Rotation) -> io::Result<()> {
+    *OUTPUT_FILE.lock().unwrap() = Some(OutputFile::open(path, gzip, rotation)?);
+    Ok(())
+}
+
+/// Stdout when it is not a terminal, written in large blocks instead of a line at a time,
+/// which is what makes dumping millions of events to a pipe or a file slow.
+/// Created on the first write, once the pager, if any, has taken over stdout.
+static STDOUT: Mutex<Option<BufWriter<io::Stdout>>> = Mutex::new(None);
+
+/// Whether stdout is a terminal, which is written to a line at a time so that output shows up
+/// as it is printed.
+static STDOUT_IS_TERMINAL: OnceLock<bool> = OnceLock::new();
+
+const STDOUT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// How long buffered output waits, at most, before it is written.
+const STDOUT_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+fn write_stdout(args: fmt::Arguments<'_>) -> io::Result<()> {
+    if *STDOUT_IS_TERMINAL.get_or_init(|| io::stdout().is_terminal()) {
+        return io::stdout().write_fmt(args);
+    }
+    let mut stdout = STDOUT.lock().unwrap();
+    let stdout = stdout.get_or_insert_with(|| {
+        // 次の書き込みを待たずに、ページの間が空いても 1 秒ごとに書き出す。
+        // 失敗した分はバッファに残り、コマンドの次の書き込みか flush_output で報告される
+        thread::spawn(|| loop {
+            thread::sleep(STDOUT_FLUSH_INTERVAL);
+            let _ = flush_stdout();
+        });
+        BufWriter::with_capacity(STDOUT_BUFFER_SIZE, io::stdout())
+    });
+    stdout.write_fmt(args)
+}
+
+fn flush_stdout() -> io::Result<()> {
+    match STDOUT.lock().unwrap().as_mut() {
+        Some(stdout) => stdout.flush(),
+        None => Ok(()),
+    }
+}
+
+/// The first output that could not be written, until [`flush_output`] returns it.
+static WRITE_ERROR: Mutex<Option<io::Error>> = Mutex::new(None);
+
+/// Keeps a failed write for [`flush_output`] to return. When the reader of stdout has gone away,
+/// e.g. `| head` or a pager that was quit, there is nobody to tell, so it exits without a message.
+fn write_failed(error: io::Error, stdout: bool) {
+    if stdout && error.kind() == io::ErrorKind::BrokenPipe {
+        process::exit(report::EXIT_BROKEN_PIPE);
+    }
+    WRITE_ERROR.lock().unwrap().get_or_insert(error);
+}
+
+/// Writes command output. When it cannot be written, e.g. because the disk is full, the rest of
+/// the output is dropped and the next [`flush_output`] returns the failure.
+pub fn write_output(args: fmt::Arguments<'_>) {
+    if WRITE_ERROR.lock().unwrap().is_some() {
+        return;
+    }
+    let mut output_file = OUTPUT_FILE.lock().unwrap();
+    let result = match output_file.as_mut() {
+        Some(output_file) => output_file.write(&args.to_string()),
+        None => write_stdout(args),
+    };
+    if let Err(e) = result {
+        write_failed(e, output_file.is_none());
+    }
+}
+
+/// Whether command output goes to a terminal rather than to a file or a pipe.
+pub fn is_terminal() -> bool {
+    OUTPUT_FILE.lock().unwrap().is_none() && io::stdout().is_terminal()
+}
+
+/// Writes buffered output to stdout or the file, e.g. after each poll of `tail --follow`.
+/// Fails if any output since the last call could not be written.
+pub fn flush_output() -> Result<(), Error> {
+    let mut output_file = OUTPUT_FILE.lock().unwrap();
+    let result = match output_file.as_mut() {
+        Some(output_file) => output_file.segment.writer.flush(),
+        None => flush_stdout(),
+    };
+    if let Err(e) = result {
+        write_failed(e, output_file.is_none());
+    }
+    match WRITE_ERROR.lock().unwrap().take() {
+        Some(e) => Err(Error::failed(format!("Failed to write the output: {}", e))),
+        None => Ok(()),
+    }
+}
+
+/// Finishes the output file, waiting for `gzip` to write the end of the archive.
+pub fn close_output_file() -> io::Result<()> {
+    match OUTPUT_FILE.lock().unwrap().take() {
+        Some(output_file) => output_file.segment.close(),
+        None => Ok(()),
+    }
+}
+
+/// How the results of a command are printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text.
+    #[default]
+    Text,
+    /// A single JSON array.
+    Json,
+    /// One JSON object per line, printed as soon as each item is fetched.
+    Ndjson,
+    /// A header row followed by one comma-separated row per item.
+    Csv,
+    /// An aligned table with a header row.
+    Table,
+    /// A YAML sequence of mappings.
+    Yaml,
+}
+
+impl OutputFormat {
+    /// The format that a file name's extension names, such as `results.csv`.
+    pub fn for_path(path: &Path) -> Option<OutputFormat> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" => Some(OutputFormat::Json),
+            "ndjson" | "jsonl" => Some(OutputFormat::Ndjson),
+            "csv" => Some(OutputFormat::Csv),
+            "yaml" | "yml" => Some(OutputFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// How timestamps are printed in text and table output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TimestampStyle {
+    /// RFC 3339 times in the `--tz` time zone.
+    #[default]
+    Absolute,
+    /// How long ago, such as `3m ago` or `2h 15m ago`.
+    Relative,
+}
+
+/// Set once from `--timestamps`.
+static TIMESTAMP_STYLE: OnceLock<TimestampStyle> = OnceLock::new();
+
+/// Uses `style` for every timestamp printed from now on.
+pub fn set_timestamp_style(style: TimestampStyle) {
+    let _ = TIMESTAMP_STYLE.set(style);
+}
+
+/// Set once from `--quiet`.
+static QUIET: OnceLock<bool> = OnceLock::new();
+
+/// Leaves out counts, totals and progress from now on, so that only the data lines are printed.
+pub fn set_quiet(quiet: bool) {
+    let _ = QUIET.set(quiet);
+}
+
+/// Whether `--quiet` was given.
+pub fn is_quiet() -> bool {
+    QUIET.get().copied().unwrap_or_default()
+}
+
+/// A field value in an output record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<Option<i64>> for Value {
+    fn from(value: Option<i64>) -> Self {
+        value.map_or(Value::Null, Value::Integer)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<Option<f64>> for Value {
+    fn from(value: Option<f64>) -> Self {
+        value.map_or(Value::Null, Value::Float)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<Option<&str>> for Value {
+    fn from(value: Option<&str>) -> Self {
+        value.map_or(Value::Null, Value::from)
+    }
+}
+
+/// Plain text rendering, used by the CSV output. `Null` is empty.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::Float(n) => write!(f, "{}", n),
+            Value::String(s) => f.write_str(s),
+        }
+    }
+}
+
+impl Value {
+    /// Whether the value is right-aligned in tables.
+    fn is_number(&self) -> bool {
+        matches!(self, Value::Integer(_) | Value::Float(_))
+    }
+
+    fn write_json(&self, writer: JsonValueWriter<'_>) {
+        match self {
+            Value::Null => writer.null(),
+            Value::Integer(n) if *n < 0 => writer.number(Number::NegInt(*n)),
+            Value::Integer(n) => writer.number(Number::PosInt(*n as u64)),
+            // JSON には NaN や無限大を書けない
+            Value::Float(n) if !n.is_finite() => writer.null(),
+            Value::Float(n) => writer.number(Number::Float(*n)),
+            Value::String(s) => writer.string(s),
+        }
+    }
+}
+
+/// An ordered set of named fields describing one item of output,
+/// such as a log stream or a log event.
+#[derive(Debug, Clone, Default)]
+pub struct Record {
+    fields: Vec<(String, Value)>,
+}
+
+impl Record {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value of the named field, if the record has it.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Appends a field, keeping insertion order for every output format.
+    pub fn field(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.fields.push((name.into(), value.into()));
+        self
+    }
+
+    /// Serializes the record as a single-line JSON object.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(JsonValueWriter::new(&mut out));
+        out
+    }
+
+    fn write_json(&self, writer: JsonValueWriter<'_>) {
+        let mut object = writer.start_object();
+        for (name, value) in &self.fields {
+            value.write_json(object.key(name));
+        }
+        object.finish();
+    }
+}
+
+/// Formats epoch milliseconds as an RFC 3339 timestamp in the `--tz` time zone,
+/// or as the time since then with `--timestamps relative`.
+pub fn format_timestamp(millis: i64) -> String {
+    if TIMESTAMP_STYLE.get() == Some(&TimestampStyle::Relative) {
+        let now = OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000;
+        return format_relative(now as i64 - millis);
+    }
+    OffsetDateTime::from_unix_timestamp_nanos(i128::from(millis) * 1_000_000)
+        .ok()
+        .and_then(|t| {
+            let offset = tz::current().offset_at(t.unix_timestamp());
+            let t = t.checked_to_offset(UtcOffset::from_whole_seconds(offset).ok()?)?;
+            match offset {
+                0 => t.format(TIMESTAMP_FORMAT),
+                _ => t.format(TIMESTAMP_OFFSET_FORMAT),
+            }
+            .ok()
+        })
+        .unwrap_or_else(|| millis.to_string())
+}
+
+/// Formats how long ago something happened in the two largest units, e.g. `2h 15m ago`.
+/// Times in the future are printed as `in 3m`.
+fn format_relative(millis_ago: i64) -> String {
+    let secs = millis_ago.unsigned_abs() / 1000;
+    let (days, hours, minutes) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+    let span = match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", secs),
+        (0, 0, m) => format!("{}m", m),
+        (0, h, 0) => format!("{}h", h),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, 0, _) => format!("{}d", d),
+        (d, h, _) => format!("{}d {}h", d, h),
+    };
+    match millis_ago < 0 {
+        true => format!("in {}", span),
+        false => format!("{} ago", span),
+    }
+}
+
+/// Formats a byte count with binary units, e.g. `512 B` or `1.5 GiB`.
+pub fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes.abs() < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value.abs() >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Parses a size such as `100MB`, `512KiB` or `1048576`.
+/// `KB`, `MB`, `GB` and `TB` are powers of 1000, `KiB`, `MiB`, `GiB` and `TiB` powers of 1024.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let amount: u64 = s[..digits]
+        .parse()
+        .map_err(|_| format!("invalid size `{}`, expected e.g. 100MB", s))?;
+    let unit: u64 = match s[digits..].trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000 * 1000,
+        "gb" => 1000 * 1000 * 1000,
+        "tb" => 1000 * 1000 * 1000 * 1000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        other => return Err(format!("unknown unit `{}` in size `{}`", other, s)),
+    };
+    amount
+        .checked_mul(unit)
+        .filter(|&bytes| bytes > 0)
+        .ok_or_else(|| format!("size `{}` is out of range", s))
+}
+
+/// Serializes the records as a single JSON array.
+pub fn to_json_array(records: &[Record]) -> String {
+    let mut out = String::new();
+    let mut array = JsonValueWriter::new(&mut out).start_array();
+    for record in records {
+        record.write_json(array.value());
+    }
+    array.finish();
+    out
+}
+
+/// Quotes a CSV field when it contains a separator, quote, or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Serializes the records as CSV, using the field names of the first record as the header.
+/// The header is left out when `header` is false, e.g. for later batches of a followed stream.
+pub fn to_csv(records: &[Record], header: bool) -> String {
+    let mut out = String::new();
+    let Some(first) = records.first() else {
+        return out;
+    };
+    if header {
+        let names: Vec<String> = first
+            .fields
+            .iter()
+            .map(|(name, _)| csv_field(name))
+            .collect();
+        out.push_str(&names.join(","));
+        out.push('\n');
+    }
+    for record in records {
+        let row: Vec<String> = record
+            .fields
+            .iter()
+            .map(|(_, value)| csv_field(&value.to_string()))
+            .collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// Renders the records as an aligned table with the field names of the first record as the header.
+/// Numeric columns are right-aligned and the last column is not padded.
+pub fn to_table(records: &[Record]) -> String {
+    let mut out = String::new();
+    let Some(first) = records.first() else {
+        return out;
+    };
+    let header: Vec<String> = first.fields.iter().map(|(name, _)| name.clone()).collect();
+    let rows: Vec<Vec<(String, bool)>> = records
+        .iter()
+        .map(|record| {
+            record
+                .fields
+                .iter()
+                .map(|(_, value)| (value.to_string(), value.is_number()))
+                .collect()
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = header.iter().map(|name| name.chars().count()).collect();
+    for row in &rows {
+        for (width, (cell, _)) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut push_row = |cells: Vec<(String, bool)>| {
+        let last = cells.len().saturating_sub(1);
+        let line: Vec<String> = cells
+            .into_iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, ((cell, right), width))| match (right, i == last) {
+                (true, _) => format!("{:>width$}", cell),
+                (false, true) => cell,
+                (false, false) => format!("{:<width$}", cell),
+            })
+            .collect();
+        out.push_str(line.join("  ").trim_end());
+        out.push('\n');
+    };
+    let numeric: Vec<bool> = first
+        .fields
+        .iter()
+        .map(|(_, value)| value.is_number())
+        .collect();
+    push_row(header.into_iter().zip(numeric).collect());
+    for row in rows {
+        push_row(row);
+    }
+    out
+}
+
+/// Writes a YAML scalar, quoting strings that would otherwise be read as
+/// another type or break the document structure.
+fn yaml_scalar(value: &Value) -> String {
+    let s = match value {
+        Value::Null => return "null".to_string(),
+        Value::Integer(n) => return n.to_string(),
+        Value::Float(n) if n.is_finite() => return n.to_string(),
+        Value::Float(_) => return "null".to_string(),
+        Value::String(s) => s,
+    };
+    let needs_quotes = s.is_empty()
+        || s.trim() != s
+        || s.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.ends_with(':')
+        || s.chars().any(char::is_control)
+        || s.parse::<f64>().is_ok()
+        || matches!(
+            s.to_ascii_lowercase().as_str(),
+            "null" | "~" | "true" | "false" | "yes" | "no" | "on" | "off"
+        );
+    if needs_quotes {
+        // JSON の文字列エスケープは YAML のダブルクォート文字列としても有効
+        let mut out = String::new();
+        JsonValueWriter::new(&mut out).string(s);
+        out
+    } else {
+        s.clone()
+    }
+}
+
+/// Serializes the records as YAML sequence items, one mapping per record.
+/// An empty slice produces an empty string so that batches can be appended.
+pub fn to_yaml_items(records: &[Record]) -> String {
+    let mut out = String::new();
+    for record in records {
+        for (i, (name, value)) in record.fields.iter().enumerate() {
+            out.push_str(if i == 0 { "- " } else { "  " });
+            out.push_str(&yaml_scalar(&Value::String(name.clone())));
+            out.push_str(": ");
+            out.push_str(&yaml_scalar(value));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Prints a listing in one of the structured formats: `records` for JSON, NDJSON, CSV and YAML,
+/// `table_rows` for `table`. Commands print their own `text` output; it gets the table here.
+pub fn print_records(format: OutputFormat, records: &[Record], table_rows: &[Record]) {
+    match format {
+        OutputFormat::Json => outln!("{}", to_json_array(records)),
+        OutputFormat::Ndjson => {
+            for record in records {
+                outln!("{}", record.to_json());
+            }
+        }
+        OutputFormat::Csv => out!("{}", to_csv(records, true)),
+        OutputFormat::Yaml => match records.is_empty() {
+            true => outln!("[]"),
+            false => out!("{}", to_yaml_items(records)),
+        },
+        OutputFormat::Table | OutputFormat::Text => out!("{}", to_table(table_rows)),
+    }
+}
+
+#[derive(Debug, Clone)]
+enum TemplatePart {
+    Literal(String),
+    Field(String),
+}
+
+/// A user-defined line format such as `{name}\t{storedBytes}`.
+///
+/// `{field}` is replaced by the value of a record field, `{{` and `}}` are literal braces,
+/// and `\t`, `\n` and `\\` are unescaped so the format can be written without `$'...'` quoting.
+#[derive(Debug, Clone)]
+pub struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+impl Template {
+    /// Parses a template whose placeholders must be one of `fields`.
+    pub fn parse(template: &str, fields: &[&str]) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("unclosed placeholder `{{{}`", name)),
+                        }
+                    }
+                    if !fields.contains(&name.as_str()) {
+                        return Err(format!(
+                            "unknown field `{}`, expected one of: {}",
+                            name,
+                            fields.join(", ")
+                        ));
+                    }
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(TemplatePart::Field(name));
+                }
+                '}' => return Err("unmatched `}`, use `}}` for a literal brace".to_string()),
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('\\') => literal.push('\\'),
+                    Some(other) => {
+                        literal.push('\\');
+                        literal.push(other);
+                    }
+                    None => literal.push('\\'),
+                },
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Fills in the placeholders from the record. Missing and null fields are empty.
+    pub fn render(&self, record: &Record) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(literal) => out.push_str(literal),
+                TemplatePart::Field(name) => {
+                    if let Some(value) = record.get(name) {
+                        out.push_str(&value.to_string());
+                    }
+                }
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,67 @@
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::os::fd::AsRawFd;
+use std::process::{Child, Command, Stdio};
+
+/// Used when `PAGER` is not set: quit if the output fits on one screen,
+/// pass colors through, and leave the output on the screen afterwards.
+const DEFAULT_PAGER: &str = "less -FRX";
+
+/// Sends everything printed to stdout through a pager until dropped.
+pub struct Pager {
+    child: Child,
+}
+
+impl Pager {
+    /// Starts `$PAGER`, or `less -FRX`, if stdout is a terminal.
+    /// Returns `None` and prints directly when there is no terminal or the pager cannot be started.
+    pub fn start() -> Option<Pager> {
+        if !io::stdout().is_terminal() {
+            return None;
+        }
+        let command = env::var("PAGER")
+            .ok()
+            .filter(|pager| !pager.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+        let mut child = match Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .stdin(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                eprintln!("Warning: Failed to start the pager `{}`: {}", command, e);
+                return None;
+            }
+        };
+        let stdin = child.stdin.take()?;
+        // コマンドの出力が書き込まれる標準出力をページャーへのパイプに差し替える。
+        // ページャーを途中で閉じたら、パニックせずに静かに終了するよう SIGPIPE を既定に戻す。
+        unsafe {
+            if libc::dup2(stdin.as_raw_fd(), libc::STDOUT_FILENO) == -1 {
+                eprintln!(
+                    "Warning: Failed to start the pager: {}",
+                    io::Error::last_os_error()
+                );
+                drop(stdin);
+                let _ = child.wait();
+                return None;
+            }
+            libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+        }
+        Some(Pager { child })
+    }
+}
+
+/// Closes the pipe so that the pager sees the end of the output, then waits for the user to quit it.
+impl Drop for Pager {
+    fn drop(&mut self) {
+        let _ = crate::output::flush_output();
+        let _ = io::stdout().flush();
+        unsafe {
+            libc::close(libc::STDOUT_FILENO);
+        }
+        let _ = self.child.wait();
+    }
+}
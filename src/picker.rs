@@ -0,0 +1,260 @@
+//! A fuzzy picker for the log group or stream that a command needs when the command line names
+//! none. Typed letters narrow the names down to those that contain them in order, as in skim or
+//! fzf, with the closest matches first: letters in a row and at the start of a path segment count
+//! more. A query with an upper-case letter matches case-sensitively.
+
+use std::io::{self, IsTerminal, Write};
+
+use aws_sdk_cloudwatchlogs::Client;
+
+use crate::progress::Progress;
+use crate::streams::{for_each_stream, StreamOrder, StreamRequestOpt};
+use crate::terminal::{self, Key, Screen, BOLD, RESET, REVERSE};
+
+/// The most log groups listed to pick from.
+const MAX_GROUPS: usize = 10_000;
+
+/// The most log streams listed to pick from, the most recently active ones.
+const MAX_STREAMS: usize = 1000;
+
+/// Whether a picker can be shown: it reads keys from stdin and draws on stderr.
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Lists the log groups and lets one be picked. `None` when none was picked.
+pub async fn pick_group(client: &Client) -> Result<Option<String>, aws_sdk_cloudwatchlogs::Error> {
+    let progress = Progress::new("groups");
+    let mut pages = client.describe_log_groups().into_paginator().send();
+    let mut groups: Vec<String> = Vec::new();
+    while let Some(page) = pages.next().await {
+        let page = page?;
+        let before = groups.len();
+        groups.extend(
+            page.log_groups()
+                .iter()
+                .filter_map(|group| group.log_group_name())
+                .map(str::to_string),
+        );
+        progress.page(groups.len() - before);
+        if groups.len() >= MAX_GROUPS {
+            groups.truncate(MAX_GROUPS);
+            break;
+        }
+    }
+    progress.clear();
+    Ok(pick_blocking("log group", groups))
+}
+
+/// Lists the streams of `group`, the most recently active first, and lets one be picked.
+pub async fn pick_stream(
+    client: &Client,
+    group: &str,
+) -> Result<Option<String>, aws_sdk_cloudwatchlogs::Error> {
+    let request = StreamRequestOpt {
+        order_by: StreamOrder::LastEvent,
+        descending: true,
+        limit: Some(MAX_STREAMS),
+        ..Default::default()
+    };
+    let progress = Progress::new("streams");
+    let mut streams = Vec::new();
+    for_each_stream(client, group, &request, &progress, |stream| {
+        streams.extend(stream.log_stream_name().map(str::to_string))
+    })
+    .await?;
+    progress.clear();
+    Ok(pick_blocking("log stream", streams))
+}
+
+fn pick_blocking(what: &str, names: Vec<String>) -> Option<String> {
+    if names.is_empty() {
+        eprintln!("No {}s to pick from.", what);
+        return None;
+    }
+    tokio::task::block_in_place(|| pick(what, &names)).unwrap_or_else(|e| {
+        eprintln!("Error: Failed to read the terminal: {}", e);
+        None
+    })
+}
+
+/// Shows `names` full-screen and returns the one picked with Enter, or `None` after Escape.
+pub fn pick(prompt: &str, names: &[String]) -> io::Result<Option<String>> {
+    let _screen = Screen::enter()?;
+    let mut query = String::new();
+    let mut selected = 0;
+    let mut top = 0;
+    loop {
+        let matches = fuzzy_filter(&query, names);
+        let height = terminal::size().1.saturating_sub(1).max(1);
+        selected = selected.min(matches.len().saturating_sub(1));
+        // 選択行が見える位置までスクロールする
+        top = top.min(selected).max((selected + 1).saturating_sub(height));
+        draw(prompt, &query, names, &matches, selected, top);
+
+        let Some(key) = terminal::read_key()? else {
+            return Ok(None);
+        };
+        match key {
+            Key::Enter => return Ok(matches.get(selected).map(|m| names[m.index].clone())),
+            Key::Escape | Key::Ctrl('c') | Key::Ctrl('d') | Key::Ctrl('g') => return Ok(None),
+            Key::Up | Key::Ctrl('p') => selected = selected.saturating_sub(1),
+            Key::Down | Key::Ctrl('n') => selected += 1,
+            Key::PageUp => selected = selected.saturating_sub(height),
+            Key::PageDown => selected += height,
+            Key::Backspace => {
+                query.pop();
+            }
+            Key::Ctrl('u') => query.clear(),
+            Key::Ctrl('w') => {
+                let kept = query.trim_end().rfind([' ', '/']).map_or(0, |i| i + 1);
+                query.truncate(kept);
+            }
+            Key::Char(c) => {
+                query.push(c);
+                selected = 0;
+                top = 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A name that matches the query, with where its matched characters are.
+struct Match {
+    index: usize,
+    score: i64,
+    positions: Vec<usize>,
+}
+
+/// The names that match `query`, the best first, or all of them in order for an empty query.
+fn fuzzy_filter(query: &str, names: &[String]) -> Vec<Match> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    let case_sensitive = query.iter().any(|c| c.is_uppercase());
+    let mut matches: Vec<Match> = names
+        .iter()
+        .enumerate()
+        .filter_map(|(index, name)| {
+            let (score, positions) = fuzzy_match(&query, name, case_sensitive)?;
+            Some(Match {
+                index,
+                score,
+                positions,
+            })
+        })
+        .collect();
+    // 空の問い合わせでは一覧の順のまま。同点なら短い名前を先にする
+    if !query.is_empty() {
+        matches.sort_by_key(|m| (-m.score, names[m.index].len(), m.index));
+    }
+    matches
+}
+
+/// The score of `name` if it contains the characters of `query` in order, and their positions.
+/// Each place where the first character occurs is tried as the start, keeping the best.
+fn fuzzy_match(query: &[char], name: &str, case_sensitive: bool) -> Option<(i64, Vec<usize>)> {
+    let chars: Vec<char> = match case_sensitive {
+        true => name.chars().collect(),
+        // 位置がずれないよう、1 文字を 1 文字の小文字にする
+        false => name
+            .chars()
+            .map(|c| c.to_lowercase().next().unwrap_or(c))
+            .collect(),
+    };
+    let Some(&first) = query.first() else {
+        return Some((0, Vec::new()));
+    };
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    for start in (0..chars.len()).filter(|&i| chars[i] == first) {
+        let Some(found) = match_from(query, &chars, start) else {
+            // ここで見つからなければ後ろから始めても見つからない
+            break;
+        };
+        if best.as_ref().is_none_or(|(score, _)| found.0 > *score) {
+            best = Some(found);
+        }
+    }
+    best
+}
+
+fn match_from(query: &[char], chars: &[char], start: usize) -> Option<(i64, Vec<usize>)> {
+    let mut positions = vec![start];
+    let mut score = boundary_bonus(chars, start);
+    let mut from = start + 1;
+    for &c in &query[1..] {
+        let i = (from..chars.len()).find(|&i| chars[i] == c)?;
+        score += match i == from {
+            true => 5,
+            false => boundary_bonus(chars, i) - (i - from).min(10) as i64,
+        };
+        positions.push(i);
+        from = i + 1;
+    }
+    Some((score, positions))
+}
+
+/// Matching the first letter of a word, such as the `a` of `/aws` or of `payments-api`, counts more.
+fn boundary_bonus(chars: &[char], i: usize) -> i64 {
+    match i.checked_sub(1).map(|before| chars[before]) {
+        None | Some('/' | '-' | '_' | '.' | ' ' | ':' | '[' | ']') => 8,
+        Some(_) => 1,
+    }
+}
+
+fn draw(
+    prompt: &str,
+    query: &str,
+    names: &[String],
+    matches: &[Match],
+    selected: usize,
+    top: usize,
+) {
+    let (cols, rows) = terminal::size();
+    let height = rows.saturating_sub(1).max(1);
+    let mut frame = String::from("\x1b[H");
+    for (row, m) in matches.iter().enumerate().skip(top).take(height) {
+        let mut line = String::from(if row == selected { "> " } else { "  " });
+        for (i, c) in names[m.index]
+            .chars()
+            .take(cols.saturating_sub(2))
+            .enumerate()
+        {
+            match m.positions.contains(&i) {
+                true => line.push_str(&format!("{}{}{}", BOLD, c, unbold(row == selected))),
+                false => line.push(c),
+            }
+        }
+        match row == selected {
+            true => frame.push_str(&format!("{}{}\x1b[K{}\r\n", REVERSE, line, RESET)),
+            false => frame.push_str(&format!("{}\x1b[K\r\n", line)),
+        }
+    }
+    frame.push_str("\x1b[J");
+    // 入力行は一番下に置き、カーソルをその末尾に出す
+    let count = format!("  {}/{}", matches.len(), names.len());
+    let line: String = format!("{}> {}", prompt, query)
+        .chars()
+        .take(cols.saturating_sub(count.len()))
+        .collect();
+    frame.push_str(&format!(
+        "\x1b[{};1H{}{}{}{}\x1b[K\x1b[{};{}H\x1b[?25h",
+        height + 1,
+        line,
+        BOLD,
+        count,
+        RESET,
+        height + 1,
+        line.chars().count() + 1
+    ));
+    let mut stderr = io::stderr().lock();
+    let _ = stderr.write_all(frame.as_bytes());
+    let _ = stderr.flush();
+}
+
+/// Ends a highlighted letter, keeping the row reversed if it is the selected one.
+fn unbold(selected: bool) -> String {
+    match selected {
+        true => format!("{}{}", RESET, REVERSE),
+        false => RESET.to_string(),
+    }
+}
@@ -0,0 +1,101 @@
+use std::cell::Cell;
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use crate::output;
+
+const SPINNER: &[char] = &['|', '/', '-', '\\'];
+
+/// How often the progress line is redrawn at most.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A one-line progress report on stderr for listings that take many pages,
+/// such as `| 12 pages, 480 streams, 0 errors`.
+///
+/// It is only drawn when both stdout and stderr are terminals, so piped output stays clean,
+/// and never with `--quiet`.
+/// Counters use `Cell` so that listings running concurrently in the same task can share one.
+#[derive(Debug)]
+pub struct Progress {
+    enabled: bool,
+    noun: &'static str,
+    pages: Cell<usize>,
+    items: Cell<usize>,
+    errors: Cell<usize>,
+    frame: Cell<usize>,
+    drawn_at: Cell<Option<Instant>>,
+}
+
+impl Progress {
+    /// Reports items named `noun`, e.g. `streams`.
+    pub fn new(noun: &'static str) -> Self {
+        Progress {
+            enabled: io::stdout().is_terminal()
+                && io::stderr().is_terminal()
+                && !output::is_quiet(),
+            noun,
+            pages: Cell::new(0),
+            items: Cell::new(0),
+            errors: Cell::new(0),
+            frame: Cell::new(0),
+            drawn_at: Cell::new(None),
+        }
+    }
+
+    /// Reports nothing, for callers of the library that draw their own progress.
+    pub fn hidden() -> Self {
+        let mut progress = Progress::new("");
+        progress.enabled = false;
+        progress
+    }
+
+    /// Counts a fetched page with `items` accepted items.
+    pub fn page(&self, items: usize) {
+        self.pages.set(self.pages.get() + 1);
+        self.items.set(self.items.get() + items);
+        self.draw();
+    }
+
+    /// Counts a failed API call.
+    pub fn error(&self) {
+        self.errors.set(self.errors.get() + 1);
+        self.draw();
+    }
+
+    /// Erases the progress line so that output can be printed in its place.
+    /// It is drawn again on the next update.
+    pub fn clear(&self) {
+        if self.enabled && self.drawn_at.take().is_some() {
+            eprint!("\r\x1b[2K");
+        }
+    }
+
+    fn draw(&self) {
+        if !self.enabled {
+            return;
+        }
+        if let Some(drawn_at) = self.drawn_at.get() {
+            if drawn_at.elapsed() < REDRAW_INTERVAL {
+                return;
+            }
+        }
+        let frame = self.frame.get();
+        self.frame.set(frame + 1);
+        eprint!(
+            "\r\x1b[2K{} {} pages, {} {}, {} errors",
+            SPINNER[frame % SPINNER.len()],
+            self.pages.get(),
+            self.items.get(),
+            self.noun,
+            self.errors.get()
+        );
+        let _ = io::stderr().flush();
+        self.drawn_at.set(Some(Instant::now()));
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
@@ -0,0 +1,223 @@
+use aws_sdk_cloudwatchlogs::{
+    types::{QueryInfo, QueryStatus},
+    Client,
+};
+use clap::{Args, Subcommand, ValueEnum};
+
+use crate::error::Error;
+use crate::outln;
+use crate::output::{self, format_timestamp, OutputFormat, Record};
+use crate::report;
+
+/// The status of a Logs Insights query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Status {
+    Scheduled,
+    Running,
+    Complete,
+    Failed,
+    Cancelled,
+    Timeout,
+}
+
+impl From<Status> for QueryStatus {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Scheduled => QueryStatus::Scheduled,
+            Status::Running => QueryStatus::Running,
+            Status::Complete => QueryStatus::Complete,
+            Status::Failed => QueryStatus::Failed,
+            Status::Cancelled => QueryStatus::Cancelled,
+            Status::Timeout => QueryStatus::Timeout,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct QueriesOpt {
+    #[command(subcommand)]
+    pub command: QueriesCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum QueriesCommand {
+    /// Lists the Logs Insights queries that are scheduled or running, or that ran recently.
+    List(ListQueriesOpt),
+
+    /// Stops running Logs Insights queries.
+    Stop(StopQueriesOpt),
+}
+
+#[derive(Debug, Args)]
+pub struct ListQueriesOpt {
+    /// Only lists the queries of this log group.
+    #[arg(short, long)]
+    pub group: Option<String>,
+
+    /// Only lists queries with this status. Can be repeated.
+    /// Defaults to the scheduled and running ones, which count toward the concurrency limit.
+    #[arg(long, value_enum, conflicts_with = "all")]
+    pub status: Vec<Status>,
+
+    /// Lists the queries of every status from the last 30 days.
+    #[arg(long)]
+    pub all: bool,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct StopQueriesOpt {
+    /// The IDs of the queries to stop, as printed by `queries list`.
+    #[arg(required = true, value_name = "QUERY-ID")]
+    pub query_ids: Vec<String>,
+}
+
+impl QueriesOpt {
+    /// The log group that the subcommand works on, if it names one.
+    pub fn group(&self) -> Option<&str> {
+        match &self.command {
+            QueriesCommand::List(opt) => opt.group.as_deref(),
+            QueriesCommand::Stop(_) => None,
+        }
+    }
+}
+
+/// Describes a query for structured output.
+fn query_record(query: &QueryInfo) -> Record {
+    Record::new()
+        .field("queryId", query.query_id())
+        .field("status", query.status().map(|s| s.as_str()))
+        .field("createTime", query.create_time())
+        .field("logGroupName", query.log_group_name())
+        .field("queryString", query.query_string())
+}
+
+/// The query string on one line, for text and table output.
+fn one_line(query: &QueryInfo) -> String {
+    query
+        .query_string()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Describes a query as a row of the `table` output.
+fn query_table_row(query: &QueryInfo) -> Record {
+    Record::new()
+        .field("QUERY ID", query.query_id())
+        .field("STATUS", query.status().map(|s| s.as_str()))
+        .field(
+            "CREATED",
+            query.create_time().map(format_timestamp).as_deref(),
+        )
+        .field("GROUP", query.log_group_name())
+        .field("QUERY", one_line(query).as_str())
+}
+
+/// Lists the queries with one status, or of every status, with DescribeQueries.
+async fn describe_queries(
+    client: &Client,
+    group: Option<&str>,
+    status: Option<QueryStatus>,
+) -> Result<Vec<QueryInfo>, aws_sdk_cloudwatchlogs::Error> {
+    let mut queries = Vec::new();
+    let mut next_token: Option<String> = None;
+    loop {
+        let page = client
+            .describe_queries()
+            .set_log_group_name(group.map(str::to_string))
+            .set_status(status.clone())
+            .set_next_token(next_token)
+            .send()
+            .await?;
+        queries.extend(page.queries.unwrap_or_default());
+        next_token = page.next_token;
+        if next_token.is_none() {
+            return Ok(queries);
+        }
+    }
+}
+
+/// Lists the queries of the Region, newest first.
+async fn list_queries(client: &Client, opt: ListQueriesOpt, verbose: bool) -> Result<(), Error> {
+    let ListQueriesOpt {
+        group,
+        status,
+        all,
+        output,
+    } = opt;
+    // DescribeQueries は一度に一つの状態でしか絞り込めない
+    let statuses: Vec<Option<QueryStatus>> = match (all, status.is_empty()) {
+        (true, _) => vec![None],
+        (false, true) => vec![Some(QueryStatus::Scheduled), Some(QueryStatus::Running)],
+        (false, false) => status.into_iter().map(|s| Some(s.into())).collect(),
+    };
+
+    if verbose {
+        if let Some(group) = &group {
+            eprintln!("Log group name:                {}", group);
+        }
+        let names: Vec<&str> = statuses.iter().flatten().map(|s| s.as_str()).collect();
+        if !names.is_empty() {
+            eprintln!("Status:                        {}", names.join(", "));
+        }
+        eprintln!();
+    }
+
+    let mut queries = Vec::new();
+    for status in statuses {
+        queries.extend(describe_queries(client, group.as_deref(), status).await?);
+    }
+    queries.sort_by_key(|query| std::cmp::Reverse(query.create_time()));
+
+    match output {
+        OutputFormat::Text => {
+            for query in &queries {
+                outln!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    query.query_id().unwrap_or_default(),
+                    query.status().map_or("-", |s| s.as_str()),
+                    query
+                        .create_time()
+                        .map_or_else(|| "-".to_string(), format_timestamp),
+                    query.log_group_name().unwrap_or("-"),
+                    one_line(query)
+                );
+            }
+        }
+        format => output::print_records(
+            format,
+            &queries.iter().map(query_record).collect::<Vec<_>>(),
+            &queries.iter().map(query_table_row).collect::<Vec<_>>(),
+        ),
+    }
+    Ok(())
+}
+
+/// Stops each query with StopQuery. Queries that fail to stop are reported and skipped.
+async fn stop_queries(client: &Client, opt: StopQueriesOpt) -> Result<(), Error> {
+    let StopQueriesOpt { query_ids } = opt;
+    for query_id in query_ids {
+        match client.stop_query().query_id(&query_id).send().await {
+            Ok(output) if output.success() => outln!("Stopped {}", query_id),
+            Ok(_) => eprintln!(
+                "Warning: {} was not stopped; it may have finished already",
+                query_id
+            ),
+            Err(e) => report::warn(&format!("Failed to stop {}", query_id), &e),
+        }
+    }
+    Ok(())
+}
+
+/// Runs a queries subcommand.
+pub async fn run(client: &Client, opt: QueriesOpt, verbose: bool) -> Result<(), Error> {
+    match opt.command {
+        QueriesCommand::List(opt) => list_queries(client, opt, verbose).await,
+        QueriesCommand::Stop(opt) => stop_queries(client, opt).await,
+    }
+}
@@ -0,0 +1,454 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use aws_sdk_cloudwatchlogs::{
+    types::{QueryStatistics, QueryStatus, ResultField},
+    Client,
+};
+use clap::Args;
+
+use crate::error::Error;
+use crate::groups::{group_arn, GroupOpt};
+use crate::outln;
+use crate::output::{self, format_bytes, parse_size, OutputFormat, Record};
+use crate::progress::Progress;
+use crate::query_templates::QueryTemplate;
+use crate::report;
+use crate::targets::Target;
+use crate::time_range::{now_millis, parse_duration, parse_timestamp};
+
+/// The price of scanning one GB with Logs Insights in US East (N. Virginia), in US dollars.
+/// Other Regions can cost more.
+const PRICE_PER_GB_SCANNED: f64 = 0.005;
+
+const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+/// How many log groups one Logs Insights query can span.
+const MAX_QUERY_GROUPS: usize = 50;
+
+#[derive(Debug, Args)]
+pub struct QueryOpt {
+    #[command(flatten)]
+    pub groups: GroupOpt,
+
+    /// The Logs Insights query, e.g. `fields @timestamp, @message | limit 20`.
+    #[arg(short = 'Q', long, required_unless_present = "template")]
+    pub query_string: Option<String>,
+
+    /// Runs a built-in query instead of `--query-string`.
+    #[arg(long, value_enum, conflicts_with = "query_string")]
+    pub template: Option<QueryTemplate>,
+
+    /// Sets a parameter of the template, as `NAME=VALUE`, e.g. `limit=100`. Can be repeated.
+    #[arg(long = "param", value_name = "NAME=VALUE", requires = "template", conflicts_with = "query_string", value_parser = parse_param)]
+    pub params: Vec<(String, String)>,
+
+    /// The beginning of the time range, e.g. `2h`, `now-10m` or `2024-05-01 13:00` (UTC).
+    #[arg(long, visible_alias = "since", value_parser = parse_timestamp)]
+    pub start: i64,
+
+    /// The end of the time range, in the same formats as `--start`. Defaults to now.
+    #[arg(long, visible_alias = "until", value_parser = parse_timestamp)]
+    pub end: Option<i64>,
+
+    /// The output format. Structured formats have one column per result field.
+    /// Defaults to the format that the extension of `--output-file` names, such as `results.csv`.
+    #[arg(short, long, value_enum)]
+    pub output: Option<OutputFormat>,
+
+    /// Keeps the `@ptr` field, which GetLogRecord takes to fetch the full event.
+    #[arg(long)]
+    pub include_ptr: bool,
+
+    /// Does not start the query when the estimated scan for the time range exceeds this,
+    /// e.g. `10GB`, and stops it once it has scanned more.
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_scan_bytes: Option<u64>,
+
+    /// How long to wait between GetQueryResults polls, e.g. `500ms` or `5s`.
+    #[arg(long, value_name = "DURATION", default_value = "1s", value_parser = parse_poll_interval)]
+    pub poll_interval: Duration,
+
+    /// Stops the query with StopQuery when it has not finished after this long, e.g. `5m`,
+    /// and prints the partial results, exiting with code 4.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub wait_timeout: Option<Duration>,
+}
+
+/// Parses `--poll-interval`, which must not be zero.
+fn parse_poll_interval(s: &str) -> Result<Duration, String> {
+    match parse_duration(s)? {
+        Duration::ZERO => Err("the poll interval must be longer than zero".to_string()),
+        interval => Ok(interval),
+    }
+}
+
+/// Parses a `NAME=VALUE` template parameter.
+fn parse_param(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some(("", _)) => Err(format!("parameter `{}` has no name", s)),
+        Some((name, value)) => Ok((name.to_string(), value.to_string())),
+        None => Err(format!("parameter `{}` is not of the form NAME=VALUE", s)),
+    }
+}
+
+impl QueryOpt {
+    /// Checks that the template's parameters exist.
+    pub fn validate(&self) -> Result<(), String> {
+        self.groups.ecs.unsupported()?;
+        self.query().map(|_| ())
+    }
+
+    /// The query to run: `--query-string`, or the filled-in template.
+    fn query(&self) -> Result<String, String> {
+        match (&self.query_string, self.template) {
+            (Some(query_string), _) => Ok(query_string.clone()),
+            (None, Some(template)) => template.render(&self.params),
+            (None, None) => unreachable!("clap requires --query-string or --template"),
+        }
+    }
+}
+
+/// The result of a finished query.
+struct QueryResults {
+    status: QueryStatus,
+    rows: Vec<Vec<ResultField>>,
+    statistics: Option<QueryStatistics>,
+}
+
+/// The query being waited for, to stop when the command is interrupted.
+static RUNNING: Mutex<Option<(Client, String)>> = Mutex::new(None);
+
+/// Held while StartQuery is in flight, until the started query is in [`RUNNING`].
+static STARTING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Stops the query that is running, if any, so that an interrupted command does not leave it
+/// scanning. Called when Ctrl-C or SIGTERM stops the command.
+/// A query that is still being started is waited for, and then stopped.
+pub async fn stop_running() {
+    drop(STARTING.lock().await);
+    let Some((client, query_id)) = RUNNING.lock().unwrap().take() else {
+        return;
+    };
+    match client.stop_query().query_id(&query_id).send().await {
+        Ok(_) => eprintln!("Stopped query {}", query_id),
+        Err(e) => report::error(Some(&format!("Failed to stop query {}", query_id)), &e),
+    }
+}
+
+/// Polls GetQueryResults every `poll_interval` until the query leaves the Scheduled/Running states.
+/// Stops the query with StopQuery once it has scanned more than `max_scan_bytes` or run longer
+/// than `wait_timeout`, so that an abandoned query does not keep scanning.
+async fn wait_for_results(
+    client: &Client,
+    query_id: &str,
+    max_scan_bytes: Option<u64>,
+    poll_interval: Duration,
+    wait_timeout: Option<Duration>,
+) -> Result<QueryResults, aws_sdk_cloudwatchlogs::Error> {
+    let started = Instant::now();
+    loop {
+        let output = client.get_query_results().query_id(query_id).send().await?;
+        let status = output
+            .status()
+            .cloned()
+            .unwrap_or(QueryStatus::UnknownValue);
+        match status {
+            QueryStatus::Scheduled | QueryStatus::Running => {
+                let scanned = output.statistics().map_or(0.0, |s| s.bytes_scanned());
+                if let Some(max_scan_bytes) = max_scan_bytes.filter(|max| scanned > *max as f64) {
+                    report::incomplete(&format!(
+                        "Stopping the query after it scanned {}, more than {}",
+                        format_bytes(scanned as i64),
+                        format_bytes(max_scan_bytes as i64)
+                    ));
+                    client.stop_query().query_id(query_id).send().await?;
+                    // 停止までに返った結果は部分的なものとして出力する
+                    return Ok(QueryResults {
+                        status: QueryStatus::Cancelled,
+                        rows: output.results.unwrap_or_default(),
+                        statistics: output.statistics,
+                    });
+                }
+                if let Some(wait_timeout) = wait_timeout.filter(|t| started.elapsed() >= *t) {
+                    report::incomplete(&format!(
+                        "Stopping the query after waiting {:?} for it to finish",
+                        wait_timeout
+                    ));
+                    client.stop_query().query_id(query_id).send().await?;
+                    return Ok(QueryResults {
+                        status: QueryStatus::Cancelled,
+                        rows: output.results.unwrap_or_default(),
+                        statistics: output.statistics,
+                    });
+                }
+                let sleep = match wait_timeout {
+                    Some(wait_timeout) => {
+                        poll_interval.min(wait_timeout.saturating_sub(started.elapsed()))
+                    }
+                    None => poll_interval,
+                };
+                tokio::time::sleep(sleep).await;
+            }
+            _ => {
+                return Ok(QueryResults {
+                    status,
+                    rows: output.results.unwrap_or_default(),
+                    statistics: output.statistics,
+                })
+            }
+        }
+    }
+}
+
+/// The estimated cost of scanning `bytes` with Logs Insights, in US dollars.
+fn scan_cost(bytes: f64) -> f64 {
+    bytes / 1e9 * PRICE_PER_GB_SCANNED
+}
+
+/// Estimates how many bytes a query of the time range scans, from the stored size of each group
+/// and the share of its kept events that fall into the range, assuming an even ingestion rate.
+/// Returns `None` when a group given by ARN or one that does not exist cannot be sized.
+async fn estimate_scan_bytes(
+    client: &Client,
+    groups: &[String],
+    start_millis: i64,
+    end_millis: i64,
+) -> Result<Option<f64>, aws_sdk_cloudwatchlogs::Error> {
+    let now = now_millis();
+    let mut total = 0.0;
+    for group in groups {
+        if group_arn(group).is_some() {
+            return Ok(None);
+        }
+        let found = client
+            .describe_log_groups()
+            .log_group_name_prefix(group)
+            .send()
+            .await?
+            .log_groups
+            .unwrap_or_default()
+            .into_iter()
+            .find(|log_group| log_group.log_group_name() == Some(group));
+        let Some(log_group) = found else {
+            return Ok(None);
+        };
+        // 保持期間より古いイベントは削除済みなので、残っている期間で按分する
+        let kept_since = log_group.creation_time().unwrap_or(now).max(
+            log_group
+                .retention_in_days()
+                .map_or(i64::MIN, |days| now - i64::from(days) * MILLIS_PER_DAY),
+        );
+        let kept = (now - kept_since).max(1) as f64;
+        let overlap = (end_millis.min(now) - start_millis.max(kept_since)).max(0) as f64;
+        total += log_group.stored_bytes().unwrap_or_default() as f64 * (overlap / kept).min(1.0);
+    }
+    Ok(Some(total))
+}
+
+/// Describes the statistics of a finished query, with its estimated cost.
+fn format_statistics(statistics: &QueryStatistics) -> String {
+    format!(
+        "Scanned {} records ({}), matched {} records, estimated cost ${:.4}",
+        statistics.records_scanned() as i64,
+        format_bytes(statistics.bytes_scanned() as i64),
+        statistics.records_matched() as i64,
+        scan_cost(statistics.bytes_scanned())
+    )
+}
+
+/// The fields of a result row to print, leaving out `@ptr` unless asked.
+fn shown_fields(row: &[ResultField], include_ptr: bool) -> impl Iterator<Item = &ResultField> {
+    row.iter()
+        .filter(move |f| include_ptr || f.field() != Some("@ptr"))
+}
+
+/// Formats a result row as tab-separated `field=value` pairs.
+fn format_row(row: &[ResultField], include_ptr: bool) -> String {
+    shown_fields(row, include_ptr)
+        .map(|f| {
+            format!(
+                "{}={}",
+                f.field().unwrap_or_default(),
+                f.value().unwrap_or_default()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+/// Turns the result rows into records with the same columns: every field name in the order it
+/// first appears. Rows can lack fields, e.g. when a query uses `fields` on unparsed events.
+fn result_records(rows: &[Vec<ResultField>], include_ptr: bool) -> Vec<Record> {
+    let mut columns: Vec<&str> = Vec::new();
+    for row in rows {
+        for field in shown_fields(row, include_ptr).filter_map(|f| f.field()) {
+            if !columns.contains(&field) {
+                columns.push(field);
+            }
+        }
+    }
+    rows.iter()
+        .map(|row| {
+            columns.iter().fold(Record::new(), |record, column| {
+                let value = row
+                    .iter()
+                    .find(|f| f.field() == Some(column))
+                    .and_then(|f| f.value());
+                record.field(*column, value)
+            })
+        })
+        .collect()
+}
+
+/// Runs a Logs Insights query and prints the result rows.
+pub async fn run(target: &Target, opt: QueryOpt, verbose: bool) -> Result<(), Error> {
+    // テンプレートは validate で確認済み
+    let query_string = opt.query().expect("the template is validated");
+    let QueryOpt {
+        groups,
+        start,
+        end,
+        output,
+        include_ptr,
+        max_scan_bytes,
+        poll_interval,
+        wait_timeout,
+        ..
+    } = opt;
+    let client = &target.client;
+    let end = end.unwrap_or_else(now_millis);
+
+    if verbose {
+        groups.print_verbose();
+        eprintln!("Query string:                  {}", &query_string);
+        eprintln!(
+            "Time range:                    {} - {}",
+            start / 1000,
+            end / 1000
+        );
+        if let Some(max_scan_bytes) = max_scan_bytes {
+            eprintln!(
+                "Maximum scan:                  {}",
+                format_bytes(max_scan_bytes as i64)
+            );
+        }
+        eprintln!();
+    }
+
+    let group = groups.resolve(target, &Progress::new("groups")).await?;
+    groups.ensure_found(group.len())?;
+    if group.len() > MAX_QUERY_GROUPS {
+        return Err(Error::failed(format!(
+            "The query would span {} log groups, but Logs Insights queries at most {}",
+            group.len(),
+            MAX_QUERY_GROUPS
+        )));
+    }
+    if verbose && group.len() > 1 {
+        eprintln!("Log group names:               {}", group.join(", "));
+        eprintln!();
+    }
+
+    if let Some(max_scan_bytes) = max_scan_bytes {
+        match estimate_scan_bytes(client, &group, start, end).await? {
+            Some(estimate) if estimate > max_scan_bytes as f64 => {
+                return Err(Error::failed(format!(
+                    "The query would scan about {} (${:.4}), more than --max-scan-bytes {}",
+                    format_bytes(estimate as i64),
+                    scan_cost(estimate),
+                    format_bytes(max_scan_bytes as i64)
+                )));
+            }
+            Some(estimate) if verbose => {
+                eprintln!(
+                    "Estimated scan:                {} (${:.4})",
+                    format_bytes(estimate as i64),
+                    scan_cost(estimate)
+                );
+                eprintln!();
+            }
+            Some(_) => {}
+            None => eprintln!(
+                "Warning: Cannot estimate the scan of every log group; the query is stopped once it scans more than {}",
+                format_bytes(max_scan_bytes as i64)
+            ),
+        }
+    }
+
+    // ARN を含む場合は、名前と ARN のどちらも受け付ける logGroupIdentifiers で渡す
+    let identifiers: Option<Vec<String>> = group
+        .iter()
+        .any(|group| group_arn(group).is_some())
+        .then(|| {
+            group
+                .iter()
+                .map(|group| group_arn(group).unwrap_or(group).to_string())
+                .collect()
+        });
+    let start_query = client
+        .start_query()
+        .set_log_group_names(identifiers.is_none().then_some(group))
+        .set_log_group_identifiers(identifiers)
+        .query_string(query_string)
+        // StartQuery は秒単位
+        .start_time(start / 1000)
+        .end_time(end / 1000);
+    // 送信中に中断されても開始したクエリを止められるよう、別タスクで送って RUNNING に登録する
+    let starting = STARTING.lock().await;
+    let running = client.clone();
+    let query_id = tokio::spawn(async move {
+        let query_id = start_query.send().await?.query_id.unwrap_or_default();
+        *RUNNING.lock().unwrap() = Some((running, query_id.clone()));
+        drop(starting);
+        Ok::<_, aws_sdk_cloudwatchlogs::Error>(query_id)
+    })
+    .await
+    .expect("the StartQuery task panicked")?;
+
+    if verbose {
+        eprintln!("Query ID:                      {}", &query_id);
+        eprintln!();
+    }
+
+    let results = wait_for_results(
+        client,
+        &query_id,
+        max_scan_bytes,
+        poll_interval,
+        wait_timeout,
+    )
+    .await;
+    RUNNING.lock().unwrap().take();
+    let QueryResults {
+        status,
+        rows,
+        statistics,
+    } = results?;
+    if status != QueryStatus::Complete {
+        report::incomplete(&format!("Query finished with status {}", status.as_str()));
+    }
+
+    match output.unwrap_or_default() {
+        OutputFormat::Text => {
+            if !output::is_quiet() {
+                outln!("Found {} rows:", rows.len());
+            }
+            for row in rows.iter() {
+                outln!("{}", format_row(row, include_ptr));
+            }
+            if let Some(statistics) = statistics.as_ref().filter(|_| !output::is_quiet()) {
+                outln!("{}", format_statistics(statistics));
+            }
+        }
+        format => {
+            let records = result_records(&rows, include_ptr);
+            output::print_records(format, &records, &records);
+            // 構造化出力を壊さないよう、統計は詳細表示のときだけ端末に出す
+            if let Some(statistics) = statistics.as_ref().filter(|_| verbose) {
+                eprintln!("{}", format_statistics(statistics));
+            }
+        }
+    }
+    Ok(())
+}
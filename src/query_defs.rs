@@ -0,0 +1,268 @@
+use std::fs;
+
+use aws_sdk_cloudwatchlogs::{types::QueryDefinition, Client};
+use clap::{Args, Subcommand};
+
+use crate::confirm::ConfirmOpt;
+use crate::error::Error;
+use crate::outln;
+use crate::output::{self, format_timestamp, OutputFormat, Record};
+
+#[derive(Debug, Args)]
+pub struct QueryDefsOpt {
+    #[command(subcommand)]
+    pub command: QueryDefsCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum QueryDefsCommand {
+    /// Lists the saved Logs Insights queries.
+    List(ListQueryDefsOpt),
+
+    /// Saves a Logs Insights query, replacing the saved query with the same name.
+    Put(PutQueryDefOpt),
+
+    /// Deletes a saved Logs Insights query.
+    Delete(DeleteQueryDefOpt),
+}
+
+#[derive(Debug, Args)]
+pub struct ListQueryDefsOpt {
+    /// Only lists saved queries whose names start with this prefix, e.g. a folder such as `team/`.
+    #[arg(long)]
+    pub prefix: Option<String>,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+/// Reads a query string from a file, such as one kept in a repository.
+fn read_query_file(path: &str) -> Result<String, String> {
+    let query = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+    match query.trim().is_empty() {
+        true => Err(format!("{} is empty", path)),
+        false => Ok(query.trim_end().to_string()),
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct PutQueryDefOpt {
+    /// The name of the saved query. `/` separates folders, e.g. `team/errors`.
+    #[arg(long)]
+    pub name: String,
+
+    /// The Logs Insights query.
+    #[arg(short = 'Q', long, required_unless_present = "file")]
+    pub query_string: Option<String>,
+
+    /// A file to read the query from.
+    #[arg(long, value_name = "FILE", conflicts_with = "query_string", value_parser = read_query_file)]
+    pub file: Option<String>,
+
+    /// A log group that the query runs on by default. Can be repeated.
+    #[arg(short, long)]
+    pub group: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct DeleteQueryDefOpt {
+    /// The name of the saved query.
+    #[arg(long)]
+    pub name: String,
+
+    #[command(flatten)]
+    pub confirm: ConfirmOpt,
+}
+
+/// Describes a saved query for structured output.
+fn definition_record(definition: &QueryDefinition) -> Record {
+    Record::new()
+        .field("name", definition.name())
+        .field("queryDefinitionId", definition.query_definition_id())
+        .field("lastModified", definition.last_modified())
+        .field(
+            "logGroupNames",
+            definition.log_group_names().join(",").as_str(),
+        )
+        .field("queryString", definition.query_string())
+}
+
+/// Describes a saved query as a row of the `table` output, without its query string.
+fn definition_table_row(definition: &QueryDefinition) -> Record {
+    Record::new()
+        .field("NAME", definition.name())
+        .field("ID", definition.query_definition_id())
+        .field(
+            "MODIFIED",
+            definition.last_modified().map(format_timestamp).as_deref(),
+        )
+        .field("GROUPS", definition.log_group_names().join(",").as_str())
+}
+
+/// Lists the saved queries whose names start with `prefix` with DescribeQueryDefinitions.
+async fn describe_definitions(
+    client: &Client,
+    prefix: Option<&str>,
+) -> Result<Vec<QueryDefinition>, aws_sdk_cloudwatchlogs::Error> {
+    let mut definitions = Vec::new();
+    let mut next_token: Option<String> = None;
+    loop {
+        let page = client
+            .describe_query_definitions()
+            .set_query_definition_name_prefix(prefix.map(str::to_string))
+            .set_next_token(next_token)
+            .send()
+            .await?;
+        definitions.extend(page.query_definitions.unwrap_or_default());
+        next_token = page.next_token;
+        if next_token.is_none() {
+            return Ok(definitions);
+        }
+    }
+}
+
+/// The saved queries named exactly `name`. The service allows several to share a name.
+async fn find_by_name(
+    client: &Client,
+    name: &str,
+) -> Result<Vec<QueryDefinition>, aws_sdk_cloudwatchlogs::Error> {
+    let mut definitions = describe_definitions(client, Some(name)).await?;
+    definitions.retain(|definition| definition.name() == Some(name));
+    Ok(definitions)
+}
+
+/// Lists the saved queries, sorted by name.
+async fn list_definitions(client: &Client, opt: ListQueryDefsOpt) -> Result<(), Error> {
+    let ListQueryDefsOpt { prefix, output } = opt;
+    let mut definitions = describe_definitions(client, prefix.as_deref()).await?;
+    definitions.sort_by(|a, b| a.name().cmp(&b.name()));
+
+    match output {
+        OutputFormat::Text => {
+            for definition in &definitions {
+                outln!(
+                    "{}\t{}",
+                    definition.name().unwrap_or_default(),
+                    match definition.log_group_names() {
+                        [] => "-".to_string(),
+                        names => names.join(","),
+                    }
+                );
+                for line in definition.query_string().unwrap_or_default().lines() {
+                    outln!("    {}", line);
+                }
+            }
+        }
+        format => output::print_records(
+            format,
+            &definitions
+                .iter()
+                .map(definition_record)
+                .collect::<Vec<_>>(),
+            &definitions
+                .iter()
+                .map(definition_table_row)
+                .collect::<Vec<_>>(),
+        ),
+    }
+    Ok(())
+}
+
+/// Saves a query with PutQueryDefinition. A saved query with the same name is updated in place,
+/// so that putting the same file again does not create a copy.
+async fn put_definition(client: &Client, opt: PutQueryDefOpt, verbose: bool) -> Result<(), Error> {
+    let PutQueryDefOpt {
+        name,
+        query_string,
+        file,
+        group,
+    } = opt;
+    let query_string = query_string.or(file).unwrap_or_default();
+
+    let existing = find_by_name(client, &name).await?;
+    if existing.len() > 1 {
+        eprintln!(
+            "Warning: {} saved queries are named {}; only the first is updated",
+            existing.len(),
+            name
+        );
+    }
+    let id = existing
+        .first()
+        .and_then(|definition| definition.query_definition_id())
+        .map(str::to_string);
+
+    if verbose {
+        eprintln!("Query name:                    {}", &name);
+        if let Some(id) = &id {
+            eprintln!("Query definition ID:           {}", id);
+        }
+        if !group.is_empty() {
+            eprintln!("Log group names:               {}", group.join(", "));
+        }
+        eprintln!("Query string:                  {}", &query_string);
+        eprintln!();
+    }
+
+    let updating = id.is_some();
+    let id = client
+        .put_query_definition()
+        .name(&name)
+        .set_query_definition_id(id)
+        .query_string(query_string)
+        .set_log_group_names((!group.is_empty()).then_some(group))
+        .send()
+        .await?
+        .query_definition_id
+        .unwrap_or_default();
+    match updating {
+        true => outln!("Updated {} ({})", name, id),
+        false => outln!("Created {} ({})", name, id),
+    }
+    Ok(())
+}
+
+/// Deletes every saved query with the name with DeleteQueryDefinition.
+async fn delete_definition(
+    client: &Client,
+    opt: DeleteQueryDefOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let DeleteQueryDefOpt { name, confirm } = opt;
+
+    if verbose {
+        eprintln!("Query name:                    {}", &name);
+        eprintln!();
+    }
+
+    let ids: Vec<String> = find_by_name(client, &name)
+        .await?
+        .iter()
+        .filter_map(|definition| definition.query_definition_id())
+        .map(str::to_string)
+        .collect();
+    if ids.is_empty() {
+        eprintln!("Warning: No saved query is named {}", name);
+        return Ok(());
+    }
+    confirm.confirm(&format!("About to delete the saved query {}:", name), &ids)?;
+    for id in ids {
+        client
+            .delete_query_definition()
+            .query_definition_id(&id)
+            .send()
+            .await?;
+        outln!("Deleted {} ({})", name, id);
+    }
+    Ok(())
+}
+
+/// Runs a saved query subcommand.
+pub async fn run(client: &Client, opt: QueryDefsOpt, verbose: bool) -> Result<(), Error> {
+    match opt.command {
+        QueryDefsCommand::List(opt) => list_definitions(client, opt).await,
+        QueryDefsCommand::Put(opt) => put_definition(client, opt, verbose).await,
+        QueryDefsCommand::Delete(opt) => delete_definition(client, opt, verbose).await,
+    }
+}
@@ -0,0 +1,89 @@
+//! Built-in Logs Insights queries for common investigations, selected with `query --template`.
+//! Templates contain `{{name}}` placeholders that are filled in from `--param name=value`
+//! or from the template's defaults.
+
+use clap::ValueEnum;
+
+/// A built-in Logs Insights query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum QueryTemplate {
+    /// Lambda invocations that logged an error, an exception or a timeout. Params: limit.
+    LambdaErrors,
+    /// API Gateway access log entries with a 5xx status. Params: min_status, limit.
+    #[value(name = "apigw-5xx")]
+    Apigw5xx,
+    /// The slowest Lambda invocations, from their REPORT lines. Params: limit.
+    SlowestRequests,
+    /// Counts the events matching a pattern over time. Params: pattern, bin.
+    ErrorsOverTime,
+}
+
+impl QueryTemplate {
+    /// The query, with `{{name}}` placeholders.
+    fn query(self) -> &'static str {
+        match self {
+            QueryTemplate::LambdaErrors => {
+                "fields @timestamp, @requestId, @message\n\
+                 | filter @message like /(?i)(error|exception|task timed out)/\n\
+                 | sort @timestamp desc\n\
+                 | limit {{limit}}"
+            }
+            QueryTemplate::Apigw5xx => {
+                "fields @timestamp, status, httpMethod, resourcePath, requestId\n\
+                 | filter status >= {{min_status}} and status <= 599\n\
+                 | sort @timestamp desc\n\
+                 | limit {{limit}}"
+            }
+            QueryTemplate::SlowestRequests => {
+                "filter @type = \"REPORT\"\n\
+                 | fields @timestamp, @requestId, @duration, @billedDuration, @maxMemoryUsed\n\
+                 | sort @duration desc\n\
+                 | limit {{limit}}"
+            }
+            QueryTemplate::ErrorsOverTime => {
+                "filter @message like /{{pattern}}/\n\
+                 | stats count() as events by bin({{bin}})"
+            }
+        }
+    }
+
+    /// The parameters of the template with their default values.
+    fn params(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            QueryTemplate::LambdaErrors => &[("limit", "50")],
+            QueryTemplate::Apigw5xx => &[("min_status", "500"), ("limit", "50")],
+            QueryTemplate::SlowestRequests => &[("limit", "20")],
+            QueryTemplate::ErrorsOverTime => &[("pattern", "ERROR"), ("bin", "5m")],
+        }
+    }
+
+    /// Fills in the placeholders, with `params` taking precedence over the defaults.
+    pub fn render(self, params: &[(String, String)]) -> Result<String, String> {
+        let known = self.params();
+        if let Some((key, _)) = params
+            .iter()
+            .find(|(key, _)| !known.iter().any(|(name, _)| name == key))
+        {
+            return Err(format!(
+                "the template has no parameter `{}`; it takes {}",
+                key,
+                known
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        let mut query = self.query().to_string();
+        for (name, default) in known {
+            // 同じキーが複数回指定されたら最後の値を使う
+            let value = params
+                .iter()
+                .rev()
+                .find(|(key, _)| key == name)
+                .map_or(*default, |(_, value)| value.as_str());
+            query = query.replace(&format!("{{{{{}}}}}", name), value);
+        }
+        Ok(query)
+    }
+}
@@ -0,0 +1,88 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use aws_smithy_runtime::client::http::hyper_014::default_client;
+use aws_smithy_runtime_api::client::{
+    http::{
+        http_client_fn, HttpClient, HttpConnector, HttpConnectorFuture, SharedHttpClient,
+        SharedHttpConnector,
+    },
+    orchestrator::HttpRequest,
+};
+
+/// A token bucket that refills at `rate` tokens per second and holds up to one second's worth.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(max_tps: u32) -> Self {
+        let rate = f64::from(max_tps);
+        TokenBucket {
+            rate,
+            state: Mutex::new(BucketState {
+                tokens: rate,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes a token, waiting until one is available.
+    async fn acquire(&self) {
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            let now = Instant::now();
+            let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+            state.tokens = (state.tokens + elapsed * self.rate).min(self.rate);
+            state.refilled_at = now;
+            // 足りない分は先の時刻のトークンを予約する。待つ順番は呼んだ順になる
+            state.tokens -= 1.0;
+            match state.tokens < 0.0 {
+                true => Duration::from_secs_f64(-state.tokens / self.rate),
+                false => Duration::ZERO,
+            }
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Sends each request through the inner connector once the bucket has a token.
+#[derive(Debug)]
+struct RateLimitedConnector {
+    inner: SharedHttpConnector,
+    bucket: Arc<TokenBucket>,
+}
+
+impl HttpConnector for RateLimitedConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let inner = self.inner.clone();
+        let bucket = self.bucket.clone();
+        HttpConnectorFuture::new(async move {
+            bucket.acquire().await;
+            inner.call(request).await
+        })
+    }
+}
+
+/// The SDK's default HTTP client, limited to `max_tps` requests per second in total
+/// across every client it is given to. Retries count as requests too.
+pub fn rate_limited_client(max_tps: u32) -> SharedHttpClient {
+    let inner = default_client().expect("the SDK is built with its default HTTPS client");
+    let bucket = Arc::new(TokenBucket::new(max_tps));
+    http_client_fn(move |settings, components| {
+        SharedHttpConnector::new(RateLimitedConnector {
+            inner: inner.http_connector(settings, components),
+            bucket: bucket.clone(),
+        })
+    })
+}
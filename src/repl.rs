@@ -0,0 +1,633 @@
+//! The `repl` command: a prompt that runs the subcommands one after another on the same client,
+//! so that the AWS configuration is loaded once. A current log group, switched with `use`, is
+//! added to the subcommands that take `--group` when the line names none. Tab completes the
+//! subcommands, their flags and values, and the names of log groups and streams.
+
+use std::ffi::OsString;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use aws_sdk_cloudwatchlogs::Client;
+use clap::Args;
+
+use crate::bookmarks::Bookmarks;
+use crate::color;
+use crate::config;
+use crate::groups;
+use crate::outln;
+use crate::terminal::{self, Key, RawMode};
+
+/// The words the REPL handles itself, besides the subcommands.
+const BUILTINS: &[&str] = &["use", "filter", "help", "exit", "quit"];
+
+/// How many names one press of Tab asks CloudWatch Logs for.
+const COMPLETION_LIMIT: i32 = 50;
+
+#[derive(Debug, Args)]
+pub struct ReplOpt {
+    /// The log group to start in. `use LOG-GROUP` switches to another one.
+    #[arg(short, long)]
+    pub group: Option<String>,
+}
+
+/// What to do after a line has been read.
+pub enum Input {
+    /// Runs a subcommand, given as words with the current group added.
+    Command(Vec<OsString>),
+    /// Reads the next line: the line was empty, or a builtin that has been handled.
+    Next,
+    /// Ends the REPL, after `exit` or at the end of input.
+    Exit,
+}
+
+pub struct Session {
+    client: Client,
+    /// The subcommands that a line can run, to look their flags up in.
+    command: clap::Command,
+    group: Option<String>,
+    editor: Editor,
+    color: bool,
+}
+
+impl Session {
+    /// `command` parses the lines, i.e. the subcommands without the program's global options.
+    pub fn new(client: Client, mut command: clap::Command, opt: ReplOpt, color: bool) -> Self {
+        command.build();
+        Session {
+            client,
+            command,
+            group: opt.group,
+            editor: Editor::default(),
+            color,
+        }
+    }
+
+    fn prompt(&self) -> String {
+        let name = self.group.as_deref().unwrap_or("logs");
+        match self.color {
+            true => format!("{}> ", color::prompt(name)),
+            false => format!("{}> ", name),
+        }
+    }
+
+    /// Reads lines until one runs a subcommand or ends the REPL.
+    pub async fn read(&mut self) -> Input {
+        let prompt = self.prompt();
+        let completer = Completer {
+            client: &self.client,
+            command: &self.command,
+            group: self.group.as_deref(),
+        };
+        let editor = &mut self.editor;
+        let line = tokio::task::block_in_place(|| {
+            editor.read_line(&prompt, &mut |before| completer.complete(before))
+        });
+        let line = match line {
+            Ok(Some(line)) => line,
+            Ok(None) => return Input::Exit,
+            Err(e) => {
+                eprintln!("Error: Failed to read the line: {}", e);
+                return Input::Exit;
+            }
+        };
+        let words = match split_words(&line) {
+            Ok(words) => words,
+            Err(message) => {
+                eprintln!("Error: {}", message);
+                return Input::Next;
+            }
+        };
+        let Some((first, rest)) = words.split_first() else {
+            return Input::Next;
+        };
+        let words = match first.as_str() {
+            "exit" | "quit" => return Input::Exit,
+            "help" => {
+                print_help();
+                return Input::Next;
+            }
+            "use" => {
+                self.switch_group(rest).await;
+                return Input::Next;
+            }
+            "filter" => {
+                match rest.split_first() {
+                    Some((pattern, rest)) => ["get-events", "--filter-pattern", pattern]
+                        .into_iter()
+                        .map(str::to_string)
+                        .chain(rest.iter().cloned())
+                        .collect(),
+                    None => {
+                        eprintln!("Error: `filter` needs a filter pattern, e.g. `filter ERROR --since 1h`");
+                        return Input::Next;
+                    }
+                }
+            }
+            _ => words,
+        };
+        let words = self.with_current_group(words);
+        match expand_bookmarks(words) {
+            Ok(words) => Input::Command(words),
+            Err(message) => {
+                eprintln!("Error: {}", message);
+                Input::Next
+            }
+        }
+    }
+
+    /// `use LOG-GROUP` switches to an existing group, and `use` alone prints the current one.
+    async fn switch_group(&mut self, args: &[String]) {
+        let group = match args {
+            [] => {
+                match &self.group {
+                    Some(group) => outln!("{}", group),
+                    None => eprintln!("No log group is in use."),
+                }
+                return;
+            }
+            [group] => group,
+            _ => {
+                eprintln!("Error: `use` takes one log group");
+                return;
+            }
+        };
+        let group = match group.strip_prefix('@') {
+            Some(_) => match expand_bookmarks(vec![String::from("-g"), group.clone()]) {
+                Ok(words) if words.len() == 2 => words[1].to_string_lossy().into_owned(),
+                Ok(_) => {
+                    eprintln!("Error: `{}` has several log groups, `use` takes one", group);
+                    return;
+                }
+                Err(message) => {
+                    eprintln!("Error: {}", message);
+                    return;
+                }
+            },
+            None => group.clone(),
+        };
+        match groups::resolve_group_arn(&self.client, &group).await {
+            Ok(_) => self.group = Some(group),
+            Err(aws_sdk_cloudwatchlogs::Error::ResourceNotFoundException(_)) => {
+                eprintln!("Error: Log group `{}` does not exist.", group)
+            }
+            Err(e) => crate::report::error(Some("Failed to look up the log group"), &e),
+        }
+    }
+
+    /// Adds `--group` with the current group when the subcommand takes one and the line gives none,
+    /// nor any flag that selects groups another way, such as `--lambda`.
+    fn with_current_group(&self, mut words: Vec<String>) -> Vec<String> {
+        let Some(group) = &self.group else {
+            return words;
+        };
+        let subcommand = find_subcommand(&self.command, &words);
+        let Some(group_arg) = subcommand
+            .get_arguments()
+            .find(|arg| arg.get_id() == "group")
+        else {
+            return words;
+        };
+        let mut selectors = vec![group_arg];
+        selectors.extend(
+            subcommand
+                .get_arguments()
+                .filter(|arg| subcommand.get_arg_conflicts_with(group_arg).contains(arg)),
+        );
+        let given = words.iter().any(|word| {
+            selectors.iter().any(|arg| {
+                arg.get_long().is_some_and(|long| {
+                    let flag = format!("--{}", long);
+                    *word == flag || word.starts_with(&format!("{}=", flag))
+                }) || arg
+                    .get_short()
+                    .is_some_and(|short| word.starts_with(&format!("-{}", short)))
+            })
+        });
+        if !given {
+            words.push(format!("--group={}", group));
+        }
+        words
+    }
+}
+
+/// Replaces `-g @NAME` with the groups of the bookmark. The client keeps its Region,
+/// so the Regions of bookmarks are not added.
+fn expand_bookmarks(words: Vec<String>) -> Result<Vec<OsString>, String> {
+    let config = config::load()?;
+    let bookmarks = Bookmarks::load()?;
+    bookmarks.expand_groups(config, words.into_iter().map(OsString::from))
+}
+
+fn print_help() {
+    eprintln!("Runs any subcommand without the program name, e.g. `get-events --since 1h`.");
+    eprintln!("The current log group is added to the subcommands that take `--group`.");
+    eprintln!();
+    eprintln!("    use LOG-GROUP              Switches the current log group; `use` prints it");
+    eprintln!("    filter PATTERN [OPTIONS]   Runs `get-events --filter-pattern PATTERN`");
+    eprintln!("    help                       Prints this help; `SUBCOMMAND --help` describes one");
+    eprintln!("    exit, quit, Ctrl-D         Leaves the REPL");
+    eprintln!();
+    eprintln!("Tab completes subcommands, flags, log groups and streams. Up and Down go through");
+    eprintln!("the lines typed before. Ctrl-C stops the running subcommand; it stops a running");
+    eprintln!("query on AWS too, which also ends the REPL.");
+}
+
+/// The deepest subcommand that the words name, e.g. `list` for `tags list -g NAME`.
+fn find_subcommand<'a>(command: &'a clap::Command, words: &[String]) -> &'a clap::Command {
+    let mut current = command;
+    for word in words {
+        match current.find_subcommand(word) {
+            Some(subcommand) => current = subcommand,
+            None if word.starts_with('-') => continue,
+            None => break,
+        }
+    }
+    current
+}
+
+/// Splits a line into words like a shell: on spaces, with `'...'`, `"..."` and `\` quoting.
+pub fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated `'`".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("unterminated `\"`".to_string()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated `\"`".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.next());
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// The candidates for the word before the cursor, which starts at char `start` of the line.
+pub struct Completion {
+    pub start: usize,
+    pub candidates: Vec<String>,
+}
+
+struct Completer<'a> {
+    client: &'a Client,
+    command: &'a clap::Command,
+    group: Option<&'a str>,
+}
+
+impl Completer<'_> {
+    fn complete(&self, before: &str) -> Completion {
+        let start = before
+            .char_indices()
+            .rfind(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let word = &before[start..];
+        let words = split_words(&before[..start]).unwrap_or_else(|_| {
+            before[..start]
+                .split_whitespace()
+                .map(str::to_string)
+                .collect()
+        });
+        let mut candidates = self.candidates(&words, word);
+        candidates.retain(|candidate| candidate.starts_with(word));
+        candidates.sort();
+        candidates.dedup();
+        Completion {
+            start: before[..start].chars().count(),
+            candidates,
+        }
+    }
+
+    fn candidates(&self, words: &[String], word: &str) -> Vec<String> {
+        let Some(previous) = words.last() else {
+            return BUILTINS
+                .iter()
+                .map(|builtin| builtin.to_string())
+                .chain(subcommand_names(self.command))
+                .collect();
+        };
+        if word.starts_with('@') {
+            return match (config::load(), Bookmarks::load()) {
+                (Ok(config), Ok(bookmarks)) => bookmarks.names(config),
+                _ => Vec::new(),
+            };
+        }
+        if words == ["use"] || matches!(previous.as_str(), "-g" | "--group") {
+            return self.groups(word);
+        }
+        if matches!(previous.as_str(), "-s" | "--stream") {
+            let group = words
+                .windows(2)
+                .rev()
+                .find(|pair| matches!(pair[0].as_str(), "-g" | "--group"))
+                .map(|pair| pair[1].as_str())
+                .or(self.group);
+            return group.map_or_else(Vec::new, |group| self.streams(group, word));
+        }
+        let words = match words.first().map(String::as_str) {
+            Some("filter") => &words[1..],
+            _ => words,
+        };
+        let subcommand = find_subcommand(self.command, words);
+        let flag = subcommand.get_arguments().find(|arg| {
+            arg.get_long()
+                .is_some_and(|long| *previous == format!("--{}", long))
+                || arg
+                    .get_short()
+                    .is_some_and(|short| *previous == format!("-{}", short))
+        });
+        if let Some(flag) = flag.filter(|flag| flag.get_action().takes_values()) {
+            return flag
+                .get_possible_values()
+                .iter()
+                .map(|value| value.get_name().to_string())
+                .collect();
+        }
+        match word.starts_with('-') {
+            true => subcommand
+                .get_arguments()
+                .filter(|arg| !arg.is_hide_set())
+                .filter_map(|arg| arg.get_long())
+                .map(|long| format!("--{}", long))
+                .collect(),
+            false => subcommand_names(subcommand).collect(),
+        }
+    }
+
+    /// Log groups whose names start with `prefix`, asked for as Tab is pressed.
+    fn groups(&self, prefix: &str) -> Vec<String> {
+        let request = self
+            .client
+            .describe_log_groups()
+            .set_log_group_name_prefix(Some(prefix.to_string()).filter(|prefix| !prefix.is_empty()))
+            .limit(COMPLETION_LIMIT);
+        match tokio::runtime::Handle::current().block_on(request.send()) {
+            Ok(output) => output
+                .log_groups()
+                .iter()
+                .filter_map(|group| group.log_group_name())
+                .map(str::to_string)
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Log streams of `group` whose names start with `prefix`.
+    fn streams(&self, group: &str, prefix: &str) -> Vec<String> {
+        let request = self
+            .client
+            .describe_log_streams()
+            .log_group_name(group)
+            .set_log_stream_name_prefix(
+                Some(prefix.to_string()).filter(|prefix| !prefix.is_empty()),
+            )
+            .limit(COMPLETION_LIMIT);
+        match tokio::runtime::Handle::current().block_on(request.send()) {
+            Ok(output) => output
+                .log_streams()
+                .iter()
+                .filter_map(|stream| stream.log_stream_name())
+                .map(str::to_string)
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+fn subcommand_names(command: &clap::Command) -> impl Iterator<Item = String> + '_ {
+    command
+        .get_subcommands()
+        .filter(|subcommand| !subcommand.is_hide_set() && subcommand.get_name() != "help")
+        .flat_map(|subcommand| {
+            std::iter::once(subcommand.get_name()).chain(subcommand.get_visible_aliases())
+        })
+        .map(str::to_string)
+}
+
+/// A single-line editor with history and completion, drawn on stderr.
+#[derive(Default)]
+struct Editor {
+    history: Vec<String>,
+}
+
+/// The number of columns the prompt takes, without its color codes.
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if in_escape => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+impl Editor {
+    /// Reads a line, or `None` at the end of input. Without a terminal, lines are read as they are.
+    fn read_line(
+        &mut self,
+        prompt: &str,
+        complete: &mut dyn FnMut(&str) -> Completion,
+    ) -> io::Result<Option<String>> {
+        if !io::stdin().is_terminal() {
+            let mut line = String::new();
+            return match io::stdin().lock().read_line(&mut line)? {
+                0 => Ok(None),
+                _ => Ok(Some(line.trim_end_matches(['\n', '\r']).to_string())),
+            };
+        }
+        let _raw = RawMode::enable()?;
+        let mut chars: Vec<char> = Vec::new();
+        let mut cursor = 0;
+        // 履歴をたどる間、書きかけの行を覚えておく
+        let mut index = self.history.len();
+        let mut draft: Vec<char> = Vec::new();
+        let mut listed = false;
+        draw(prompt, &chars, cursor);
+        loop {
+            let Some(key) = terminal::read_key()? else {
+                return Ok(None);
+            };
+            let tab = key == Key::Tab;
+            match key {
+                Key::Enter => {
+                    eprint!("\r\n");
+                    let line: String = chars.iter().collect();
+                    if !line.trim().is_empty() && self.history.last() != Some(&line) {
+                        self.history.push(line.clone());
+                    }
+                    return Ok(Some(line));
+                }
+                Key::Ctrl('c') => {
+                    eprint!("^C\r\n");
+                    return Ok(Some(String::new()));
+                }
+                Key::Ctrl('d') if chars.is_empty() => {
+                    eprint!("\r\n");
+                    return Ok(None);
+                }
+                Key::Char(c) => {
+                    chars.insert(cursor, c);
+                    cursor += 1;
+                }
+                Key::Backspace | Key::Ctrl('h') if cursor > 0 => {
+                    cursor -= 1;
+                    chars.remove(cursor);
+                }
+                Key::Delete | Key::Ctrl('d') if cursor < chars.len() => {
+                    chars.remove(cursor);
+                }
+                Key::Left | Key::Ctrl('b') => cursor = cursor.saturating_sub(1),
+                Key::Right | Key::Ctrl('f') => cursor = (cursor + 1).min(chars.len()),
+                Key::Home | Key::Ctrl('a') => cursor = 0,
+                Key::End | Key::Ctrl('e') => cursor = chars.len(),
+                Key::Ctrl('u') => {
+                    chars.drain(..cursor);
+                    cursor = 0;
+                }
+                Key::Ctrl('k') => chars.truncate(cursor),
+                Key::Ctrl('w') => {
+                    let mut start = cursor;
+                    while start > 0 && chars[start - 1].is_whitespace() {
+                        start -= 1;
+                    }
+                    while start > 0 && !chars[start - 1].is_whitespace() {
+                        start -= 1;
+                    }
+                    chars.drain(start..cursor);
+                    cursor = start;
+                }
+                Key::Ctrl('l') => eprint!("\x1b[H\x1b[2J"),
+                Key::Up | Key::Ctrl('p') if index > 0 => {
+                    if index == self.history.len() {
+                        draft = chars.clone();
+                    }
+                    index -= 1;
+                    chars = self.history[index].chars().collect();
+                    cursor = chars.len();
+                }
+                Key::Down | Key::Ctrl('n') if index < self.history.len() => {
+                    index += 1;
+                    chars = match self.history.get(index) {
+                        Some(line) => line.chars().collect(),
+                        None => draft.clone(),
+                    };
+                    cursor = chars.len();
+                }
+                Key::Tab => {
+                    let before: String = chars[..cursor].iter().collect();
+                    let completion = complete(&before);
+                    let word: String = chars[completion.start..cursor].iter().collect();
+                    match completion.candidates.as_slice() {
+                        [] => eprint!("\x07"),
+                        [only] => {
+                            let mut insert: Vec<char> =
+                                only.chars().skip(word.chars().count()).collect();
+                            insert.push(' ');
+                            let at = cursor;
+                            cursor += insert.len();
+                            chars.splice(at..at, insert);
+                        }
+                        candidates => {
+                            let common = common_prefix(candidates);
+                            if common.chars().count() > word.chars().count() {
+                                let insert: Vec<char> =
+                                    common.chars().skip(word.chars().count()).collect();
+                                let at = cursor;
+                                cursor += insert.len();
+                                chars.splice(at..at, insert);
+                            } else if listed {
+                                list_candidates(candidates);
+                            } else {
+                                eprint!("\x07");
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+            listed = tab;
+            draw(prompt, &chars, cursor);
+        }
+    }
+}
+
+/// Redraws the prompt and the line, scrolled so that the cursor stays on the screen.
+fn draw(prompt: &str, chars: &[char], cursor: usize) {
+    let (columns, _) = terminal::size();
+    let width = columns.saturating_sub(visible_width(prompt) + 1).max(10);
+    let start = (cursor + 1).saturating_sub(width);
+    let end = (start + width).min(chars.len());
+    let visible: String = chars[start..end].iter().collect();
+    let mut text = format!("\r{}{}\x1b[K", prompt, visible);
+    if end > cursor {
+        text.push_str(&format!("\x1b[{}D", end - cursor));
+    }
+    let mut stderr = io::stderr().lock();
+    let _ = stderr.write_all(text.as_bytes());
+    let _ = stderr.flush();
+}
+
+fn common_prefix(candidates: &[String]) -> String {
+    let mut prefix: Vec<char> = candidates[0].chars().collect();
+    for candidate in &candidates[1..] {
+        let same = prefix
+            .iter()
+            .zip(candidate.chars())
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        prefix.truncate(same);
+    }
+    prefix.into_iter().collect()
+}
+
+/// Prints the candidates in columns below the line, on the second Tab.
+fn list_candidates(candidates: &[String]) {
+    let (columns, _) = terminal::size();
+    let width = candidates
+        .iter()
+        .map(|c| c.chars().count())
+        .max()
+        .unwrap_or(0)
+        + 2;
+    let per_line = (columns / width).max(1);
+    let mut text = String::from("\r\n");
+    for (i, candidate) in candidates.iter().enumerate() {
+        text.push_str(&format!("{:<width$}", candidate, width = width));
+        if (i + 1) % per_line == 0 || i + 1 == candidates.len() {
+            text.push_str("\r\n");
+        }
+    }
+    eprint!("{}", text);
+}
@@ -0,0 +1,180 @@
+//! Reports the failed AWS calls: with advice for the common kinds of failure in text output,
+//! and as one JSON object per line on standard error when the command's output is JSON.
+//! The kind of failure also decides the exit code.
+
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+use aws_sdk_cloudwatchlogs::error::ProvideErrorMetadata;
+
+use crate::error::{Error, ErrorKind};
+use crate::output::Record;
+
+/// Any failure without a code of its own, such as an invalid request or a failed download.
+pub const EXIT_FAILURE: i32 = 1;
+// 2 は clap が使い方の誤りに使う
+/// The log group or stream does not exist.
+pub const EXIT_NOT_FOUND: i32 = 3;
+/// The command finished, but some pages or calls failed, so the output is incomplete.
+pub const EXIT_PARTIAL: i32 = 4;
+/// The credentials are missing, expired or not allowed to make a call.
+pub const EXIT_ACCESS_DENIED: i32 = 5;
+/// The command was stopped with Ctrl-C, as 128 plus the number of SIGINT.
+pub const EXIT_INTERRUPTED: i32 = 130;
+/// The reader of the output went away, e.g. `| head`, as 128 plus the number of SIGPIPE.
+pub const EXIT_BROKEN_PIPE: i32 = 141;
+/// The command was stopped with SIGTERM, as 128 plus its number.
+pub const EXIT_TERMINATED: i32 = 143;
+
+/// The exit code of the warnings reported so far, or 0 without any.
+static WARNED: AtomicI32 = AtomicI32::new(0);
+
+/// Set from the `--output` of the subcommand, before each one that the REPL runs.
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Reports every failure from now on as JSON, or as text.
+pub fn set_json(json: bool) {
+    JSON.store(json, Ordering::SeqCst);
+}
+
+/// Whether failures are reported as JSON.
+pub fn is_json() -> bool {
+    JSON.load(Ordering::SeqCst)
+}
+
+/// Forgets the warnings reported so far, so that the next command of the REPL starts over.
+pub fn reset_warnings() {
+    WARNED.store(0, Ordering::SeqCst);
+}
+
+/// Reports a failed call that the command carries on after, such as a page that could not be read.
+/// `context` says what failed, e.g. `Failed to retrieve a log stream`.
+pub fn warn<E>(context: &str, error: &E)
+where
+    E: ProvideErrorMetadata + std::error::Error,
+{
+    let code = match ErrorKind::of(error) {
+        ErrorKind::AccessDenied => EXIT_ACCESS_DENIED,
+        _ => EXIT_PARTIAL,
+    };
+    WARNED.fetch_max(code, Ordering::SeqCst);
+    match is_json() {
+        true => eprintln!("{}", record("warning", Some(context), error).to_json()),
+        false => eprintln!("Warning: {}: {}", context, describe(error)),
+    }
+}
+
+/// Reports output that is incomplete without a failed call, such as the rows of a query that was
+/// stopped before it finished, so that the command exits with [`EXIT_PARTIAL`].
+pub fn incomplete(message: &str) {
+    WARNED.fetch_max(EXIT_PARTIAL, Ordering::SeqCst);
+    match is_json() {
+        true => {
+            let record = Record::new()
+                .field("level", "warning")
+                .field("kind", ErrorKind::Other.as_str())
+                .field("message", message);
+            eprintln!("{}", record.to_json());
+        }
+        false => eprintln!("Warning: {}", message),
+    }
+}
+
+/// The code to exit with after the command succeeded: [`EXIT_PARTIAL`] once a call has failed,
+/// or [`EXIT_ACCESS_DENIED`] if one was denied.
+pub fn warned_exit_code() -> i32 {
+    WARNED.load(Ordering::SeqCst)
+}
+
+/// The code to exit with after the command failed with `error`.
+pub fn exit_code(error: &impl ProvideErrorMetadata) -> i32 {
+    match ErrorKind::of(error) {
+        ErrorKind::AccessDenied => EXIT_ACCESS_DENIED,
+        ErrorKind::ResourceNotFound => EXIT_NOT_FOUND,
+        ErrorKind::Throttling | ErrorKind::Other => EXIT_FAILURE,
+    }
+}
+
+/// Reports the error that a subcommand failed with, and returns the code to exit with.
+pub fn failure(failure: &Error) -> i32 {
+    match failure {
+        Error::Service(e) => error(None, e),
+        Error::Aborted => eprintln!("Aborted."),
+        other => match is_json() {
+            true => {
+                let kind = other.kind();
+                let record = Record::new()
+                    .field("level", "error")
+                    .field("kind", kind.as_str())
+                    .field("message", other.to_string().as_str())
+                    .field("hint", kind.hint());
+                eprintln!("{}", record.to_json());
+            }
+            false => eprintln!("Error: {}", other),
+        },
+    }
+    failure.exit_code()
+}
+
+/// Reports the failure that ends the command.
+pub fn error<E>(context: Option<&str>, error: &E)
+where
+    E: ProvideErrorMetadata + std::error::Error,
+{
+    match (is_json(), context) {
+        (true, _) => eprintln!("{}", record("error", context, error).to_json()),
+        (false, Some(context)) => eprintln!("Error: {}: {}", context, describe(error)),
+        (false, None) => eprintln!("Error: {}", describe(error)),
+    }
+}
+
+/// The message of the service, or the chain of causes when the call did not get a response,
+/// such as `dispatch failure: io error: ... Connection refused (os error 111)`.
+fn message<E>(error: &E) -> String
+where
+    E: ProvideErrorMetadata + std::error::Error,
+{
+    if let Some(message) = error.message() {
+        return message.to_string();
+    }
+    let mut text = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        // 原因の文言を自分のメッセージに含めるエラーもあるので、重複は足さない
+        let cause_text = cause.to_string();
+        if !text.contains(&cause_text) {
+            text.push_str(": ");
+            text.push_str(&cause_text);
+        }
+        source = cause.source();
+    }
+    text
+}
+
+/// The message with its error code and, for the classified kinds, what to try next.
+pub fn describe<E>(error: &E) -> String
+where
+    E: ProvideErrorMetadata + std::error::Error,
+{
+    let mut text = message(error);
+    if let Some(code) = error.code() {
+        text.push_str(&format!(" ({})", code));
+    }
+    if let Some(hint) = ErrorKind::of(error).hint() {
+        text.push_str(&format!(". {}.", hint));
+    }
+    text
+}
+
+fn record<E>(level: &str, context: Option<&str>, error: &E) -> Record
+where
+    E: ProvideErrorMetadata + std::error::Error,
+{
+    let kind = ErrorKind::of(error);
+    Record::new()
+        .field("level", level)
+        .field("kind", kind.as_str())
+        .field("code", error.code())
+        .field("message", message(error).as_str())
+        .field("context", context)
+        .field("hint", kind.hint())
+}
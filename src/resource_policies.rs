@@ -0,0 +1,197 @@
+use std::fs;
+use std::io::{self, Read};
+
+use aws_sdk_cloudwatchlogs::{types::ResourcePolicy, Client};
+use clap::{Args, Subcommand};
+
+use crate::confirm::ConfirmOpt;
+use crate::error::Error;
+use crate::jq::Json;
+use crate::outln;
+use crate::output::{self, format_timestamp, OutputFormat, Record};
+
+#[derive(Debug, Args)]
+pub struct ResourcePoliciesOpt {
+    #[command(subcommand)]
+    pub command: ResourcePoliciesCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ResourcePoliciesCommand {
+    /// Lists the resource policies that let other AWS services write to CloudWatch Logs,
+    /// with their documents.
+    List(ListResourcePoliciesOpt),
+
+    /// Creates or replaces a resource policy.
+    Put(PutResourcePolicyOpt),
+
+    /// Deletes a resource policy.
+    Delete(DeleteResourcePolicyOpt),
+}
+
+#[derive(Debug, Args)]
+pub struct ListResourcePoliciesOpt {
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+/// Reads a JSON policy document from a file, or from standard input for `-`,
+/// and checks that it parses.
+pub fn read_policy_document(path: &str) -> Result<String, String> {
+    let document = match path {
+        "-" => {
+            let mut document = String::new();
+            io::stdin()
+                .read_to_string(&mut document)
+                .map_err(|e| format!("failed to read standard input: {}", e))?;
+            document
+        }
+        path => fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?,
+    };
+    Json::parse(&document).map_err(|e| format!("the policy document is not valid JSON: {}", e))?;
+    Ok(document)
+}
+
+#[derive(Debug, Args)]
+pub struct PutResourcePolicyOpt {
+    /// The name of the policy. An existing policy with this name is replaced.
+    #[arg(long)]
+    pub name: String,
+
+    /// A file with the JSON policy document, or `-` to read it from standard input.
+    #[arg(long, value_name = "FILE", value_parser = read_policy_document)]
+    pub document: String,
+}
+
+#[derive(Debug, Args)]
+pub struct DeleteResourcePolicyOpt {
+    /// The name of the policy.
+    #[arg(long)]
+    pub name: String,
+
+    #[command(flatten)]
+    pub confirm: ConfirmOpt,
+}
+
+/// Describes a resource policy for structured output.
+fn policy_record(policy: &ResourcePolicy) -> Record {
+    Record::new()
+        .field("name", policy.policy_name())
+        .field("lastUpdatedTime", policy.last_updated_time())
+        .field("document", policy.policy_document())
+}
+
+/// Describes a resource policy as a row of the `table` output, without its document.
+fn policy_table_row(policy: &ResourcePolicy) -> Record {
+    Record::new().field("NAME", policy.policy_name()).field(
+        "UPDATED",
+        policy.last_updated_time().map(format_timestamp).as_deref(),
+    )
+}
+
+/// Lists every resource policy in the Region with DescribeResourcePolicies.
+async fn list_policies(client: &Client, opt: ListResourcePoliciesOpt) -> Result<(), Error> {
+    let ListResourcePoliciesOpt { output } = opt;
+    let mut policies: Vec<ResourcePolicy> = Vec::new();
+    let mut next_token: Option<String> = None;
+    loop {
+        let page = client
+            .describe_resource_policies()
+            .set_next_token(next_token)
+            .send()
+            .await?;
+        policies.extend(page.resource_policies.unwrap_or_default());
+        next_token = page.next_token;
+        if next_token.is_none() {
+            break;
+        }
+    }
+
+    match output {
+        OutputFormat::Text => {
+            for policy in &policies {
+                outln!(
+                    "{}\t{}",
+                    policy.policy_name().unwrap_or_default(),
+                    policy
+                        .last_updated_time()
+                        .map_or_else(|| "-".to_string(), format_timestamp)
+                );
+                // 文書は一行の JSON として字下げして続ける
+                if let Some(document) = policy.policy_document() {
+                    let document = Json::parse(document)
+                        .map_or_else(|_| document.to_string(), |json| json.to_json());
+                    outln!("    {}", document);
+                }
+            }
+        }
+        format => output::print_records(
+            format,
+            &policies.iter().map(policy_record).collect::<Vec<_>>(),
+            &policies.iter().map(policy_table_row).collect::<Vec<_>>(),
+        ),
+    }
+    Ok(())
+}
+
+/// Creates or replaces a resource policy with PutResourcePolicy.
+async fn put_policy(
+    client: &Client,
+    opt: PutResourcePolicyOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let PutResourcePolicyOpt { name, document } = opt;
+
+    if verbose {
+        eprintln!("Policy name:                   {}", &name);
+        eprintln!();
+    }
+
+    client
+        .put_resource_policy()
+        .policy_name(&name)
+        .policy_document(document)
+        .send()
+        .await?;
+    outln!("Put resource policy {}", name);
+    Ok(())
+}
+
+/// Deletes a resource policy with DeleteResourcePolicy.
+async fn delete_policy(
+    client: &Client,
+    opt: DeleteResourcePolicyOpt,
+    verbose: bool,
+) -> Result<(), Error> {
+    let DeleteResourcePolicyOpt { name, confirm } = opt;
+
+    if verbose {
+        eprintln!("Policy name:                   {}", &name);
+        eprintln!();
+    }
+
+    confirm.confirm(
+        &format!(
+            "About to delete resource policy {}. The services it allows will no longer be able to write logs.",
+            name
+        ),
+        &[],
+    )?;
+    client
+        .delete_resource_policy()
+        .policy_name(&name)
+        .send()
+        .await?;
+    outln!("Deleted resource policy {}", name);
+    Ok(())
+}
+
+/// Runs a resource policy subcommand.
+pub async fn run(client: &Client, opt: ResourcePoliciesOpt, verbose: bool) -> Result<(), Error> {
+    match opt.command {
+        ResourcePoliciesCommand::List(opt) => list_policies(client, opt).await,
+        ResourcePoliciesCommand::Put(opt) => put_policy(client, opt, verbose).await,
+        ResourcePoliciesCommand::Delete(opt) => delete_policy(client, opt, verbose).await,
+    }
+}
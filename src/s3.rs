@@ -0,0 +1,81 @@
+//! A minimal S3 client for reading exported logs. The S3 SDK is not part of this build,
+//! so requests go through [`SignedClient`].
+
+use crate::signed::{percent_encode, xml_values, SignedClient};
+use crate::targets::Target;
+
+/// Reads objects from S3 with the credentials and Region of a target.
+pub struct S3Reader {
+    client: SignedClient,
+}
+
+impl S3Reader {
+    /// The target's `--endpoint-url` is used with path-style addressing instead of the Regional S3 endpoint.
+    pub fn new(target: &Target) -> Result<Self, String> {
+        Ok(S3Reader {
+            client: SignedClient::new(target)?,
+        })
+    }
+
+    fn url(&self, bucket: &str, path: &str) -> String {
+        let region = self.client.region();
+        match self.client.endpoint_url() {
+            Some(endpoint_url) => format!("{}/{}{}", endpoint_url, bucket, path),
+            // ドットを含むバケット名は仮想ホスト形式だと証明書と一致しない
+            None if bucket.contains('.') => {
+                format!("https://s3.{}.amazonaws.com/{}{}", region, bucket, path)
+            }
+            None => format!("https://{}.s3.{}.amazonaws.com{}", bucket, region, path),
+        }
+    }
+
+    /// Sends a signed GET request and returns the response body.
+    async fn get(&self, url: String) -> Result<Vec<u8>, String> {
+        let (status, body) = self.client.send("s3", "GET", &url, &[], Vec::new()).await?;
+        if status != 200 {
+            let body = String::from_utf8_lossy(&body);
+            let code = xml_values(&body, "Code").into_iter().next();
+            return Err(format!(
+                "S3 returned {}{}",
+                status,
+                code.map(|code| format!(" ({})", code)).unwrap_or_default()
+            ));
+        }
+        Ok(body)
+    }
+
+    /// Lists the keys under `prefix` in key order with ListObjectsV2.
+    pub async fn list_keys(&self, bucket: &str, prefix: &str) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        let mut continuation_token: Option<String> = None;
+        loop {
+            let mut query = format!("?list-type=2&prefix={}", percent_encode(prefix, false));
+            if let Some(token) = &continuation_token {
+                query.push_str(&format!(
+                    "&continuation-token={}",
+                    percent_encode(token, false)
+                ));
+            }
+            let body = self.get(self.url(bucket, &format!("/{}", query))).await?;
+            let body = String::from_utf8_lossy(&body);
+            keys.extend(xml_values(&body, "Key"));
+            continuation_token = match xml_values(&body, "IsTruncated").first() {
+                Some(truncated) if truncated == "true" => {
+                    xml_values(&body, "NextContinuationToken")
+                        .into_iter()
+                        .next()
+                }
+                _ => None,
+            };
+            if continuation_token.is_none() {
+                return Ok(keys);
+            }
+        }
+    }
+
+    /// Downloads an object with GetObject.
+    pub async fn get_object(&self, bucket: &str, key: &str) -> Result<Vec<u8>, String> {
+        self.get(self.url(bucket, &format!("/{}", percent_encode(key, true))))
+            .await
+    }
+}
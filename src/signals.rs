@@ -0,0 +1,33 @@
+//! Ctrl-C and SIGTERM for the commands that run until they are stopped, such as `tail --follow`,
+//! `export` and `query`. The command is dropped at its next wait, so output is never cut off in
+//! the middle of a batch of events, and the checkpoint written after that batch stays current.
+
+use std::future;
+
+use tokio::signal::unix::{signal, SignalKind};
+
+use crate::report;
+
+/// Waits for SIGINT or SIGTERM, and returns the exit code to stop with.
+pub async fn interrupted() -> i32 {
+    tokio::select! {
+        _ = received(SignalKind::interrupt()) => report::EXIT_INTERRUPTED,
+        _ = received(SignalKind::terminate()) => report::EXIT_TERMINATED,
+    }
+}
+
+/// Waits for SIGTERM only, for full-screen commands that read Ctrl-C as a key.
+pub async fn terminated() -> i32 {
+    received(SignalKind::terminate()).await;
+    report::EXIT_TERMINATED
+}
+
+async fn received(kind: SignalKind) {
+    match signal(kind) {
+        Ok(mut signal) => {
+            signal.recv().await;
+        }
+        // 待ち受けられなければ、そのシグナルではこれまでどおりに終了する
+        Err(_) => future::pending().await,
+    }
+}
@@ -0,0 +1,217 @@
+//! Signed requests for the AWS services whose SDKs are not part of this build.
+//! Requests are signed with SigV4 and sent through the SDK's HTTPS connector.
+
+use std::time::SystemTime;
+
+use aws_sdk_cloudwatchlogs::config::{ProvideCredentials, SharedCredentialsProvider};
+use aws_sigv4::http_request::{
+    sign, PayloadChecksumKind, PercentEncodingMode, SignableBody, SignableRequest, SigningSettings,
+    UriPathNormalizationMode,
+};
+use aws_smithy_runtime::client::http::hyper_014::HyperConnector;
+use aws_smithy_runtime_api::client::{http::HttpConnector, orchestrator::HttpRequest};
+use aws_smithy_types::{body::SdkBody, byte_stream::ByteStream};
+
+use crate::jq::Json;
+use crate::targets::Target;
+
+/// Sends requests signed with the credentials and Region of a target.
+pub struct SignedClient {
+    connector: HyperConnector,
+    credentials: SharedCredentialsProvider,
+    region: String,
+    endpoint_url: Option<String>,
+}
+
+impl SignedClient {
+    /// The target's `--endpoint-url` replaces the Regional endpoint of every service.
+    pub fn new(target: &Target) -> Result<Self, String> {
+        let config = target.client.config();
+        let credentials = target
+            .credentials
+            .clone()
+            .ok_or("no credentials are configured")?;
+        Ok(SignedClient {
+            connector: HyperConnector::builder().build_https(),
+            credentials,
+            region: config
+                .region()
+                .map_or_else(|| "us-east-1".to_string(), |region| region.to_string()),
+            endpoint_url: target
+                .endpoint_url
+                .as_ref()
+                .map(|url| url.trim_end_matches('/').to_string()),
+        })
+    }
+
+    pub fn region(&self) -> &str {
+        &self.region
+    }
+
+    pub fn endpoint_url(&self) -> Option<&str> {
+        self.endpoint_url.as_deref()
+    }
+
+    /// Signs and sends a request to `service`, and returns the status code and the response body.
+    pub async fn send(
+        &self,
+        service: &str,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<(u16, Vec<u8>), String> {
+        let credentials = self
+            .credentials
+            .provide_credentials()
+            .await
+            .map_err(|e| format!("failed to load credentials: {}", e))?;
+        let identity = credentials.into();
+        let mut settings = SigningSettings::default();
+        let signable_body = match service {
+            "s3" => {
+                // S3 はパスを正規化も二重エンコードもせずに署名を検証する
+                settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+                settings.percent_encoding_mode = PercentEncodingMode::Single;
+                settings.uri_path_normalization_mode = UriPathNormalizationMode::Disabled;
+                SignableBody::UnsignedPayload
+            }
+            _ => SignableBody::Bytes(&body),
+        };
+        let params = aws_sigv4::sign::v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&self.region)
+            .name(service)
+            .time(SystemTime::now())
+            .settings(settings)
+            .build()
+            .map_err(|e| e.to_string())?
+            .into();
+        let signable = SignableRequest::new(method, url, headers.iter().copied(), signable_body)
+            .map_err(|e| e.to_string())?;
+        let (instructions, _) = sign(signable, &params)
+            .map_err(|e| e.to_string())?
+            .into_parts();
+
+        let request = http::Request::builder()
+            .method(method)
+            .uri(url)
+            .body(SdkBody::from(body))
+            .map_err(|e| format!("invalid request to `{}`: {}", url, e))?;
+        let mut request =
+            HttpRequest::try_from(request).map_err(|e| format!("invalid URL `{}`: {}", url, e))?;
+        for (name, value) in headers {
+            request
+                .headers_mut()
+                .insert(name.to_string(), value.to_string());
+        }
+        for header in instructions.into_parts().0 {
+            request
+                .headers_mut()
+                .insert(header.name(), header.value().to_string());
+        }
+        let response = self
+            .connector
+            .call(request)
+            .await
+            .map_err(|e| format!("{:?}", e))?;
+        let status = response.status().as_u16();
+        let body = ByteStream::new(response.into_body())
+            .collect()
+            .await
+            .map_err(|e| e.to_string())?
+            .to_vec();
+        Ok((status, body))
+    }
+
+    /// Calls an operation of a JSON protocol API such as ECS, named by `target` as in
+    /// `AmazonEC2ContainerServiceV20141113.DescribeTasks`, and returns the response.
+    pub async fn call_json(
+        &self,
+        service: &str,
+        target: &str,
+        content_type: &str,
+        request: Vec<(&str, Json)>,
+    ) -> Result<Json, String> {
+        let url = match &self.endpoint_url {
+            Some(endpoint_url) => format!("{}/", endpoint_url),
+            None => format!("https://{}.{}.amazonaws.com/", service, self.region),
+        };
+        let body = Json::Object(
+            request
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+        .to_json();
+        let headers = [("content-type", content_type), ("x-amz-target", target)];
+        let (status, body) = self
+            .send(service, "POST", &url, &headers, body.into_bytes())
+            .await?;
+        let operation = target.rsplit('.').next().unwrap_or(target);
+        let response = Json::parse(&String::from_utf8_lossy(&body))
+            .map_err(|e| format!("{} returned invalid JSON: {}", operation, e))?;
+        if status != 200 {
+            // __type は `com.amazonaws.ecs#ClusterNotFoundException` の形のこともある
+            let code = match response.get_path("__type") {
+                Some(Json::String(code)) => {
+                    format!(" ({})", code.rsplit('#').next().unwrap_or(code))
+                }
+                _ => String::new(),
+            };
+            let message = match response.get_path("message") {
+                Some(Json::String(message)) => format!(": {}", message),
+                _ => String::new(),
+            };
+            return Err(format!(
+                "{} returned {}{}{}",
+                operation, status, code, message
+            ));
+        }
+        Ok(response)
+    }
+}
+
+/// Percent-encodes everything except the unreserved characters, and `/` if `keep_slash` is set.
+pub fn percent_encode(s: &str, keep_slash: bool) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b'/' if keep_slash => "/".to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Returns the contents of every `<tag>` element as they are, including any nested elements.
+/// The responses read have no attributes on these elements, so no parser is needed.
+pub fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        elements.push(&rest[..end]);
+        rest = &rest[end + close.len()..];
+    }
+    elements
+}
+
+/// Returns the text of every `<tag>` element, with the XML entities unescaped.
+pub fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    xml_elements(xml, tag)
+        .into_iter()
+        .map(|text| {
+            text.replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&")
+        })
+        .collect()
+}
@@ -0,0 +1,243 @@
+//! Prints the logged history of a Step Functions execution. The Step Functions SDK is not part of
+//! this build, so its JSON API is called through [`SignedClient`].
+
+use clap::Args;
+
+use crate::error::Error;
+use crate::events::{filter_events, parse_event_template, EventFilter, EventPrinter};
+use crate::jq::Json;
+use crate::output::{format_timestamp, OutputFormat, Template};
+use crate::signed::SignedClient;
+use crate::targets::Target;
+use crate::time_range::{now_millis, parse_timestamp};
+
+#[derive(Debug, Args)]
+pub struct SfnLogsOpt {
+    /// The ARN of a standard or Express workflow execution.
+    #[arg(value_name = "EXECUTION-ARN", value_parser = parse_execution_arn)]
+    pub execution: ExecutionArn,
+
+    /// The log group to read, instead of the one in the logging configuration of the state machine.
+    #[arg(short, long)]
+    pub group: Option<String>,
+
+    /// The beginning of the time range, e.g. `2h`, `now-10m` or `2024-05-01 13:00` (UTC).
+    /// Defaults to the start of a standard execution, or an hour ago for an Express one.
+    #[arg(long, value_parser = parse_timestamp)]
+    pub since: Option<i64>,
+
+    /// The end of the time range, in the same formats as `--since`.
+    /// Defaults to the end of a standard execution, or now.
+    #[arg(long, value_parser = parse_timestamp)]
+    pub until: Option<i64>,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+
+    /// Prints only these fields of each history event, separated by tabs, e.g. `id,type,details.name`.
+    #[arg(long, value_delimiter = ',')]
+    pub fields: Vec<String>,
+
+    /// Prints each event with a template such as `{timestamp} {message}`.
+    /// Fields: timestamp, timestampMs, stream, eventId, message.
+    #[arg(long, conflicts_with = "output", value_parser = parse_event_template)]
+    pub format: Option<Template>,
+}
+
+/// An execution ARN and the state machine it belongs to.
+#[derive(Debug, Clone)]
+pub struct ExecutionArn {
+    pub arn: String,
+    pub state_machine_arn: String,
+    /// Whether it is an Express workflow execution, which DescribeExecution does not know about.
+    pub express: bool,
+}
+
+/// Parses `arn:aws:states:<Region>:<account>:execution:<state machine>:<name>`,
+/// or `...:express:<state machine>:<name>:<ID>` for Express workflows.
+fn parse_execution_arn(s: &str) -> Result<ExecutionArn, String> {
+    let parts: Vec<&str> = s.split(':').collect();
+    let express = match parts.get(5) {
+        _ if parts.len() < 8 || parts[2] != "states" => None,
+        Some(&"execution") => Some(false),
+        Some(&"express") => Some(true),
+        _ => None,
+    };
+    let Some(express) = express else {
+        return Err(format!(
+            "`{}` is not a Step Functions execution ARN, expected e.g. \
+             arn:aws:states:us-east-1:123456789012:execution:MyStateMachine:run-1",
+            s
+        ));
+    };
+    Ok(ExecutionArn {
+        arn: s.to_string(),
+        state_machine_arn: format!("{}:stateMachine:{}", parts[..5].join(":"), parts[6]),
+        express,
+    })
+}
+
+fn str_at<'a>(json: &'a Json, path: &str) -> Option<&'a str> {
+    match json.get_path(path) {
+        Some(Json::String(value)) => Some(value),
+        _ => None,
+    }
+}
+
+/// A timestamp in epoch seconds, as the JSON protocol sends them, in milliseconds.
+fn millis_at(json: &Json, path: &str) -> Option<f64> {
+    match json.get_path(path) {
+        Some(Json::Number(seconds)) => Some(seconds * 1000.0),
+        _ => None,
+    }
+}
+
+async fn call(
+    client: &SignedClient,
+    operation: &str,
+    request: Vec<(&str, Json)>,
+) -> Result<Json, String> {
+    let target = format!("AWSStepFunctions.{}", operation);
+    client
+        .call_json("states", &target, "application/x-amz-json-1.0", request)
+        .await
+}
+
+/// The log group that the state machine logs its executions to.
+async fn state_machine_log_group(client: &SignedClient, arn: &str) -> Result<String, String> {
+    let state_machine = call(
+        client,
+        "DescribeStateMachine",
+        vec![("stateMachineArn", Json::String(arn.to_string()))],
+    )
+    .await?;
+    let level = str_at(&state_machine, "loggingConfiguration.level").unwrap_or("OFF");
+    let destination = match state_machine.get_path("loggingConfiguration.destinations") {
+        Some(Json::Array(destinations)) => destinations
+            .iter()
+            .find_map(|destination| str_at(destination, "cloudWatchLogsLogGroup.logGroupArn")),
+        _ => None,
+    };
+    let Some(destination) = destination.filter(|_| level != "OFF") else {
+        return Err(format!(
+            "the state machine {} does not log to CloudWatch Logs",
+            arn
+        ));
+    };
+    if level != "ALL" {
+        eprintln!(
+            "Warning: The state machine only logs events at the {} level",
+            level
+        );
+    }
+    // 宛先は `arn:aws:logs:<Region>:<アカウント>:log-group:<名前>:*` の形
+    Ok(destination
+        .split_once(":log-group:")
+        .map_or(destination, |(_, group)| group)
+        .trim_end_matches(":*")
+        .to_string())
+}
+
+/// Prints the history events that a state machine logged for one execution, in order.
+pub async fn run(
+    target: &Target,
+    opt: SfnLogsOpt,
+    verbose: bool,
+    color: bool,
+) -> Result<(), Error> {
+    let SfnLogsOpt {
+        execution,
+        group,
+        since,
+        until,
+        output,
+        fields,
+        format,
+    } = opt;
+    let client = &target.client;
+    let fail = |e: String| Error::failed(format!("Failed to look up the execution: {}", e));
+    let signed = SignedClient::new(target).map_err(fail)?;
+
+    // Express ワークフローの実行は DescribeExecution で調べられない
+    let description = match execution.express {
+        true => None,
+        false => Some(
+            call(
+                &signed,
+                "DescribeExecution",
+                vec![("executionArn", Json::String(execution.arn.clone()))],
+            )
+            .await
+            .map_err(fail)?,
+        ),
+    };
+    let group = match group {
+        Some(group) => group,
+        None => state_machine_log_group(&signed, &execution.state_machine_arn)
+            .await
+            .map_err(fail)?,
+    };
+    let start_time = since.unwrap_or_else(|| {
+        description
+            .as_ref()
+            .and_then(|description| millis_at(description, "startDate"))
+            .map_or(now_millis() - 60 * 60 * 1000, |millis| {
+                millis.floor() as i64
+            })
+    });
+    let end_time = until.or_else(|| {
+        description
+            .as_ref()
+            .and_then(|description| millis_at(description, "stopDate"))
+            .map(|millis| millis.ceil() as i64)
+    });
+
+    if verbose {
+        eprintln!("Execution:                     {}", execution.arn);
+        eprintln!(
+            "State machine:                 {}",
+            execution.state_machine_arn
+        );
+        if let Some(status) = description
+            .as_ref()
+            .and_then(|description| str_at(description, "status"))
+        {
+            eprintln!("Status:                        {}", status);
+        }
+        eprintln!("Log group name:                {}", group);
+        eprintln!(
+            "Time range:                    {} - {}",
+            format_timestamp(start_time),
+            end_time.map_or("now".to_string(), format_timestamp)
+        );
+        eprintln!();
+    }
+
+    let filter = EventFilter {
+        filter_pattern: Some(format!("{{ $.execution_arn = \"{}\" }}", execution.arn)),
+        start_time: Some(start_time),
+        end_time,
+        ..Default::default()
+    };
+    let mut events = filter_events(client, &group, &filter).await?;
+    // 同じミリ秒の遷移はイベント ID の順に並べる
+    events.sort_by_key(|event| {
+        let id = Json::parse(event.message.trim()).ok().and_then(|message| {
+            match message.get_path("id") {
+                Some(Json::String(id)) => id.parse::<u64>().ok(),
+                Some(Json::Number(id)) => Some(*id as u64),
+                _ => None,
+            }
+        });
+        (event.timestamp, id)
+    });
+
+    let mut printer = EventPrinter::new(output, false)
+        .with_template(format)
+        .with_fields(fields)
+        .with_color(color);
+    printer.print(&events);
+    printer.finish();
+    Ok(())
+}
@@ -0,0 +1,858 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+use aws_sdk_cloudwatchlogs::{
+    types::{LogStream, OrderBy},
+    Client,
+};
+use std::cell::RefCell;
+use std::future;
+use std::time::Duration;
+
+use clap::{Args, ValueEnum};
+use futures_util::{future::join_all, stream, StreamExt};
+use regex_lite::Regex;
+use tokio::sync::mpsc;
+
+use crate::error::Error;
+use crate::groups::{group_stored_bytes, GroupOpt};
+use crate::logs_api::{is_not_found, LogsApi};
+use crate::output::{self, format_bytes, format_timestamp, OutputFormat, Record, Template};
+use crate::progress::Progress;
+use crate::report;
+use crate::targets::Target;
+use crate::time_range::{now_millis, parse_duration};
+use crate::watch::{Entry, Watch};
+use crate::{out, outln};
+
+/// The fields of a log stream available to `--format` and structured output.
+const STREAM_FIELDS: &[&str] = &[
+    "profile",
+    "region",
+    "group",
+    "name",
+    "creationTime",
+    "lastEventTimestamp",
+    "storedBytes",
+    "arn",
+];
+
+fn parse_stream_template(template: &str) -> Result<Template, String> {
+    Template::parse(template, STREAM_FIELDS)
+}
+
+/// The sort order of DescribeLogStreams.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum StreamOrder {
+    /// By log stream name.
+    #[default]
+    Name,
+    /// By the timestamp of the most recent event.
+    LastEvent,
+}
+
+impl From<StreamOrder> for OrderBy {
+    fn from(order: StreamOrder) -> Self {
+        match order {
+            StreamOrder::Name => OrderBy::LogStreamName,
+            StreamOrder::LastEvent => OrderBy::LastEventTime,
+        }
+    }
+}
+
+/// Options that select and order the listed streams.
+#[derive(Debug, Clone, Default, Args)]
+pub struct StreamRequestOpt {
+    /// How the streams are sorted.
+    #[arg(long, value_enum, default_value_t)]
+    pub order_by: StreamOrder,
+
+    /// Sorts in descending order, e.g. the most recently active streams first.
+    #[arg(long)]
+    pub descending: bool,
+
+    /// Only lists streams whose names start with this prefix, e.g. `2024/05/01/[$LATEST]`.
+    /// Cannot be combined with `--order-by last-event`.
+    #[arg(long)]
+    pub prefix: Option<String>,
+
+    /// Only lists streams whose names match this regular expression.
+    /// Applied client-side while paginating, so it works with any naming scheme.
+    #[arg(long = "match", value_name = "REGEX")]
+    pub name_match: Option<Regex>,
+
+    /// Stops paginating once this many streams have been listed.
+    #[arg(long, value_name = "N")]
+    pub limit: Option<usize>,
+
+    /// Only lists streams that received an event within this duration, e.g. `24h`.
+    /// CloudWatch updates lastEventTimestamp lazily, so it can lag by up to an hour.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub active_within: Option<Duration>,
+
+    /// How many streams each DescribeLogStreams call returns (1-50, the service default is 50).
+    /// Smaller pages mean more calls but less work per call when throttled.
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..=50))]
+    pub page_size: Option<i32>,
+
+    /// Lists the streams under each of these name prefixes at the same time, e.g. `2024/,2025/`,
+    /// which is much faster for log groups with 100,000s of streams. `hex` stands for the 16
+    /// prefixes `0` to `f`. Added after `--prefix`. Streams under none of them are not listed.
+    /// `--max-tps` still limits the calls of all of them together.
+    #[arg(long, value_name = "PREFIX", value_delimiter = ',')]
+    pub shard_by_prefix: Vec<String>,
+}
+
+impl StreamRequestOpt {
+    /// Checks the combinations that DescribeLogStreams rejects.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.prefix.is_some() && self.order_by == StreamOrder::LastEvent {
+            return Err("--prefix cannot be used with --order-by last-event".to_string());
+        }
+        if !self.shard_by_prefix.is_empty() && self.order_by == StreamOrder::LastEvent {
+            return Err("--shard-by-prefix cannot be used with --order-by last-event".to_string());
+        }
+        // 重なるシャードがあると同じストリームを二度数えてしまう
+        let shards = self.shard_prefixes();
+        for (i, shard) in shards.iter().enumerate() {
+            if let Some(other) = shards[i + 1..]
+                .iter()
+                .find(|other| other.starts_with(shard))
+            {
+                return Err(format!(
+                    "--shard-by-prefix {} and {} overlap",
+                    quote_shard(shard),
+                    quote_shard(other)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The prefixes of `--shard-by-prefix` with `hex` expanded, sorted by name.
+    fn shard_prefixes(&self) -> Vec<String> {
+        let mut shards: Vec<String> = self
+            .shard_by_prefix
+            .iter()
+            .flat_map(|shard| match shard.as_str() {
+                "hex" => "0123456789abcdef".chars().map(String::from).collect(),
+                _ => vec![shard.clone()],
+            })
+            .collect();
+        shards.sort();
+        shards.dedup();
+        shards
+    }
+
+    /// A request for each shard of `--shard-by-prefix`, in the order their streams are listed:
+    /// shards that do not overlap list their streams in the same order as one listing would.
+    fn shards(&self) -> Vec<StreamRequestOpt> {
+        let mut shards = self.shard_prefixes();
+        if self.descending {
+            shards.reverse();
+        }
+        shards
+            .into_iter()
+            .map(|shard| StreamRequestOpt {
+                prefix: Some(format!(
+                    "{}{}",
+                    self.prefix.as_deref().unwrap_or_default(),
+                    shard
+                )),
+                shard_by_prefix: Vec::new(),
+                ..self.clone()
+            })
+            .collect()
+    }
+
+    /// Whether `count` streams are enough to stop paginating.
+    fn reached_limit(&self, count: usize) -> bool {
+        self.limit.is_some_and(|limit| count >= limit)
+    }
+
+    /// The oldest lastEventTimestamp that `--active-within` accepts.
+    fn active_cutoff(&self) -> Option<i64> {
+        self.active_within
+            .map(|d| now_millis() - d.as_millis() as i64)
+    }
+
+    /// Whether the stream is recent enough for `--active-within`.
+    /// Empty streams have no lastEventTimestamp and are never active.
+    fn is_active(&self, stream: &LogStream) -> bool {
+        match self.active_cutoff() {
+            Some(cutoff) => stream
+                .last_event_timestamp()
+                .is_some_and(|last| last >= cutoff),
+            None => true,
+        }
+    }
+
+    /// Whether every remaining stream is known to be inactive, which is the case once
+    /// streams sorted by last event in descending order fall behind the cutoff.
+    fn rest_inactive(&self, stream: &LogStream) -> bool {
+        self.order_by == StreamOrder::LastEvent && self.descending && !self.is_active(stream)
+    }
+
+    /// Whether the stream passes the client-side filters.
+    fn accepts(&self, stream: &LogStream) -> bool {
+        let name_matches = match &self.name_match {
+            Some(re) => re.is_match(stream.log_stream_name().unwrap_or_default()),
+            None => true,
+        };
+        name_matches && self.is_active(stream)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ListStreamsOpt {
+    #[command(flatten)]
+    pub groups: GroupOpt,
+
+    #[command(flatten)]
+    pub request: StreamRequestOpt,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+
+    /// Prints stream ARNs instead of names in text output and adds an ARN column to tables.
+    /// Structured formats always include the ARN.
+    #[arg(long)]
+    pub show_arn: bool,
+
+    /// Prints each stream with a template such as `{name}\t{lastEventTimestamp}`.
+    /// Fields: name, creationTime, lastEventTimestamp, arn, storedBytes (always 0, as the service
+    /// no longer reports it per stream), and profile, region and group when listing with several
+    /// profiles, Regions or log groups.
+    #[arg(long, conflicts_with = "output", value_parser = parse_stream_template)]
+    pub format: Option<Template>,
+
+    /// Lists the streams again every INTERVAL, e.g. `30s`, until interrupted. Each listing marks
+    /// the streams that are new (`+`), gone (`-`) or have had events since the one before (`*`),
+    /// with the time of their last event. Text output only.
+    #[arg(long, value_name = "INTERVAL", value_parser = parse_duration, conflicts_with = "format")]
+    pub watch: Option<Duration>,
+}
+
+impl ListStreamsOpt {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.watch.is_some() && self.output != OutputFormat::Text {
+            return Err("--watch only prints text output".to_string());
+        }
+        self.request.validate()
+    }
+}
+
+/// Paginates DescribeLogStreams and hands every accepted stream to `on_stream`
+/// as soon as its page arrives, reporting each page to `progress`.
+/// Returns how many streams were accepted.
+pub async fn for_each_stream(
+    client: &impl LogsApi,
+    log_group_name: &str,
+    request: &StreamRequestOpt,
+    progress: &Progress,
+    mut on_stream: impl FnMut(&LogStream),
+) -> Result<usize, aws_sdk_cloudwatchlogs::Error> {
+    if request.shard_by_prefix.is_empty() {
+        return for_each_page(client, log_group_name, request, progress, on_stream).await;
+    }
+    // シャードは並行して読むが、名前順が崩れないようシャードの順に渡す。
+    // 先頭のシャードのストリームは届いたそばから渡し、後のシャードの分だけを溜めておく
+    let shards = request.shards();
+    let (senders, receivers): (Vec<_>, Vec<_>) =
+        shards.iter().map(|_| mpsc::unbounded_channel()).unzip();
+    let fetch = stream::iter(shards.iter().zip(senders)).for_each_concurrent(
+        SHARD_CONCURRENCY,
+        |(shard, sender)| async move {
+            let result = for_each_page(client, log_group_name, shard, progress, |stream| {
+                let _ = sender.send(Ok(stream.clone()));
+            })
+            .await;
+            if let Err(e) = result {
+                let _ = sender.send(Err(e));
+            }
+        },
+    );
+    let receive = async {
+        let mut count = 0;
+        'shards: for mut receiver in receivers {
+            while let Some(stream) = receiver.recv().await {
+                on_stream(&stream?);
+                count += 1;
+                if request.reached_limit(count) {
+                    break 'shards;
+                }
+            }
+        }
+        Ok(count)
+    };
+    // 上限に達するかエラーで受け取りを終えたら、読みかけのシャードは破棄してそれ以上呼ばない
+    tokio::select! {
+        result = receive => result,
+        _ = async { fetch.await; future::pending::<()>().await } => unreachable!(),
+    }
+}
+
+/// How many shards of `--shard-by-prefix` are listed at once.
+const SHARD_CONCURRENCY: usize = 8;
+
+/// Shows a shard that is empty, and so overlaps every other.
+fn quote_shard(shard: &str) -> &str {
+    match shard {
+        "" => "\"\"",
+        shard => shard,
+    }
+}
+
+/// [`for_each_stream`] for a single listing, without shards.
+async fn for_each_page(
+    client: &impl LogsApi,
+    log_group_name: &str,
+    request: &StreamRequestOpt,
+    progress: &Progress,
+    mut on_stream: impl FnMut(&LogStream),
+) -> Result<usize, aws_sdk_cloudwatchlogs::Error> {
+    let mut next_token: Option<String> = None;
+    let mut count = 0;
+
+    'pages: while !request.reached_limit(count) {
+        let result = client
+            .describe_log_streams(log_group_name, request, next_token.take())
+            .await;
+        let page = match result {
+            Ok(page) => page,
+            // ロググループがなければ続けても無駄なので呼び出し元に返す
+            Err(e) if is_not_found(&e) => return Err(e),
+            Err(e) => {
+                // エラーの場合は警告を表示し、SDK のページネーターと同じくそこで打ち切る
+                progress.clear();
+                report::warn("Failed to retrieve a log stream", &e);
+                progress.error();
+                break;
+            }
+        };
+        progress.clear();
+        let before = count;
+        for log_stream in page.log_streams() {
+            if request.reached_limit(count) || request.rest_inactive(log_stream) {
+                progress.page(count - before);
+                break 'pages;
+            }
+            if request.accepts(log_stream) {
+                on_stream(log_stream);
+                count += 1;
+            }
+        }
+        progress.page(count - before);
+        next_token = page.next_token.filter(|token| !token.is_empty());
+        if next_token.is_none() {
+            break;
+        }
+    }
+    Ok(count)
+}
+
+/// Lists the name of every stream in the log group.
+pub async fn stream_names(
+    client: &impl LogsApi,
+    log_group_name: &str,
+    progress: &Progress,
+) -> Result<Vec<String>, aws_sdk_cloudwatchlogs::Error> {
+    let mut names = Vec::new();
+    let request = StreamRequestOpt::default();
+    for_each_stream(client, log_group_name, &request, progress, |stream| {
+        names.extend(stream.log_stream_name().map(str::to_string))
+    })
+    .await?;
+    Ok(names)
+}
+
+/// Lists the log streams of a log group, for embedding in other tools.
+///
+/// The options are those of `list-streams`, set with the `with_` methods.
+/// Any [`LogsApi`] can be listed from, such as an SDK [`Client`] or a [`MemoryLogs`](crate::MemoryLogs).
+#[derive(Debug, Clone)]
+pub struct StreamLister<C = Client> {
+    client: C,
+    group: String,
+    request: StreamRequestOpt,
+}
+
+impl<C: LogsApi> StreamLister<C> {
+    /// Lists the streams of `group`, a log group name or ARN, sorted by name.
+    pub fn new(client: C, group: impl Into<String>) -> Self {
+        StreamLister {
+            client,
+            group: group.into(),
+            request: StreamRequestOpt::default(),
+        }
+    }
+
+    pub fn with_order_by(mut self, order_by: StreamOrder) -> Self {
+        self.request.order_by = order_by;
+        self
+    }
+
+    pub fn with_descending(mut self, descending: bool) -> Self {
+        self.request.descending = descending;
+        self
+    }
+
+    /// Only lists streams whose names start with `prefix`. Cannot be used with [`StreamOrder::LastEvent`].
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.request.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Only lists streams whose names match `name_match`, checked while paginating.
+    pub fn with_name_match(mut self, name_match: Regex) -> Self {
+        self.request.name_match = Some(name_match);
+        self
+    }
+
+    /// Stops paginating once `limit` streams have been listed.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.request.limit = Some(limit);
+        self
+    }
+
+    /// Only lists streams that received an event within `active_within`.
+    pub fn with_active_within(mut self, active_within: Duration) -> Self {
+        self.request.active_within = Some(active_within);
+        self
+    }
+
+    /// Lists the streams under each of `shards`, name prefixes after the one of
+    /// [`with_prefix`](Self::with_prefix), concurrently. Cannot be used with [`StreamOrder::LastEvent`].
+    pub fn with_shard_by_prefix<S: Into<String>>(
+        mut self,
+        shards: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.request.shard_by_prefix = shards.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Paginates DescribeLogStreams and returns the accepted streams.
+    /// Pages that fail after the SDK's retries are reported as warnings and skipped.
+    pub async fn list(&self) -> Result<Vec<LogStream>, Error> {
+        self.request.validate().map_err(Error::InvalidRequest)?;
+        let mut streams = Vec::new();
+        for_each_stream(
+            &self.client,
+            &self.group,
+            &self.request,
+            &Progress::hidden(),
+            |stream| streams.push(stream.clone()),
+        )
+        .await
+        .map_err(|e| Error::from_service(&self.group, e))?;
+        Ok(streams)
+    }
+}
+
+// snippet-end:[cloudwatchlogs.rust.list-log-streams]
+
+/// Describes a log stream for structured output. Times are epoch milliseconds.
+/// `group` is only given when several log groups are listed.
+// storedBytes はストリーム単位では非推奨で、2019年以降は常に0が返る
+#[allow(deprecated)]
+fn stream_record(target: &Target, group: Option<&str>, stream: &LogStream) -> Record {
+    let record = match group {
+        Some(group) => target.record().field("group", group),
+        None => target.record(),
+    };
+    record
+        .field("name", stream.log_stream_name())
+        .field("creationTime", stream.creation_time())
+        .field("lastEventTimestamp", stream.last_event_timestamp())
+        .field("storedBytes", stream.stored_bytes())
+        .field("arn", stream.arn())
+}
+
+/// Describes a log stream as a row of the `table` output.
+/// There is no size column, as DescribeLogStreams no longer reports the size of a stream.
+fn stream_table_row(
+    target: &Target,
+    group: Option<&str>,
+    stream: &LogStream,
+    show_arn: bool,
+) -> Record {
+    let row = match group {
+        Some(group) => target.table_row().field("GROUP", group),
+        None => target.table_row(),
+    };
+    let row = row
+        .field("NAME", stream.log_stream_name())
+        .field(
+            "LAST EVENT",
+            stream
+                .last_event_timestamp()
+                .map(format_timestamp)
+                .as_deref(),
+        )
+        .field(
+            "CREATED",
+            stream.creation_time().map(format_timestamp).as_deref(),
+        );
+    match show_arn {
+        true => row.field("ARN", stream.arn()),
+        false => row,
+    }
+}
+
+/// Prints streams one by one while pagination is still running.
+/// Only `table` output has to hold on to every row to align its columns.
+struct StreamPrinter {
+    output: OutputFormat,
+    template: Option<Template>,
+    verbose: bool,
+    show_arn: bool,
+    count: usize,
+    table_rows: Vec<Record>,
+}
+
+impl StreamPrinter {
+    /// Verbose text and table output end with the stored bytes of the listed log groups.
+    fn new(
+        output: OutputFormat,
+        template: Option<Template>,
+        verbose: bool,
+        show_arn: bool,
+    ) -> Self {
+        StreamPrinter {
+            output,
+            template,
+            verbose,
+            show_arn,
+            count: 0,
+            table_rows: Vec::new(),
+        }
+    }
+
+    /// Prints a stream of `target`, labelled with its `group` when several log groups are listed.
+    fn print(&mut self, target: &Target, group: Option<&str>, stream: &LogStream) {
+        let first = self.count == 0;
+        self.count += 1;
+        if let Some(template) = &self.template {
+            outln!("{}", template.render(&stream_record(target, group, stream)));
+            return;
+        }
+        match self.output {
+            OutputFormat::Text => {
+                let prefix = match group {
+                    Some(group) => format!("{}{}\t", target.text_prefix(), group),
+                    None => target.text_prefix(),
+                };
+                let name = match self.show_arn {
+                    true => stream.arn().ok_or("No stream ARN found"),
+                    false => stream.log_stream_name().ok_or("No stream name found"),
+                };
+                match name {
+                    Ok(name) => outln!("{}{}", prefix, name),
+                    Err(missing) => outln!("{}{}", prefix, missing),
+                }
+            }
+            OutputFormat::Json => {
+                let separator = if first { "[" } else { "," };
+                outln!(
+                    "{}{}",
+                    separator,
+                    stream_record(target, group, stream).to_json()
+                );
+            }
+            OutputFormat::Ndjson => outln!("{}", stream_record(target, group, stream).to_json()),
+            OutputFormat::Csv => out!(
+                "{}",
+                output::to_csv(&[stream_record(target, group, stream)], first)
+            ),
+            OutputFormat::Yaml => out!(
+                "{}",
+                output::to_yaml_items(&[stream_record(target, group, stream)])
+            ),
+            OutputFormat::Table => {
+                self.table_rows
+                    .push(stream_table_row(target, group, stream, self.show_arn))
+            }
+        }
+    }
+
+    /// Whether [`finish`](Self::finish) prints the stored bytes of the log groups.
+    fn shows_stored_bytes(&self) -> bool {
+        self.template.is_none()
+            && !output::is_quiet()
+            && match self.output {
+                OutputFormat::Text => self.verbose,
+                OutputFormat::Table => true,
+                _ => false,
+            }
+    }
+
+    /// Closes the output and, for text, prints the count. `stored_bytes` is the total of the
+    /// log groups when [`shows_stored_bytes`](Self::shows_stored_bytes), or `None` if a group
+    /// could not be sized.
+    fn finish(self, stored_bytes: Option<i64>) {
+        if self.template.is_some() {
+            return;
+        }
+        let stored_bytes = stored_bytes.map_or("unavailable".to_string(), format_bytes);
+        match self.output {
+            OutputFormat::Text if output::is_quiet() => {}
+            OutputFormat::Text => {
+                outln!("Found {} streams.", self.count);
+                if self.verbose {
+                    outln!("Stored bytes of the log groups: {}", stored_bytes);
+                }
+            }
+            OutputFormat::Json if self.count == 0 => outln!("[]"),
+            OutputFormat::Json => outln!("]"),
+            OutputFormat::Yaml if self.count == 0 => outln!("[]"),
+            OutputFormat::Table => {
+                out!("{}", output::to_table(&self.table_rows));
+                if !output::is_quiet() {
+                    outln!("Stored bytes of the log groups: {}", stored_bytes);
+                }
+            }
+            OutputFormat::Ndjson | OutputFormat::Csv | OutputFormat::Yaml => {}
+        }
+    }
+}
+
+/// Prints every log stream in the log groups, listing concurrently when there are several
+/// targets or log groups.
+pub async fn run(
+    targets: &[Target],
+    opt: ListStreamsOpt,
+    verbose: bool,
+    color: bool,
+) -> Result<(), Error> {
+    let ListStreamsOpt {
+        groups,
+        request,
+        output,
+        show_arn,
+        format,
+        watch,
+    } = opt;
+
+    if verbose {
+        groups.print_verbose();
+        eprintln!();
+    }
+
+    // リージョンごとにロググループが異なりうるので、プレフィックスはターゲットごとに解決する
+    let resolving = Progress::new("groups");
+    let resolved = join_all(
+        targets
+            .iter()
+            .map(|target| groups.resolve(target, &resolving)),
+    )
+    .await;
+    drop(resolving);
+    let group_lists = resolved.into_iter().collect::<Result<Vec<_>, _>>()?;
+    groups.ensure_found(group_lists.iter().map(Vec::len).sum())?;
+    if let Some(interval) = watch {
+        return watch_streams(targets, &group_lists, &request, show_arn, interval, color).await;
+    }
+
+    // 各リージョンのページは届いた順に表示する。同じタスク内で動くので RefCell で足りる
+    let printer = RefCell::new(StreamPrinter::new(output, format, verbose, show_arn));
+    let progress = Progress::new("streams");
+    let (printer_ref, request, progress) = (&printer, &request, &progress);
+    let label_groups = group_lists.iter().any(|groups| groups.len() > 1);
+    let listings = targets
+        .iter()
+        .zip(&group_lists)
+        .flat_map(|(target, groups)| {
+            groups.iter().map(move |group| {
+                let label = label_groups.then_some(group.as_str());
+                for_each_stream(&target.client, group, request, progress, move |stream| {
+                    printer_ref.borrow_mut().print(target, label, stream)
+                })
+            })
+        });
+    for result in join_all(listings).await {
+        result?;
+    }
+    progress.clear();
+    let printer = printer.into_inner();
+    // ストリームごとの storedBytes は常に 0 なので、ロググループの値を合計する
+    let stored_bytes = match printer.shows_stored_bytes() {
+        true => total_stored_bytes(targets, &group_lists).await,
+        false => None,
+    };
+    printer.finish(stored_bytes);
+    Ok(())
+}
+
+/// The total `storedBytes` of the log groups, or `None` if any of them could not be sized.
+async fn total_stored_bytes(targets: &[Target], group_lists: &[Vec<String>]) -> Option<i64> {
+    let sizes = join_all(
+        targets
+            .iter()
+            .zip(group_lists)
+            .flat_map(|(target, groups)| {
+                groups
+                    .iter()
+                    .map(|group| group_stored_bytes(&target.client, group))
+            }),
+    )
+    .await;
+    let mut total = 0;
+    for size in sizes {
+        match size {
+            Ok(size) => total += size?,
+            Err(e) => {
+                report::warn("Failed to retrieve the stored bytes of a log group", &e);
+                return None;
+            }
+        }
+    }
+    Some(total)
+}
+
+/// Lists the streams of the log groups every `interval` and prints what changed, until interrupted.
+async fn watch_streams(
+    targets: &[Target],
+    group_lists: &[Vec<String>],
+    request: &StreamRequestOpt,
+    show_arn: bool,
+    interval: Duration,
+    color: bool,
+) -> Result<(), Error> {
+    let label_groups = group_lists.iter().any(|groups| groups.len() > 1);
+    let mut watch = Watch::new(interval, color);
+    loop {
+        let listings = targets
+            .iter()
+            .zip(group_lists)
+            .flat_map(|(target, groups)| {
+                groups.iter().map(move |group| async move {
+                    let label = label_groups.then_some(group.as_str());
+                    let mut entries = Vec::new();
+                    for_each_stream(
+                        &target.client,
+                        group,
+                        request,
+                        &Progress::hidden(),
+                        |stream| entries.push(watch_entry(target, label, stream, show_arn)),
+                    )
+                    .await?;
+                    Ok::<_, aws_sdk_cloudwatchlogs::Error>(entries)
+                })
+            });
+        // 表示順が毎回変わらないよう、ロググループの順につなげる
+        let mut entries = Vec::new();
+        for result in join_all(listings).await {
+            entries.extend(result?);
+        }
+        watch.print("streams", entries)?;
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// A stream as a line of `--watch`: its name, or ARN, and the time of its last event.
+fn watch_entry(target: &Target, group: Option<&str>, stream: &LogStream, show_arn: bool) -> Entry {
+    let mut key = target.text_prefix();
+    if let Some(group) = group {
+        key.push_str(&format!("{}\t", group));
+    }
+    let name = match show_arn {
+        true => stream.arn(),
+        false => stream.log_stream_name(),
+    };
+    key.push_str(name.unwrap_or("-"));
+    let last_event = stream.last_event_timestamp();
+    let line = format!(
+        "{}\t{}",
+        key,
+        last_event.map(format_timestamp).as_deref().unwrap_or("-")
+    );
+    Entry {
+        key,
+        line,
+        last_event,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs_api::MemoryLogs;
+
+    fn names(streams: &[LogStream]) -> Vec<&str> {
+        streams
+            .iter()
+            .filter_map(|stream| stream.log_stream_name())
+            .collect()
+    }
+
+    fn five_streams() -> MemoryLogs {
+        ["a", "b", "c", "d", "e"]
+            .iter()
+            .enumerate()
+            .fold(MemoryLogs::new(), |logs, (i, name)| {
+                logs.with_event("app", *name, i as i64, "message")
+            })
+    }
+
+    #[tokio::test]
+    async fn limit_stops_paginating() {
+        let logs = five_streams().with_page_size(2);
+        let streams = StreamLister::new(&logs, "app")
+            .with_limit(3)
+            .list()
+            .await
+            .unwrap();
+        assert_eq!(names(&streams), ["a", "b", "c"]);
+        assert_eq!(logs.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn active_within_stops_at_the_first_inactive_stream() {
+        let now = now_millis();
+        let logs = MemoryLogs::new()
+            .with_event("app", "old", now - 3_600_000, "message")
+            .with_event("app", "older", now - 7_200_000, "message")
+            .with_event("app", "recent", now - 1_000, "message")
+            .with_event("app", "oldest", now - 10_800_000, "message")
+            .with_page_size(1);
+        let streams = StreamLister::new(&logs, "app")
+            .with_order_by(StreamOrder::LastEvent)
+            .with_descending(true)
+            .with_active_within(Duration::from_secs(600))
+            .list()
+            .await
+            .unwrap();
+        assert_eq!(names(&streams), ["recent"]);
+        assert_eq!(logs.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_page_keeps_the_streams_before_it() {
+        let logs = five_streams().with_page_size(2).with_failing_call(1);
+        let streams = StreamLister::new(&logs, "app").list().await.unwrap();
+        assert_eq!(names(&streams), ["a", "b"]);
+        assert_eq!(logs.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn shards_keep_name_order_up_to_the_limit() {
+        let logs = ["b1", "a1", "b2", "a2", "c1"]
+            .iter()
+            .fold(MemoryLogs::new(), |logs, name| {
+                logs.with_event("app", *name, 1, "message")
+            })
+            .with_page_size(1);
+        let lister = StreamLister::new(&logs, "app").with_shard_by_prefix(["a", "b"]);
+        let streams = lister.list().await.unwrap();
+        assert_eq!(names(&streams), ["a1", "a2", "b1", "b2"]);
+        let streams = lister.with_limit(3).list().await.unwrap();
+        assert_eq!(names(&streams), ["a1", "a2", "b1"]);
+    }
+
+    #[tokio::test]
+    async fn missing_group_is_an_error() {
+        let logs = MemoryLogs::new().with_group("app");
+        assert!(StreamLister::new(&logs, "other").list().await.is_err());
+    }
+}
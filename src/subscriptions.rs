@@ -0,0 +1,360 @@
+use aws_sdk_cloudwatchlogs::{
+    types::{Distribution, SubscriptionFilter},
+    Client,
+};
+use clap::{Args, Subcommand, ValueEnum};
+use futures_util::{stream, StreamExt};
+
+use crate::confirm::ConfirmOpt;
+use crate::error::Error;
+use crate::groups::{get_groups, GroupRequestOpt};
+use crate::outln;
+use crate::output::{self, format_timestamp, OutputFormat, Record};
+use crate::progress::Progress;
+use crate::report;
+
+/// How many log groups are checked with DescribeSubscriptionFilters at the same time.
+const LIST_CONCURRENCY: usize = 5;
+
+#[derive(Debug, Args)]
+pub struct SubscriptionsOpt {
+    #[command(subcommand)]
+    pub command: SubscriptionsCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SubscriptionsCommand {
+    /// Lists the subscription filters of a log group, or of every log group.
+    List(ListSubscriptionsOpt),
+
+    /// Creates or replaces a subscription filter that forwards matching events.
+    Put(PutSubscriptionOpt),
+
+    /// Deletes a subscription filter.
+    Delete(DeleteSubscriptionOpt),
+}
+
+#[derive(Debug, Args)]
+pub struct ListSubscriptionsOpt {
+    /// The log group name. Every log group is checked if not supplied.
+    #[arg(short, long)]
+    pub group: Option<String>,
+
+    /// Only checks log groups whose names start with this prefix.
+    #[arg(long, value_name = "PREFIX", conflicts_with = "group")]
+    pub group_prefix: Option<String>,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+/// How events are spread over the shards of a Kinesis destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SubscriptionDistribution {
+    /// Events of the same log stream go to the same shard.
+    ByLogStream,
+    /// Events are spread evenly.
+    Random,
+}
+
+impl From<SubscriptionDistribution> for Distribution {
+    fn from(distribution: SubscriptionDistribution) -> Self {
+        match distribution {
+            SubscriptionDistribution::ByLogStream => Distribution::ByLogStream,
+            SubscriptionDistribution::Random => Distribution::Random,
+        }
+    }
+}
+
+/// The kind of destination a subscription filter delivers to, e.g. `kinesis`.
+fn destination_type(arn: &str) -> Option<&'static str> {
+    let parts: Vec<&str> = arn.splitn(7, ':').collect();
+    let [partition_arn, _, service, region, account, resource, rest @ ..] = parts.as_slice() else {
+        return None;
+    };
+    if *partition_arn != "arn"
+        || region.is_empty()
+        || !(account.len() == 12 && account.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return None;
+    }
+    let named = |prefix: &str| {
+        resource
+            .strip_prefix(prefix)
+            .is_some_and(|name| !name.is_empty())
+    };
+    match *service {
+        "kinesis" if named("stream/") => Some("kinesis"),
+        "firehose" if named("deliverystream/") => Some("firehose"),
+        "lambda" if *resource == "function" && rest.first().is_some_and(|f| !f.is_empty()) => {
+            Some("lambda")
+        }
+        "logs" if *resource == "destination" && rest.first().is_some_and(|d| !d.is_empty()) => {
+            Some("logs")
+        }
+        _ => None,
+    }
+}
+
+/// Checks that a destination ARN names a Kinesis stream, Firehose delivery stream,
+/// Lambda function or CloudWatch Logs destination.
+fn parse_destination(arn: &str) -> Result<String, String> {
+    match destination_type(arn) {
+        Some(_) => Ok(arn.to_string()),
+        None => Err(format!(
+            "`{}` is not the ARN of a Kinesis stream, Firehose delivery stream, \
+             Lambda function or CloudWatch Logs destination",
+            arn
+        )),
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct PutSubscriptionOpt {
+    /// The log group name.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// The name of the filter. An existing filter with this name is replaced.
+    #[arg(long)]
+    pub name: String,
+
+    /// The filter pattern that selects the forwarded events. Every event is forwarded if not supplied.
+    #[arg(long, default_value = "")]
+    pub pattern: String,
+
+    /// The ARN of the Kinesis stream, Firehose delivery stream, Lambda function
+    /// or CloudWatch Logs destination to forward to.
+    #[arg(long, value_name = "ARN", value_parser = parse_destination)]
+    pub destination: String,
+
+    /// The IAM role that CloudWatch Logs assumes to write to a Kinesis or Firehose destination.
+    #[arg(long, value_name = "ARN")]
+    pub destination_role: Option<String>,
+
+    /// How events are spread over the shards of a Kinesis destination.
+    #[arg(long, value_enum)]
+    pub distribution: Option<SubscriptionDistribution>,
+}
+
+#[derive(Debug, Args)]
+pub struct DeleteSubscriptionOpt {
+    /// The log group name.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// The name of the filter to delete.
+    #[arg(long)]
+    pub name: String,
+
+    #[command(flatten)]
+    pub confirm: ConfirmOpt,
+}
+
+impl SubscriptionsOpt {
+    /// Checks option combinations that clap cannot express on its own.
+    pub fn validate(&self) -> Result<(), String> {
+        let SubscriptionsCommand::Put(opt) = &self.command else {
+            return Ok(());
+        };
+        let streaming = matches!(
+            destination_type(&opt.destination),
+            Some("kinesis" | "firehose")
+        );
+        if streaming && opt.destination_role.is_none() {
+            return Err(
+                "--destination-role is required for Kinesis and Firehose destinations".to_string(),
+            );
+        }
+        if opt.distribution.is_some() && destination_type(&opt.destination) != Some("kinesis") {
+            return Err("--distribution can only be used with Kinesis destinations".to_string());
+        }
+        Ok(())
+    }
+
+    /// The log group that the subcommand works on, if it names one.
+    pub fn group(&self) -> Option<&str> {
+        match &self.command {
+            SubscriptionsCommand::List(opt) => opt.group.as_deref(),
+            SubscriptionsCommand::Put(opt) => Some(&opt.group),
+            SubscriptionsCommand::Delete(opt) => Some(&opt.group),
+        }
+    }
+}
+
+/// Describes a subscription filter for structured output.
+fn subscription_record(filter: &SubscriptionFilter) -> Record {
+    Record::new()
+        .field("logGroupName", filter.log_group_name())
+        .field("name", filter.filter_name())
+        .field(
+            "destinationType",
+            filter.destination_arn().and_then(destination_type),
+        )
+        .field("destinationArn", filter.destination_arn())
+        .field("filterPattern", filter.filter_pattern())
+        .field("roleArn", filter.role_arn())
+        .field("distribution", filter.distribution().map(|d| d.as_str()))
+        .field("creationTime", filter.creation_time())
+}
+
+/// Describes a subscription filter as a row of the `table` output.
+fn subscription_table_row(filter: &SubscriptionFilter) -> Record {
+    Record::new()
+        .field("GROUP", filter.log_group_name())
+        .field("NAME", filter.filter_name())
+        .field("DESTINATION", filter.destination_arn())
+        .field(
+            "CREATED",
+            filter.creation_time().map(format_timestamp).as_deref(),
+        )
+        .field("PATTERN", filter.filter_pattern())
+}
+
+/// The subscription filters of one log group.
+async fn group_subscriptions(
+    client: &Client,
+    group: &str,
+) -> Result<Vec<SubscriptionFilter>, aws_sdk_cloudwatchlogs::Error> {
+    let mut pages = client
+        .describe_subscription_filters()
+        .log_group_name(group)
+        .into_paginator()
+        .send();
+    let mut filters = Vec::new();
+    while let Some(page) = pages.next().await {
+        filters.extend(page?.subscription_filters.unwrap_or_default());
+    }
+    Ok(filters)
+}
+
+/// Prints the subscription filters of a log group, or of every log group that has any.
+async fn list_subscriptions(client: &Client, opt: ListSubscriptionsOpt) -> Result<(), Error> {
+    let ListSubscriptionsOpt {
+        group,
+        group_prefix,
+        output,
+    } = opt;
+
+    let filters = match group {
+        Some(group) => group_subscriptions(client, &group).await?,
+        None => {
+            let progress = Progress::new("groups");
+            let request = GroupRequestOpt {
+                prefix: group_prefix,
+                ..Default::default()
+            };
+            let groups = get_groups(client, &request, &progress).await?;
+            let results: Vec<_> = stream::iter(&groups)
+                .filter_map(|group| async move { group.log_group_name() })
+                .map(|name| async move { (name, group_subscriptions(client, name).await) })
+                .buffered(LIST_CONCURRENCY)
+                .collect()
+                .await;
+            progress.clear();
+            let mut filters = Vec::new();
+            for (name, result) in results {
+                match result {
+                    Ok(found) => filters.extend(found),
+                    // 一覧の取得後に消されたグループなどは飛ばして続ける
+                    Err(e) => report::warn(
+                        &format!("Failed to retrieve the subscription filters of {}", name),
+                        &e,
+                    ),
+                }
+            }
+            filters
+        }
+    };
+
+    match output {
+        OutputFormat::Text => {
+            for filter in &filters {
+                outln!(
+                    "{}\t{}\t{}",
+                    filter.log_group_name().unwrap_or_default(),
+                    filter.filter_name().unwrap_or_default(),
+                    filter.destination_arn().unwrap_or_default()
+                );
+            }
+        }
+        format => output::print_records(
+            format,
+            &filters.iter().map(subscription_record).collect::<Vec<_>>(),
+            &filters
+                .iter()
+                .map(subscription_table_row)
+                .collect::<Vec<_>>(),
+        ),
+    }
+    Ok(())
+}
+
+/// Creates or replaces a subscription filter with PutSubscriptionFilter.
+async fn put_subscription(client: &Client, opt: PutSubscriptionOpt) -> Result<(), Error> {
+    let PutSubscriptionOpt {
+        group,
+        name,
+        pattern,
+        destination,
+        destination_role,
+        distribution,
+    } = opt;
+    client
+        .put_subscription_filter()
+        .log_group_name(&group)
+        .filter_name(&name)
+        .filter_pattern(pattern)
+        .destination_arn(&destination)
+        .set_role_arn(destination_role)
+        .set_distribution(distribution.map(Distribution::from))
+        .send()
+        .await?;
+    outln!(
+        "Put subscription filter {} on {} forwarding to {}",
+        name,
+        group,
+        destination
+    );
+    Ok(())
+}
+
+/// Deletes a subscription filter with DeleteSubscriptionFilter.
+async fn delete_subscription(client: &Client, opt: DeleteSubscriptionOpt) -> Result<(), Error> {
+    let DeleteSubscriptionOpt {
+        group,
+        name,
+        confirm,
+    } = opt;
+    confirm.confirm(
+        &format!(
+            "About to delete the subscription filter {} of {}. Its events stop being forwarded.",
+            name, group
+        ),
+        &[],
+    )?;
+    client
+        .delete_subscription_filter()
+        .log_group_name(&group)
+        .filter_name(&name)
+        .send()
+        .await?;
+    outln!("Deleted subscription filter {} of {}", name, group);
+    Ok(())
+}
+
+/// Manages subscription filters.
+pub async fn run(client: &Client, opt: SubscriptionsOpt, verbose: bool) -> Result<(), Error> {
+    if verbose {
+        if let Some(group) = opt.group() {
+            eprintln!("Log group name:                {}", group);
+        }
+        eprintln!();
+    }
+    match opt.command {
+        SubscriptionsCommand::List(opt) => list_subscriptions(client, opt).await,
+        SubscriptionsCommand::Put(opt) => put_subscription(client, opt).await,
+        SubscriptionsCommand::Delete(opt) => delete_subscription(client, opt).await,
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use aws_sdk_cloudwatchlogs::Client;
+use clap::{Args, Subcommand};
+
+use crate::confirm::ConfirmOpt;
+use crate::error::Error;
+use crate::groups::resolve_group_arn;
+use crate::manage::parse_tag;
+use crate::output::{self, OutputFormat, Record};
+use crate::{out, outln};
+
+#[derive(Debug, Args)]
+pub struct TagsOpt {
+    #[command(subcommand)]
+    pub command: TagsCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TagsCommand {
+    /// Prints the tags of a log group.
+    List(ListTagsOpt),
+
+    /// Adds tags to a log group, replacing the values of existing keys.
+    Add(AddTagsOpt),
+
+    /// Removes tags from a log group.
+    Remove(RemoveTagsOpt),
+}
+
+#[derive(Debug, Args)]
+pub struct ListTagsOpt {
+    /// The log group name or ARN.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct AddTagsOpt {
+    /// The log group name or ARN.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// The tags to add, as `KEY=VALUE`.
+    #[arg(required = true, value_name = "KEY=VALUE", value_parser = parse_tag)]
+    pub tags: Vec<(String, String)>,
+}
+
+#[derive(Debug, Args)]
+pub struct RemoveTagsOpt {
+    /// The log group name or ARN.
+    #[arg(short, long)]
+    pub group: String,
+
+    /// The keys of the tags to remove.
+    #[arg(required = true, value_name = "KEY")]
+    pub keys: Vec<String>,
+
+    #[command(flatten)]
+    pub confirm: ConfirmOpt,
+}
+
+impl TagsOpt {
+    /// The log group that the subcommand works on.
+    pub fn group(&self) -> &str {
+        match &self.command {
+            TagsCommand::List(opt) => &opt.group,
+            TagsCommand::Add(opt) => &opt.group,
+            TagsCommand::Remove(opt) => &opt.group,
+        }
+    }
+}
+
+/// Prints the tags of a log group, sorted by key.
+async fn list_tags(client: &Client, opt: ListTagsOpt) -> Result<(), Error> {
+    let ListTagsOpt { group, output } = opt;
+    let arn = resolve_group_arn(client, &group).await?;
+    let mut tags: Vec<(String, String)> = client
+        .list_tags_for_resource()
+        .resource_arn(arn)
+        .send()
+        .await?
+        .tags
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    tags.sort();
+
+    let records = || {
+        tags.iter()
+            .map(|(key, value)| {
+                Record::new()
+                    .field("key", key.as_str())
+                    .field("value", value.as_str())
+            })
+            .collect::<Vec<_>>()
+    };
+    match output {
+        OutputFormat::Text => {
+            for (key, value) in &tags {
+                outln!("{}\t{}", key, value);
+            }
+        }
+        OutputFormat::Json => outln!("{}", output::to_json_array(&records())),
+        OutputFormat::Ndjson => {
+            for record in records() {
+                outln!("{}", record.to_json());
+            }
+        }
+        OutputFormat::Csv => out!("{}", output::to_csv(&records(), true)),
+        OutputFormat::Yaml => {
+            let records = records();
+            match records.is_empty() {
+                true => outln!("[]"),
+                false => out!("{}", output::to_yaml_items(&records)),
+            }
+        }
+        OutputFormat::Table => {
+            let rows: Vec<Record> = tags
+                .iter()
+                .map(|(key, value)| {
+                    Record::new()
+                        .field("KEY", key.as_str())
+                        .field("VALUE", value.as_str())
+                })
+                .collect();
+            out!("{}", output::to_table(&rows));
+        }
+    }
+    Ok(())
+}
+
+/// Adds tags to a log group with TagResource.
+async fn add_tags(client: &Client, opt: AddTagsOpt) -> Result<(), Error> {
+    let AddTagsOpt { group, tags } = opt;
+    let arn = resolve_group_arn(client, &group).await?;
+    let count = tags.len();
+    client
+        .tag_resource()
+        .resource_arn(arn)
+        .set_tags(Some(tags.into_iter().collect::<HashMap<_, _>>()))
+        .send()
+        .await?;
+    outln!("Added {} tags to {}", count, group);
+    Ok(())
+}
+
+/// Removes tags from a log group with UntagResource.
+async fn remove_tags(client: &Client, opt: RemoveTagsOpt) -> Result<(), Error> {
+    let RemoveTagsOpt {
+        group,
+        keys,
+        confirm,
+    } = opt;
+    let arn = resolve_group_arn(client, &group).await?;
+    confirm.confirm(
+        &format!("About to remove {} tags from {}:", keys.len(), group),
+        &keys,
+    )?;
+    let count = keys.len();
+    client
+        .untag_resource()
+        .resource_arn(arn)
+        .set_tag_keys(Some(keys))
+        .send()
+        .await?;
+    outln!("Removed {} tags from {}", count, group);
+    Ok(())
+}
+
+/// Manages the tags of a log group.
+pub async fn run(client: &Client, opt: TagsOpt, verbose: bool) -> Result<(), Error> {
+    if verbose {
+        eprintln!("Log group name:                {}", opt.group());
+        eprintln!();
+    }
+    match opt.command {
+        TagsCommand::List(opt) => list_tags(client, opt).await,
+        TagsCommand::Add(opt) => add_tags(client, opt).await,
+        TagsCommand::Remove(opt) => remove_tags(client, opt).await,
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Args;
+use futures_util::future::join_all;
+use regex_lite::Regex;
+
+use crate::checkpoint::Checkpoint;
+use crate::error::Error;
+use crate::events::{
+    filter_events, merge_by_timestamp, parse_event_template, EventFilter, EventPrinter, LogEvent,
+};
+use crate::groups::GroupOpt;
+use crate::jq;
+use crate::output::{self, OutputFormat, Template};
+use crate::progress::Progress;
+use crate::targets::Target;
+use crate::time_range::parse_timestamp;
+
+/// The most log streams that one FilterLogEvents call can name.
+const MAX_FILTERED_STREAMS: usize = 100;
+
+#[derive(Debug, Args)]
+pub struct TailOpt {
+    #[command(flatten)]
+    pub groups: GroupOpt,
+
+    /// Only show events from log streams whose names start with this prefix.
+    /// Cannot be used with `--ecs-cluster`, which names the streams.
+    #[arg(long, conflicts_with = "ecs_cluster")]
+    pub stream_prefix: Option<String>,
+
+    /// A CloudWatch Logs filter pattern, e.g. `ERROR` or `{ $.level = "error" }`.
+    #[arg(long)]
+    pub filter_pattern: Option<String>,
+
+    /// Where to start, e.g. `2h`, `now-10m` or `2024-05-01 13:00` (UTC).
+    #[arg(long, default_value = "10m", value_parser = parse_timestamp)]
+    pub since: i64,
+
+    /// Where to stop, in the same formats as `--since`. Cannot be used with `--follow`.
+    #[arg(long, conflicts_with = "follow", value_parser = parse_timestamp)]
+    pub until: Option<i64>,
+
+    /// Keep polling and print new events as they arrive.
+    #[arg(short, long)]
+    pub follow: bool,
+
+    /// Seconds to wait between polls in follow mode.
+    #[arg(long, default_value_t = 2)]
+    pub interval: u64,
+
+    /// The output format. In follow mode, `json` is printed as one object per line.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+
+    /// Prints the time since the previous event, such as `+12ms` or `+3.4s`, in text and table output.
+    #[arg(long)]
+    pub delta: bool,
+
+    /// Only prints events whose message matches this regular expression.
+    /// Applied client-side after fetching, so any regex syntax is allowed.
+    #[arg(long, value_name = "REGEX")]
+    pub grep: Option<Regex>,
+
+    /// Prints the events that do not match `--grep` instead.
+    #[arg(long, requires = "grep")]
+    pub invert_match: bool,
+
+    /// Applies a jq filter such as `select(.level == "error") | .msg` to each JSON message.
+    /// Events whose message is not JSON, or on which the filter fails, are skipped.
+    #[arg(long, value_name = "FILTER", value_parser = jq::Filter::parse)]
+    pub jq: Option<jq::Filter>,
+
+    /// Prints only these fields of JSON messages, separated by tabs, e.g. `level,requestId,msg`.
+    /// Nested fields are written as `ctx.id`. Other messages are printed as they are.
+    #[arg(long, value_delimiter = ',', conflicts_with = "jq")]
+    pub fields: Vec<String>,
+
+    /// Reads messages in API Gateway's default access log format, so that `--fields` and `--jq`
+    /// can pick out fields such as `status` or `resourcePath`. Without them, the fields are printed in columns.
+    #[arg(long)]
+    pub access_log: bool,
+
+    /// Prints each event with a template such as `{timestamp} {stream} {message}`.
+    /// Fields: timestamp, timestampMs, stream, eventId, message, and group when tailing several log groups.
+    #[arg(long, conflicts_with = "output", value_parser = parse_event_template)]
+    pub format: Option<Template>,
+
+    /// Records the last printed event of each stream in this file, and starts from there when
+    /// the file exists, so that a restarted tail prints every event once.
+    /// `--since` only applies to the log groups the file has no record of.
+    #[arg(long, value_name = "PATH")]
+    pub checkpoint_file: Option<PathBuf>,
+}
+
+impl TailOpt {
+    pub fn validate(&self) -> Result<(), String> {
+        self.groups.ecs.validate()
+    }
+}
+
+/// Where polling a log group, or some of its streams, has got to.
+/// `startTime` is inclusive, so events at the last printed time are told apart by their IDs.
+pub(crate) struct Cursor {
+    group: String,
+    streams: Vec<String>,
+    pub(crate) last_timestamp: i64,
+    seen_at_last: HashSet<String>,
+}
+
+impl Cursor {
+    pub(crate) fn new(group: String, streams: Vec<String>, since: i64) -> Self {
+        Cursor {
+            group,
+            streams,
+            last_timestamp: since,
+            seen_at_last: HashSet::new(),
+        }
+    }
+
+    /// Starts after the last event that the checkpoint has recorded for the streams, if any.
+    fn resume(&mut self, checkpoint: &Checkpoint) {
+        if let Some((timestamp, seen)) = checkpoint.resume_group(&self.group, &self.streams) {
+            self.last_timestamp = timestamp;
+            self.seen_at_last = seen.into_iter().collect();
+        }
+    }
+
+    /// Drops the events that were already printed and moves past the rest.
+    pub(crate) fn retain_new(&mut self, events: &mut Vec<LogEvent>) {
+        events.retain(|event| {
+            let event_id = event.event_id.as_deref().unwrap_or_default();
+            if event.timestamp < self.last_timestamp
+                || (event.timestamp == self.last_timestamp && self.seen_at_last.contains(event_id))
+            {
+                return false;
+            }
+            if event.timestamp > self.last_timestamp {
+                self.last_timestamp = event.timestamp;
+                self.seen_at_last.clear();
+            }
+            self.seen_at_last.insert(event_id.to_string());
+            true
+        });
+    }
+}
+
+/// Prints the recent events in the log groups, optionally following new ones.
+pub async fn run(target: &Target, opt: TailOpt, verbose: bool, color: bool) -> Result<(), Error> {
+    let TailOpt {
+        groups,
+        stream_prefix,
+        filter_pattern,
+        since,
+        until,
+        follow,
+        interval,
+        output,
+        delta,
+        grep,
+        invert_match,
+        jq,
+        fields,
+        access_log,
+        format,
+        checkpoint_file,
+    } = opt;
+    // 終わりのない配列は出力できないので、追跡中は1行1オブジェクトにする
+    let output = match output {
+        OutputFormat::Json if follow => OutputFormat::Ndjson,
+        other => other,
+    };
+    let client = &target.client;
+    let containers = groups
+        .ecs
+        .resolve(target)
+        .await
+        .map_err(|e| Error::failed(format!("Failed to look up the ECS tasks: {}", e)))?;
+
+    if verbose {
+        groups.print_verbose();
+        for container in &containers {
+            eprintln!(
+                "Container log stream:          {} {}",
+                container.group, container.stream
+            );
+        }
+        if let Some(prefix) = &stream_prefix {
+            eprintln!("Log stream prefix:             {}", prefix);
+        }
+        if let Some(pattern) = &filter_pattern {
+            eprintln!("Filter pattern:                {}", pattern);
+        }
+        eprintln!();
+    }
+
+    let group_names = groups.resolve(target, &Progress::new("groups")).await?;
+    let mut cursors: Vec<Cursor> = group_names
+        .iter()
+        .map(|group| Cursor::new(group.clone(), Vec::new(), since))
+        .collect();
+    // コンテナのストリームはロググループごとにまとめて取得する。全体を追うグループでは不要
+    let mut container_streams: Vec<(String, Vec<String>)> = Vec::new();
+    for container in containers {
+        if group_names.contains(&container.group) {
+            continue;
+        }
+        match container_streams
+            .iter_mut()
+            .find(|(group, _)| *group == container.group)
+        {
+            Some((_, streams)) => streams.push(container.stream),
+            None => container_streams.push((container.group, vec![container.stream])),
+        }
+    }
+    let label_groups = group_names.len() + container_streams.len() > 1;
+    for (group, streams) in container_streams {
+        for chunk in streams.chunks(MAX_FILTERED_STREAMS) {
+            cursors.push(Cursor::new(group.clone(), chunk.to_vec(), since));
+        }
+    }
+    groups.ensure_found(cursors.len())?;
+    let mut checkpoint = checkpoint_file
+        .map(|path| Checkpoint::load(&path))
+        .transpose()
+        .map_err(Error::failed)?;
+    if let Some(checkpoint) = &checkpoint {
+        for cursor in &mut cursors {
+            cursor.resume(checkpoint);
+        }
+    }
+    let mut printer = EventPrinter::new(output, true)
+        .with_template(format)
+        .with_grep(grep, invert_match)
+        .with_jq(jq)
+        .with_fields(fields)
+        .with_access_log(access_log)
+        .with_delta(delta)
+        .with_color(color);
+    let filter = EventFilter {
+        stream_prefix,
+        filter_pattern,
+        end_time: until,
+        ..Default::default()
+    };
+
+    loop {
+        let polls = cursors.iter().map(|cursor| {
+            let filter = EventFilter {
+                start_time: Some(cursor.last_timestamp),
+                stream_names: cursor.streams.clone(),
+                ..filter.clone()
+            };
+            async move { filter_events(client, &cursor.group, &filter).await }
+        });
+        let results = join_all(polls).await;
+        let mut lists = Vec::new();
+        for (cursor, result) in cursors.iter_mut().zip(results) {
+            let mut events = result?;
+            cursor.retain_new(&mut events);
+            if let Some(checkpoint) = &mut checkpoint {
+                checkpoint.advance(&cursor.group, &events);
+            }
+            if label_groups {
+                for event in &mut events {
+                    event.group = Some(cursor.group.clone());
+                }
+            }
+            lists.push(events);
+        }
+        let events = merge_by_timestamp(lists);
+        printer.print(&events);
+        output::flush_output()?;
+        // 出力を書き終えてから記録する
+        if let (Some(checkpoint), false) = (&checkpoint, events.is_empty()) {
+            checkpoint.save().map_err(Error::failed)?;
+        }
+
+        if !follow {
+            break;
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+    printer.finish();
+    Ok(())
+}
@@ -0,0 +1,291 @@
+use std::time::SystemTime;
+
+use aws_config::{retry::RetryConfig, sts::AssumeRoleProvider, Region, SdkConfig};
+use aws_sdk_cloudwatchlogs::{
+    config::{Builder as ClientConfigBuilder, Credentials, SharedCredentialsProvider},
+    Client,
+};
+use clap::{Args, ValueEnum};
+use futures_util::future::join_all;
+
+use crate::output::Record;
+use crate::rate_limit::rate_limited_client;
+
+/// The commercial Regions in which CloudWatch Logs is available, used by `--all-regions`.
+/// Opt-in Regions that are not enabled for the account fail with a warning.
+pub const ALL_REGIONS: &[&str] = &[
+    "us-east-1",
+    "us-east-2",
+    "us-west-1",
+    "us-west-2",
+    "af-south-1",
+    "ap-east-1",
+    "ap-south-1",
+    "ap-south-2",
+    "ap-southeast-1",
+    "ap-southeast-2",
+    "ap-southeast-3",
+    "ap-southeast-4",
+    "ap-northeast-1",
+    "ap-northeast-2",
+    "ap-northeast-3",
+    "ca-central-1",
+    "ca-west-1",
+    "eu-central-1",
+    "eu-central-2",
+    "eu-west-1",
+    "eu-west-2",
+    "eu-west-3",
+    "eu-south-1",
+    "eu-south-2",
+    "eu-north-1",
+    "il-central-1",
+    "me-south-1",
+    "me-central-1",
+    "sa-east-1",
+];
+
+/// The session name recorded in CloudTrail for assumed roles.
+const ROLE_SESSION_NAME: &str = "print-log-stream-names";
+
+/// Options for running as an IAM role, assumed with the credentials of the loaded profile.
+#[derive(Debug, Clone, Default, Args)]
+pub struct RoleOpt {
+    /// Assumes this role, e.g. `arn:aws:iam::123456789012:role/ReadLogs`.
+    #[arg(long, global = true)]
+    pub role_arn: Option<String>,
+
+    /// The external ID that the role's trust policy requires.
+    #[arg(long, global = true, requires = "role_arn")]
+    pub external_id: Option<String>,
+
+    /// The serial number or ARN of the MFA device that the role requires.
+    #[arg(long, global = true, requires_all = ["role_arn", "mfa_code"])]
+    pub mfa_serial: Option<String>,
+
+    /// The current code shown by the MFA device.
+    #[arg(long, global = true, requires = "mfa_serial")]
+    pub mfa_code: Option<String>,
+}
+
+/// How the SDK retries throttled and failed requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum RetryMode {
+    /// Exponential backoff with jitter.
+    #[default]
+    Standard,
+    /// Standard retries that also slow down the request rate while being throttled.
+    Adaptive,
+}
+
+/// Options for how the clients connect to CloudWatch Logs.
+#[derive(Debug, Clone, Default, Args)]
+pub struct ConnectionOpt {
+    /// Sends requests to this endpoint instead of AWS, e.g. `http://localhost:4566` for LocalStack.
+    #[arg(long, global = true, value_name = "URL")]
+    pub endpoint_url: Option<String>,
+
+    /// How many times each request is attempted, including the first, before giving up.
+    /// The SDK default is 3.
+    #[arg(long, global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_attempts: Option<u32>,
+
+    /// The retry strategy. `adaptive` backs off harder when large groups get throttled.
+    #[arg(long, global = true, value_enum)]
+    pub retry_mode: Option<RetryMode>,
+
+    /// Sends at most this many CloudWatch Logs requests per second in total, so that
+    /// pagination leaves room under the account's TPS quota for other consumers.
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_tps: Option<u32>,
+}
+
+impl ConnectionOpt {
+    /// The retry config from the flags, or `None` to keep the environment's and profile's.
+    fn retry_config(&self) -> Option<RetryConfig> {
+        if self.max_attempts.is_none() && self.retry_mode.is_none() {
+            return None;
+        }
+        let config = match self.retry_mode.unwrap_or_default() {
+            RetryMode::Standard => RetryConfig::standard(),
+            RetryMode::Adaptive => RetryConfig::adaptive(),
+        };
+        Some(match self.max_attempts {
+            Some(max_attempts) => config.with_max_attempts(max_attempts),
+            None => config,
+        })
+    }
+}
+
+/// Assumes the role once with the MFA code. The credentials are not refreshed,
+/// because that would need a new code, so they expire after the default session of an hour.
+async fn assume_role_with_mfa(
+    config: &SdkConfig,
+    role: &RoleOpt,
+) -> Result<Credentials, aws_sdk_sts::Error> {
+    let output = aws_sdk_sts::Client::new(config)
+        .assume_role()
+        .set_role_arn(role.role_arn.clone())
+        .role_session_name(ROLE_SESSION_NAME)
+        .set_external_id(role.external_id.clone())
+        .set_serial_number(role.mfa_serial.clone())
+        .set_token_code(role.mfa_code.clone())
+        .send()
+        .await?;
+    let credentials = output
+        .credentials()
+        .expect("a successful AssumeRole response has credentials");
+    Ok(Credentials::new(
+        credentials.access_key_id(),
+        credentials.secret_access_key(),
+        Some(credentials.session_token().to_string()),
+        SystemTime::try_from(*credentials.expiration()).ok(),
+        "AssumeRoleWithMfa",
+    ))
+}
+
+/// A provider that assumes the role with the config's credentials and refreshes it as needed.
+async fn assume_role_provider(
+    config: &SdkConfig,
+    role_arn: &str,
+    role: &RoleOpt,
+) -> AssumeRoleProvider {
+    let mut builder = AssumeRoleProvider::builder(role_arn)
+        .session_name(ROLE_SESSION_NAME)
+        .configure(config);
+    if let Some(external_id) = &role.external_id {
+        builder = builder.external_id(external_id);
+    }
+    builder.build().await
+}
+
+/// A client to run a command with, labeled when the command runs in more than one
+/// profile or Region.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub client: Client,
+    /// The credentials the client signs with, for the services this build has no SDK for.
+    pub credentials: Option<SharedCredentialsProvider>,
+    /// The endpoint given with `--endpoint-url`, which those services are sent to as well.
+    pub endpoint_url: Option<String>,
+    /// The profile, set only when results from several profiles are printed together.
+    pub profile: Option<String>,
+    /// The Region, set only when results from several Regions are printed together.
+    pub region: Option<String>,
+}
+
+impl Target {
+    /// The values that tell this target's results apart from the others.
+    fn labels(&self) -> Vec<(&'static str, &str)> {
+        let profile = self.profile.as_deref().map(|profile| ("profile", profile));
+        let region = self.region.as_deref().map(|region| ("region", region));
+        profile.into_iter().chain(region).collect()
+    }
+
+    /// Prepended to each line of text output, e.g. `prod\tus-east-1\t`.
+    pub fn text_prefix(&self) -> String {
+        self.labels()
+            .into_iter()
+            .map(|(_, value)| format!("{}\t", value))
+            .collect()
+    }
+
+    /// Starts a structured output record with the labels.
+    pub fn record(&self) -> Record {
+        self.labels()
+            .into_iter()
+            .fold(Record::new(), |record, (name, value)| {
+                record.field(name, value)
+            })
+    }
+
+    /// Starts a `table` row with the labels as upper-case columns.
+    pub fn table_row(&self) -> Record {
+        self.labels()
+            .into_iter()
+            .fold(Record::new(), |record, (name, value)| {
+                record.field(name.to_ascii_uppercase(), value)
+            })
+    }
+}
+
+/// Lists the values to run with, or a single `None` to leave the choice to the environment.
+fn or_default(values: &[String]) -> Vec<Option<&String>> {
+    match values {
+        [] => vec![None],
+        values => values.iter().map(Some).collect(),
+    }
+}
+
+/// Creates a client for each combination of profile and Region, as the role if one is given.
+/// Without any Region, the environment or the profile decides.
+pub async fn load_targets(
+    regions: &[String],
+    profile_names: &[String],
+    role: &RoleOpt,
+    connection: &ConnectionOpt,
+) -> Result<Vec<Target>, aws_sdk_sts::Error> {
+    let profile_labeled = profile_names.len() > 1;
+    let region_labeled = regions.len() > 1;
+    let combinations: Vec<(Option<&String>, Option<&String>)> = or_default(profile_names)
+        .into_iter()
+        .flat_map(|profile| {
+            or_default(regions)
+                .into_iter()
+                .map(move |region| (profile, region))
+        })
+        .collect();
+    // 設定の読み込みはプロファイルのファイルを読むので、組み合わせごとに並行して行う
+    let configs = join_all(combinations.iter().map(|(profile_name, region)| {
+        let mut config_loader = aws_config::from_env();
+        if let Some(profile_name) = profile_name {
+            config_loader = config_loader.profile_name(profile_name.as_str());
+        }
+        if let Some(region) = region {
+            config_loader = config_loader.region(Region::new(region.to_string()));
+        }
+        if let Some(endpoint_url) = &connection.endpoint_url {
+            config_loader = config_loader.endpoint_url(endpoint_url);
+        }
+        if let Some(retry_config) = connection.retry_config() {
+            config_loader = config_loader.retry_config(retry_config);
+        }
+        config_loader.load()
+    }))
+    .await;
+
+    // MFA のコードは使い回せないので、ロールは最初の設定で一度だけ引き受けて共有する
+    let mfa_credentials = match role.mfa_serial {
+        Some(_) => Some(assume_role_with_mfa(&configs[0], role).await?),
+        None => None,
+    };
+
+    // 上限はアカウントにかかるので、全クライアントで1つのバケツを共有する
+    let http_client = connection.max_tps.map(rate_limited_client);
+
+    let mut targets = Vec::new();
+    for ((profile, region), config) in combinations.into_iter().zip(configs) {
+        let mut client_config = ClientConfigBuilder::from(&config);
+        if let Some(http_client) = &http_client {
+            client_config = client_config.http_client(http_client.clone());
+        }
+        let credentials = if let Some(credentials) = &mfa_credentials {
+            Some(SharedCredentialsProvider::new(credentials.clone()))
+        } else if let Some(role_arn) = &role.role_arn {
+            Some(SharedCredentialsProvider::new(
+                assume_role_provider(&config, role_arn, role).await,
+            ))
+        } else {
+            config.credentials_provider()
+        };
+        client_config.set_credentials_provider(credentials.clone());
+        targets.push(Target {
+            client: Client::from_conf(client_config.build()),
+            credentials,
+            endpoint_url: connection.endpoint_url.clone(),
+            profile: profile.filter(|_| profile_labeled).cloned(),
+            region: region.filter(|_| region_labeled).cloned(),
+        });
+    }
+    Ok(targets)
+}
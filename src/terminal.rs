@@ -0,0 +1,202 @@
+//! Reading single keys from the terminal, for the interactive commands.
+//! No terminal library is part of this build, so raw mode is set with termios directly.
+
+use std::io;
+
+/// Escape sequences for the selected rows of the full-screen commands.
+/// They are attributes rather than colors, so they are used with `--color never` too.
+pub const REVERSE: &str = "\x1b[7m";
+pub const BOLD: &str = "\x1b[1m";
+pub const RESET: &str = "\x1b[0m";
+
+/// A key press, with the escape sequences of the common special keys decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    /// A letter pressed with Ctrl, e.g. `Ctrl('c')`.
+    Ctrl(char),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+}
+
+/// Puts the terminal on standard input in raw mode until dropped: keys are read as they are
+/// pressed, without echo, and Ctrl-C is read as a key instead of interrupting.
+pub struct RawMode {
+    original: libc::termios,
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<RawMode> {
+        // SAFETY: termios は tcgetattr が埋める C の構造体
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut raw = original;
+        raw.c_iflag &= !(libc::ICRNL | libc::IXON | libc::BRKINT | libc::ISTRIP | libc::INPCK);
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        // 出力の改行変換 (OPOST) はそのままにして、println! の \n で行頭に戻るようにする
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &raw) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(RawMode { original })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &self.original);
+        }
+    }
+}
+
+/// Switches stderr to the alternate screen, with the cursor hidden and the terminal in raw mode,
+/// and back to the screen as it was when dropped.
+pub struct Screen {
+    _raw: RawMode,
+}
+
+impl Screen {
+    pub fn enter() -> io::Result<Screen> {
+        let raw = RawMode::enable()?;
+        eprint!("\x1b[?1049h\x1b[?25l");
+        Ok(Screen { _raw: raw })
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        eprint!("\x1b[?25h\x1b[?1049l");
+    }
+}
+
+/// The number of columns and rows of the terminal on stderr, or 80 x 24 if it cannot be told.
+pub fn size() -> (usize, usize) {
+    // SAFETY: winsize は ioctl が埋める C の構造体
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    match unsafe { libc::ioctl(libc::STDERR_FILENO, libc::TIOCGWINSZ, &mut size) } {
+        0 if size.ws_col > 0 && size.ws_row > 0 => (size.ws_col as usize, size.ws_row as usize),
+        _ => (80, 24),
+    }
+}
+
+/// Whether another byte arrives within `millis`, to tell the Escape key from an escape sequence.
+fn input_within(millis: i32) -> bool {
+    let mut fd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    unsafe { libc::poll(&mut fd, 1, millis) > 0 }
+}
+
+/// Reads the next key if one is pressed within `millis`, so that a loop can do other work between keys.
+pub fn poll_key(millis: i32) -> io::Result<Option<Key>> {
+    match input_within(millis) {
+        true => read_key(),
+        false => Ok(None),
+    }
+}
+
+/// Reads one byte from standard input without buffering, so that [`input_within`] sees
+/// whether the rest of an escape sequence has arrived.
+fn read_byte() -> io::Result<Option<u8>> {
+    let mut byte = 0u8;
+    loop {
+        match unsafe { libc::read(libc::STDIN_FILENO, (&mut byte as *mut u8).cast(), 1) } {
+            0 => return Ok(None),
+            1 => return Ok(Some(byte)),
+            _ => {
+                let error = io::Error::last_os_error();
+                if error.kind() != io::ErrorKind::Interrupted {
+                    return Err(error);
+                }
+            }
+        }
+    }
+}
+
+/// Reads the next key from standard input, or `None` at the end of input.
+pub fn read_key() -> io::Result<Option<Key>> {
+    let Some(byte) = read_byte()? else {
+        return Ok(None);
+    };
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f | 0x08 => Key::Backspace,
+        0x1b if !input_within(50) => Key::Escape,
+        0x1b => read_escape()?,
+        0x01..=0x1a => Key::Ctrl((b'a' + byte - 1) as char),
+        byte if byte < 0x80 => Key::Char(byte as char),
+        byte => {
+            // UTF-8 の残りのバイトを読む
+            let len = match byte {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                _ => 4,
+            };
+            let mut bytes = vec![byte];
+            for _ in 1..len {
+                bytes.extend(read_byte()?);
+            }
+            match String::from_utf8_lossy(&bytes).chars().next() {
+                Some(c) => Key::Char(c),
+                None => return read_key(),
+            }
+        }
+    };
+    Ok(Some(key))
+}
+
+/// Decodes `ESC [ A`, `ESC [ 3 ~`, `ESC O H` and the like. Unknown sequences read as Escape.
+fn read_escape() -> io::Result<Key> {
+    let Some(kind) = read_byte()? else {
+        return Ok(Key::Escape);
+    };
+    if kind != b'[' && kind != b'O' {
+        return Ok(Key::Escape);
+    }
+    let mut parameter = String::new();
+    loop {
+        let Some(byte) = read_byte()? else {
+            return Ok(Key::Escape);
+        };
+        let key = match byte {
+            b'0'..=b'9' | b';' => {
+                parameter.push(byte as char);
+                continue;
+            }
+            b'A' => Key::Up,
+            b'B' => Key::Down,
+            b'C' => Key::Right,
+            b'D' => Key::Left,
+            b'H' => Key::Home,
+            b'F' => Key::End,
+            b'~' => match parameter.as_str() {
+                "1" | "7" => Key::Home,
+                "3" => Key::Delete,
+                "4" | "8" => Key::End,
+                "5" => Key::PageUp,
+                "6" => Key::PageDown,
+                _ => Key::Escape,
+            },
+            _ => Key::Escape,
+        };
+        return Ok(key);
+    }
+}
@@ -0,0 +1,120 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use time::{
+    format_description::{well_known::Rfc3339, FormatItem},
+    macros::format_description,
+    Date, OffsetDateTime, PrimitiveDateTime,
+};
+
+/// Date-time layouts accepted in addition to RFC 3339. They are read as UTC.
+const DATE_TIME_FORMATS: &[&[FormatItem<'_>]] = &[
+    format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"),
+    format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]"),
+    format_description!("[year]-[month]-[day] [hour]:[minute]"),
+    format_description!("[year]-[month]-[day]T[hour]:[minute]"),
+];
+
+/// Returns the current time as epoch milliseconds.
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Parses a duration such as `90s`, `10m`, `2h`, `1h30m`, `7d` or `1w`.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("empty duration".to_string());
+    }
+    let mut total = Duration::ZERO;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(format!(
+                "invalid duration `{}`, expected e.g. 10m or 1h30m",
+                s
+            ));
+        }
+        let amount: u32 = rest[..digits]
+            .parse()
+            .map_err(|_| format!("invalid duration `{}`", s))?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let unit = match &rest[..unit_len] {
+            "ms" => Duration::from_millis(1),
+            "s" => Duration::from_secs(1),
+            "m" => Duration::from_secs(60),
+            "h" => Duration::from_secs(60 * 60),
+            "d" => Duration::from_secs(24 * 60 * 60),
+            "w" => Duration::from_secs(7 * 24 * 60 * 60),
+            "" => {
+                return Err(format!(
+                    "missing unit in duration `{}` (ms, s, m, h, d, w)",
+                    s
+                ))
+            }
+            other => return Err(format!("unknown unit `{}` in duration `{}`", other, s)),
+        };
+        rest = &rest[unit_len..];
+        total = unit
+            .checked_mul(amount)
+            .and_then(|d| total.checked_add(d))
+            .ok_or_else(|| format!("duration `{}` is too large", s))?;
+    }
+    Ok(total)
+}
+
+fn duration_millis(duration: Duration) -> i64 {
+    duration.as_millis() as i64
+}
+
+/// Parses a point in time and returns it as epoch milliseconds.
+///
+/// Accepts `now`, a duration ago (`2h`), `now-10m` / `now+5m`, epoch seconds or milliseconds,
+/// RFC 3339 (`2024-05-01T13:00:00+09:00`), and `YYYY-MM-DD[ HH:MM[:SS]]` in UTC.
+pub fn parse_timestamp(s: &str) -> Result<i64, String> {
+    let s = s.trim();
+    let now = now_millis();
+
+    if s == "now" {
+        return Ok(now);
+    }
+    if let Some(offset) = s.strip_prefix("now-") {
+        return Ok(now - duration_millis(parse_duration(offset)?));
+    }
+    if let Some(offset) = s.strip_prefix("now+") {
+        return Ok(now + duration_millis(parse_duration(offset)?));
+    }
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) {
+        let n: i64 = s
+            .parse()
+            .map_err(|_| format!("invalid timestamp `{}`", s))?;
+        // 13桁ならミリ秒、それより短ければ秒とみなす
+        return Ok(if n >= 100_000_000_000 { n } else { n * 1000 });
+    }
+    if let Ok(duration) = parse_duration(s) {
+        return Ok(now - duration_millis(duration));
+    }
+    if let Ok(t) = OffsetDateTime::parse(s, &Rfc3339) {
+        return Ok((t.unix_timestamp_nanos() / 1_000_000) as i64);
+    }
+    for format in DATE_TIME_FORMATS {
+        if let Ok(t) = PrimitiveDateTime::parse(s, format) {
+            return Ok((t.assume_utc().unix_timestamp_nanos() / 1_000_000) as i64);
+        }
+    }
+    if let Ok(date) = Date::parse(s, format_description!("[year]-[month]-[day]")) {
+        return Ok(date.midnight().assume_utc().unix_timestamp() * 1000);
+    }
+    Err(format!(
+        "invalid time `{}`, expected e.g. 2h, now-10m, 2024-05-01 13:00 or an RFC 3339 timestamp",
+        s
+    ))
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use clap::Args;
+
+use crate::error::Error;
+use crate::events::{filter_groups_events, parse_event_template, EventFilter, EventPrinter};
+use crate::groups::GroupOpt;
+use crate::output::{format_timestamp, OutputFormat, Template};
+use crate::progress::Progress;
+use crate::targets::Target;
+use crate::time_range::parse_duration;
+
+#[derive(Debug, Args)]
+pub struct TraceOpt {
+    /// The X-Ray trace ID, e.g. `1-5759e988-bd862e3fe1be46a994272793`.
+    /// A trace header such as `Root=1-5759e988-...;Sampled=1` is also accepted.
+    #[arg(value_name = "TRACE-ID", value_parser = parse_trace_id)]
+    pub trace: TraceId,
+
+    #[command(flatten)]
+    pub groups: GroupOpt,
+
+    /// How long before the start of the trace to search, to allow for clock differences.
+    #[arg(long, default_value = "1m", value_parser = parse_duration)]
+    pub before: Duration,
+
+    /// How long after the start of the trace to search.
+    #[arg(long, default_value = "15m", value_parser = parse_duration)]
+    pub after: Duration,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    pub output: OutputFormat,
+
+    /// Prints each event with a template such as `{timestamp} {group} {message}`.
+    /// Fields: timestamp, timestampMs, stream, eventId, message, and group when searching several log groups.
+    #[arg(long, conflicts_with = "output", value_parser = parse_event_template)]
+    pub format: Option<Template>,
+}
+
+/// An X-Ray trace ID and the time it started.
+#[derive(Debug, Clone)]
+pub struct TraceId {
+    pub id: String,
+    pub start_millis: i64,
+}
+
+/// Parses a trace ID of the form `1-<8 hex digits of epoch seconds>-<24 hex digits>`,
+/// optionally inside a trace header.
+fn parse_trace_id(s: &str) -> Result<TraceId, String> {
+    let id = s
+        .split(';')
+        .find_map(|field| field.trim().strip_prefix("Root="))
+        .unwrap_or(s.trim());
+    let invalid = || {
+        format!(
+            "`{}` is not an X-Ray trace ID, expected e.g. 1-5759e988-bd862e3fe1be46a994272793",
+            s
+        )
+    };
+    let mut parts = id.split('-');
+    let (Some("1"), Some(time), Some(unique), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    if time.len() != 8 || unique.len() != 24 || !unique.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let seconds = i64::from_str_radix(time, 16).map_err(|_| invalid())?;
+    Ok(TraceId {
+        id: id.to_string(),
+        start_millis: seconds * 1000,
+    })
+}
+
+/// Finds the events that mention an X-Ray trace ID in the log groups, around the time the trace
+/// started, and prints them as one timeline.
+pub async fn run(target: &Target, opt: TraceOpt, verbose: bool, color: bool) -> Result<(), Error> {
+    let TraceOpt {
+        trace,
+        groups,
+        before,
+        after,
+        output,
+        format,
+    } = opt;
+    let client = &target.client;
+    let start_time = trace.start_millis - before.as_millis() as i64;
+    let end_time = trace.start_millis + after.as_millis() as i64;
+
+    if verbose {
+        eprintln!("Trace ID:                      {}", trace.id);
+        groups.print_verbose();
+        eprintln!(
+            "Time range:                    {} - {}",
+            format_timestamp(start_time),
+            format_timestamp(end_time)
+        );
+        eprintln!();
+    }
+
+    let group_names = groups.resolve(target, &Progress::new("groups")).await?;
+    groups.ensure_found(group_names.len())?;
+    let filter = EventFilter {
+        // 引用符で囲むとハイフンを含む ID が1つの語として完全一致する
+        filter_pattern: Some(format!("\"{}\"", trace.id)),
+        start_time: Some(start_time),
+        end_time: Some(end_time),
+        ..Default::default()
+    };
+    let events = filter_groups_events(client, &group_names, &filter).await?;
+
+    let mut printer = EventPrinter::new(output, true)
+        .with_template(format)
+        .with_color(color);
+    printer.print(&events);
+    printer.finish();
+    Ok(())
+}
@@ -0,0 +1,392 @@
+//! Time zones for printed timestamps.
+//!
+//! The `time` crate only knows fixed UTC offsets, so zones are read from the system tz database
+//! (`/usr/share/zoneinfo`, or `$TZDIR`) or from a POSIX `TZ` value such as `EST5EDT,M3.2.0,M11.1.0`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use time::{Date, Month, OffsetDateTime};
+
+const DEFAULT_TZDIR: &str = "/usr/share/zoneinfo";
+
+/// The Julian day of 1970-01-01.
+const UNIX_EPOCH_JULIAN_DAY: i64 = 2_440_588;
+
+/// The zone timestamps are printed in, set once from `--tz`.
+static TIME_ZONE: OnceLock<TimeZone> = OnceLock::new();
+
+/// Uses `zone` for every timestamp printed from now on.
+pub fn set(zone: TimeZone) {
+    let _ = TIME_ZONE.set(zone);
+}
+
+/// The zone given to `set`, or UTC.
+pub fn current() -> &'static TimeZone {
+    TIME_ZONE.get_or_init(TimeZone::default)
+}
+
+/// A time zone: the UTC offsets it has used over time. The default is UTC.
+#[derive(Debug, Clone, Default)]
+pub struct TimeZone {
+    /// Epoch seconds at which the offset changed, with the offset in seconds east of UTC from then on.
+    transitions: Vec<(i64, i32)>,
+    /// The offset before the first transition.
+    initial: i32,
+    /// How the offset changes after the last transition.
+    rule: Option<PosixRule>,
+}
+
+impl TimeZone {
+    /// Parses `--tz`: `utc`, `local` (`TZ`, or `/etc/localtime`), or an IANA name such as `Asia/Tokyo`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            s if s.eq_ignore_ascii_case("utc") => Ok(TimeZone::default()),
+            s if s.eq_ignore_ascii_case("local") => TimeZone::local(),
+            s => TimeZone::named(s),
+        }
+    }
+
+    /// The zone of this machine, like the C library picks it.
+    fn local() -> Result<Self, String> {
+        match env::var("TZ") {
+            Ok(tz) if tz.is_empty() => Ok(TimeZone::default()),
+            Ok(tz) => TimeZone::named(tz.strip_prefix(':').unwrap_or(&tz)),
+            // /etc/localtime がなければ C ライブラリと同じく UTC とみなす
+            Err(_) => Ok(TimeZone::from_file(Path::new("/etc/localtime")).unwrap_or_default()),
+        }
+    }
+
+    /// A zone in the tz database, or else a POSIX `TZ` value.
+    fn named(name: &str) -> Result<Self, String> {
+        let path = match Path::new(name).is_absolute() {
+            true => PathBuf::from(name),
+            false => env::var_os("TZDIR")
+                .map_or_else(|| PathBuf::from(DEFAULT_TZDIR), PathBuf::from)
+                .join(name),
+        };
+        if let Some(zone) = TimeZone::from_file(&path) {
+            return Ok(zone);
+        }
+        PosixRule::parse(name)
+            .map(|rule| TimeZone {
+                rule: Some(rule),
+                ..TimeZone::default()
+            })
+            .ok_or_else(|| format!("unknown time zone `{}`", name))
+    }
+
+    fn from_file(path: &Path) -> Option<Self> {
+        TimeZone::from_tzif(&fs::read(path).ok()?)
+    }
+
+    /// Reads a compiled tz database file (RFC 8536).
+    fn from_tzif(data: &[u8]) -> Option<Self> {
+        let (version2, counts) = tzif_header(data)?;
+        // バージョン2以降は64ビットの時刻を持つ2つ目のデータブロックと、末尾の TZ 文字列を使う
+        let (data, counts, time_size) = match version2 {
+            false => (data, counts, 4),
+            true => {
+                let data = data.get(44 + counts.data_len(4)..)?;
+                (data, tzif_header(data)?.1, 8)
+            }
+        };
+        let mut rest = &data[44..];
+        let times = take(&mut rest, counts.transitions * time_size)?;
+        let indices = take(&mut rest, counts.transitions)?;
+        let types = take(&mut rest, counts.types * 6)?;
+        take(
+            &mut rest,
+            counts.abbreviation_bytes
+                + counts.leap_seconds * (time_size + 4)
+                + counts.std_indicators
+                + counts.ut_indicators,
+        )?;
+
+        let offset = |index: usize| {
+            let bytes = types.get(index * 6..index * 6 + 4)?;
+            Some(i32::from_be_bytes(bytes.try_into().ok()?))
+        };
+        let transitions = times
+            .chunks_exact(time_size)
+            .zip(indices)
+            .map(|(time, &index)| {
+                let time = match time_size {
+                    4 => i64::from(i32::from_be_bytes(time.try_into().ok()?)),
+                    _ => i64::from_be_bytes(time.try_into().ok()?),
+                };
+                Some((time, offset(usize::from(index))?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let rule = match version2 {
+            false => None,
+            true => std::str::from_utf8(rest)
+                .ok()
+                .map(|footer| footer.trim_matches('\n'))
+                .and_then(PosixRule::parse),
+        };
+        Some(TimeZone {
+            transitions,
+            initial: offset(0).unwrap_or_default(),
+            rule,
+        })
+    }
+
+    /// The offset from UTC in seconds at `secs` seconds since the epoch.
+    pub fn offset_at(&self, secs: i64) -> i32 {
+        let after = self.transitions.partition_point(|&(time, _)| time <= secs);
+        match (after, &self.rule) {
+            (n, Some(rule)) if n == self.transitions.len() => rule.offset_at(secs),
+            (0, _) => self.initial,
+            (n, _) => self.transitions[n - 1].1,
+        }
+    }
+}
+
+/// The record counts in a TZif header.
+struct TzifCounts {
+    ut_indicators: usize,
+    std_indicators: usize,
+    leap_seconds: usize,
+    transitions: usize,
+    types: usize,
+    abbreviation_bytes: usize,
+}
+
+impl TzifCounts {
+    /// The length of the data block that follows the header.
+    fn data_len(&self, time_size: usize) -> usize {
+        self.transitions * (time_size + 1)
+            + self.types * 6
+            + self.abbreviation_bytes
+            + self.leap_seconds * (time_size + 4)
+            + self.std_indicators
+            + self.ut_indicators
+    }
+}
+
+/// Reads a TZif header: whether the file is version 2 or later, and the record counts.
+fn tzif_header(data: &[u8]) -> Option<(bool, TzifCounts)> {
+    if data.len() < 44 || &data[..4] != b"TZif" {
+        return None;
+    }
+    let count = |i: usize| {
+        let bytes = data[20 + i * 4..24 + i * 4].try_into().ok()?;
+        usize::try_from(u32::from_be_bytes(bytes)).ok()
+    };
+    let counts = TzifCounts {
+        ut_indicators: count(0)?,
+        std_indicators: count(1)?,
+        leap_seconds: count(2)?,
+        transitions: count(3)?,
+        types: count(4)?,
+        abbreviation_bytes: count(5)?,
+    };
+    Some((data[4] >= b'2', counts))
+}
+
+fn take<'a>(rest: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if rest.len() < len {
+        return None;
+    }
+    let (taken, remaining) = rest.split_at(len);
+    *rest = remaining;
+    Some(taken)
+}
+
+/// A POSIX `TZ` rule: a standard offset and, optionally, daylight saving time and when it applies.
+#[derive(Debug, Clone)]
+struct PosixRule {
+    std_offset: i32,
+    dst: Option<DstRule>,
+}
+
+#[derive(Debug, Clone)]
+struct DstRule {
+    offset: i32,
+    /// The day and the local standard time, in seconds, that daylight saving time starts.
+    start: (RuleDate, i32),
+    /// The day and the local daylight saving time that it ends.
+    end: (RuleDate, i32),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RuleDate {
+    /// `Jn`: the day of the year from 1 to 365, never counting February 29.
+    Julian(u16),
+    /// `n`: the day of the year from 0 to 365.
+    Ordinal(u16),
+    /// `Mm.w.d`: day `d` (0 is Sunday) of week `w` (5 is the last) of month `m`.
+    MonthWeekDay(u8, u8, u8),
+}
+
+impl RuleDate {
+    /// The number of days from the epoch to this date in `year`.
+    fn epoch_day(self, year: i32) -> Option<i64> {
+        let date = match self {
+            RuleDate::Julian(day) => {
+                let leap_day = time::util::is_leap_year(year) && day >= 60;
+                Date::from_ordinal_date(year, day + u16::from(leap_day)).ok()?
+            }
+            RuleDate::Ordinal(day) => Date::from_ordinal_date(year, day + 1).ok()?,
+            RuleDate::MonthWeekDay(month, week, weekday) => {
+                let month = Month::try_from(month).ok()?;
+                let first = Date::from_calendar_date(year, month, 1).ok()?;
+                let first_weekday = first.weekday().number_days_from_sunday();
+                let mut day = 1 + (weekday + 7 - first_weekday) % 7 + (week - 1) * 7;
+                // 第5週は「最後の」週を意味する
+                while day > month.length(year) {
+                    day -= 7;
+                }
+                Date::from_calendar_date(year, month, day).ok()?
+            }
+        };
+        Some(i64::from(date.to_julian_day()) - UNIX_EPOCH_JULIAN_DAY)
+    }
+}
+
+impl PosixRule {
+    /// Parses a value such as `JST-9`, `CET-1CEST,M3.5.0,M10.5.0/3` or `<+0330>-3:30`.
+    fn parse(s: &str) -> Option<Self> {
+        let mut parser = RuleParser { rest: s };
+        parser.name()?;
+        // POSIX のオフセットは UTC から西向きに正なので符号を反転する
+        let std_offset = -parser.time()?;
+        if parser.rest.is_empty() {
+            return Some(PosixRule {
+                std_offset,
+                dst: None,
+            });
+        }
+        parser.name()?;
+        let offset = match parser.rest.starts_with(',') || parser.rest.is_empty() {
+            true => std_offset + 3600,
+            false => -parser.time()?,
+        };
+        // 規則が省略されたら米国の規則を使う
+        let (start, end) = match parser.rest.is_empty() {
+            true => (
+                (RuleDate::MonthWeekDay(3, 2, 0), 7200),
+                (RuleDate::MonthWeekDay(11, 1, 0), 7200),
+            ),
+            false => {
+                parser.eat(',')?;
+                let start = parser.transition()?;
+                parser.eat(',')?;
+                (start, parser.transition()?)
+            }
+        };
+        if !parser.rest.is_empty() {
+            return None;
+        }
+        Some(PosixRule {
+            std_offset,
+            dst: Some(DstRule { offset, start, end }),
+        })
+    }
+
+    fn offset_at(&self, secs: i64) -> i32 {
+        let Some(dst) = &self.dst else {
+            return self.std_offset;
+        };
+        let year = OffsetDateTime::from_unix_timestamp(secs + i64::from(self.std_offset))
+            .map_or(1970, |t| t.year());
+        let at = |(date, time): (RuleDate, i32), offset: i32| {
+            Some(date.epoch_day(year)? * 86400 + i64::from(time) - i64::from(offset))
+        };
+        let (Some(start), Some(end)) = (at(dst.start, self.std_offset), at(dst.end, dst.offset))
+        else {
+            return self.std_offset;
+        };
+        // 南半球では夏時間が年をまたぐ
+        let in_dst = match start < end {
+            true => start <= secs && secs < end,
+            false => secs < end || start <= secs,
+        };
+        match in_dst {
+            true => dst.offset,
+            false => self.std_offset,
+        }
+    }
+}
+
+struct RuleParser<'a> {
+    rest: &'a str,
+}
+
+impl RuleParser<'_> {
+    fn eat(&mut self, c: char) -> Option<()> {
+        self.rest = self.rest.strip_prefix(c)?;
+        Some(())
+    }
+
+    /// A zone abbreviation such as `EST` or `<+0330>`.
+    fn name(&mut self) -> Option<()> {
+        let len = match self.rest.strip_prefix('<') {
+            Some(quoted) => quoted.find('>')? + 2,
+            None => self
+                .rest
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(self.rest.len()),
+        };
+        if len < 3 {
+            return None;
+        }
+        self.rest = &self.rest[len..];
+        Some(())
+    }
+
+    fn number(&mut self) -> Option<u16> {
+        let len = self
+            .rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.rest.len());
+        let (digits, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        digits.parse().ok()
+    }
+
+    /// `[+|-]hh[:mm[:ss]]` in seconds.
+    fn time(&mut self) -> Option<i32> {
+        let sign = match self.rest.chars().next() {
+            Some('-') => -1,
+            _ => 1,
+        };
+        self.rest = self.rest.trim_start_matches(['+', '-']);
+        let mut secs = i32::from(self.number()?) * 3600;
+        if self.eat(':').is_some() {
+            secs += i32::from(self.number()?) * 60;
+            if self.eat(':').is_some() {
+                secs += i32::from(self.number()?);
+            }
+        }
+        Some(sign * secs)
+    }
+
+    /// `date[/time]`, at 02:00 when the time is left out.
+    fn transition(&mut self) -> Option<(RuleDate, i32)> {
+        let date = match self.rest.chars().next()? {
+            'J' => {
+                self.eat('J')?;
+                RuleDate::Julian(self.number().filter(|day| (1..=365).contains(day))?)
+            }
+            'M' => {
+                self.eat('M')?;
+                let month = self.number().filter(|m| (1..=12).contains(m))?;
+                self.eat('.')?;
+                let week = self.number().filter(|w| (1..=5).contains(w))?;
+                self.eat('.')?;
+                let weekday = self.number().filter(|d| *d <= 6)?;
+                RuleDate::MonthWeekDay(month as u8, week as u8, weekday as u8)
+            }
+            _ => RuleDate::Ordinal(self.number().filter(|day| *day <= 365)?),
+        };
+        let time = match self.eat('/') {
+            Some(()) => self.time()?,
+            None => 7200,
+        };
+        Some((date, time))
+    }
+}
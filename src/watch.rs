@@ -0,0 +1,120 @@
+//! `--watch` for `list-streams` and `list-groups`: the listing is run again on an interval and
+//! printed whole, with the lines that changed since the previous listing marked, like `watch(1)`
+//! with `--differences`. On a terminal the screen is cleared before each listing.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use crate::color;
+use crate::error::Error;
+use crate::output::{self, format_timestamp};
+use crate::time_range::now_millis;
+use crate::{out, outln};
+
+/// A listed stream or log group.
+pub(crate) struct Entry {
+    /// What tells it apart from the others, e.g. the Region, group and name.
+    pub key: String,
+    /// The text to print.
+    pub line: String,
+    /// When the stream last had an event. Log groups have none.
+    pub last_event: Option<i64>,
+}
+
+/// The previous listing, to compare the next one with.
+pub(crate) struct Watch {
+    interval: Duration,
+    color: bool,
+    previous: Option<Vec<Entry>>,
+}
+
+impl Watch {
+    pub(crate) fn new(interval: Duration, color: bool) -> Self {
+        Watch {
+            interval,
+            color,
+            previous: None,
+        }
+    }
+
+    /// Prints a listing of `noun`: new lines are marked `+`, streams with new events `*`,
+    /// and the lines that are gone are printed once more at the end, marked `-`.
+    pub(crate) fn print(&mut self, noun: &str, entries: Vec<Entry>) -> Result<(), Error> {
+        let previous = self.previous.take();
+        let before: HashMap<&str, Option<i64>> = previous
+            .iter()
+            .flatten()
+            .map(|entry| (entry.key.as_str(), entry.last_event))
+            .collect();
+        let keys: HashSet<&str> = entries.iter().map(|entry| entry.key.as_str()).collect();
+        let mut lines = Vec::new();
+        let (mut added, mut changed) = (0, 0);
+        for entry in &entries {
+            let line = match before.get(entry.key.as_str()) {
+                _ if previous.is_none() => format!("  {}", entry.line),
+                None => {
+                    added += 1;
+                    self.paint(color::added, format!("+ {}", entry.line))
+                }
+                Some(last) if entry.last_event > *last => {
+                    changed += 1;
+                    self.paint(color::changed, format!("* {}", entry.line))
+                }
+                Some(_) => format!("  {}", entry.line),
+            };
+            lines.push(line);
+        }
+        let gone: Vec<&Entry> = previous
+            .iter()
+            .flatten()
+            .filter(|entry| !keys.contains(entry.key.as_str()))
+            .collect();
+        for entry in &gone {
+            lines.push(self.paint(color::removed, format!("- {}", entry.line)));
+        }
+
+        if output::is_terminal() {
+            // 画面を消して左上から書く
+            out!("\x1b[H\x1b[2J");
+        }
+        if !output::is_quiet() {
+            let command: Vec<String> = std::env::args().skip(1).collect();
+            outln!(
+                "Every {}: {}    {}",
+                format_interval(self.interval),
+                command.join(" "),
+                format_timestamp(now_millis())
+            );
+            let mut summary = format!("{} {}", entries.len(), noun);
+            if previous.is_some() {
+                summary.push_str(&format!(": {} new, {} gone", added, gone.len()));
+                if entries.iter().any(|entry| entry.last_event.is_some()) {
+                    summary.push_str(&format!(", {} with new events", changed));
+                }
+            }
+            outln!("{}.", summary);
+            outln!();
+        }
+        for line in lines {
+            outln!("{}", line);
+        }
+        self.previous = Some(entries);
+        output::flush_output()
+    }
+
+    fn paint(&self, paint: fn(&str) -> String, line: String) -> String {
+        match self.color {
+            true => paint(&line),
+            false => line,
+        }
+    }
+}
+
+/// Formats the interval for the header, e.g. `500ms`, `2s` or `1.5s`.
+fn format_interval(interval: Duration) -> String {
+    match interval.as_millis() {
+        millis @ 0..=999 => format!("{}ms", millis),
+        _ if interval.subsec_nanos() == 0 => format!("{}s", interval.as_secs()),
+        _ => format!("{}s", interval.as_secs_f64()),
+    }
+}